@@ -393,7 +393,7 @@ mod tests {
     use halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner, Value},
         dev::{FailureLocation, MockProver, VerifyFailure},
-        plonk::{Circuit, ConstraintSystem, Error},
+        plonk::{Any, Circuit, ConstraintSystem, Error},
     };
     use halo2curves::{pasta::pallas, FieldExt};
 
@@ -588,6 +588,18 @@ mod tests {
                         region: (1, "Range check 6 bits").into(),
                         offset: 1,
                     },
+                    input: vec!["0x400".to_string()],
+                    cell_values: vec![
+                        (((Any::advice(), 0).into(), 0).into(), "0x400".to_string()),
+                        (
+                            ((Any::advice(), 0).into(), 1).into(),
+                            "0x3f00000000000000000000000000000021bd7e981927c5372ac87c294c000001"
+                                .to_string()
+                        ),
+                        (((Any::Fixed, 2).into(), 0).into(), "1".to_string()),
+                        (((Any::Fixed, 3).into(), 0).into(), "0".to_string()),
+                    ],
+                    nearest_table_rows: vec![(1023, vec!["0x3ff".to_string()])],
                 }])
             );
         }
@@ -609,6 +621,17 @@ mod tests {
                             region: (1, "Range check 6 bits").into(),
                             offset: 0,
                         },
+                        input: vec!["0x400".to_string()],
+                        cell_values: vec![
+                            (((Any::advice(), 0).into(), 0).into(), "0x400".to_string()),
+                            (
+                                ((Any::advice(), 0).into(), 1).into(),
+                                "0x4000".to_string()
+                            ),
+                            (((Any::Fixed, 2).into(), 0).into(), "1".to_string()),
+                            (((Any::Fixed, 3).into(), 0).into(), "0".to_string()),
+                        ],
+                        nearest_table_rows: vec![(1023, vec!["0x3ff".to_string()])],
                     },
                     VerifyFailure::Lookup {
                         name: "lookup",
@@ -617,6 +640,17 @@ mod tests {
                             region: (1, "Range check 6 bits").into(),
                             offset: 1,
                         },
+                        input: vec!["0x4000".to_string()],
+                        cell_values: vec![
+                            (((Any::advice(), 0).into(), 0).into(), "0x4000".to_string()),
+                            (
+                                ((Any::advice(), 0).into(), 1).into(),
+                                "0x3f00000000000000000000000000000021bd7e981927c5372ac87c294c000001".to_string()
+                            ),
+                            (((Any::Fixed, 2).into(), 0).into(), "1".to_string()),
+                            (((Any::Fixed, 3).into(), 0).into(), "0".to_string()),
+                        ],
+                        nearest_table_rows: vec![(1023, vec!["0x3ff".to_string()])],
                     },
                 ])
             );
@@ -647,6 +681,21 @@ mod tests {
                         region: (1, "Range check 6 bits").into(),
                         offset: 0,
                     },
+                    input: vec![
+                        "0x40000000000000000000000000000000224698fc094cf91b992d30ed0000004"
+                            .to_string()
+                    ],
+                    cell_values: vec![
+                        (
+                            ((Any::advice(), 0).into(), 0).into(),
+                            "0x40000000000000000000000000000000224698fc094cf91b992d30ed0000004"
+                                .to_string()
+                        ),
+                        (((Any::advice(), 0).into(), 1).into(), "0x3f".to_string()),
+                        (((Any::Fixed, 2).into(), 0).into(), "1".to_string()),
+                        (((Any::Fixed, 3).into(), 0).into(), "0".to_string()),
+                    ],
+                    nearest_table_rows: vec![(1023, vec!["0x3ff".to_string()])],
                 }])
             );
         }
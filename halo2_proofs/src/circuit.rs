@@ -8,6 +8,7 @@ use crate::{
     arithmetic::FieldExt,
     plonk::{
         Advice, Any, Assigned, Challenge, Column, Error, Fixed, Instance, Selector, TableColumn,
+        TaggedTable,
     },
 };
 
@@ -18,8 +19,16 @@ pub use value::Value;
 pub mod floor_planner;
 pub use floor_planner::single_pass::SimpleFloorPlanner;
 
+mod composition;
+pub use composition::{ExposedCells, SubCircuit};
+
 pub mod layouter;
 
+mod public_inputs;
+pub use public_inputs::{compute_instances, PublicInputs};
+
+pub mod rows;
+
 /// A chip implements a set of instructions that can be used by gadgets.
 ///
 /// The chip stores state that is required at circuit synthesis time in
@@ -461,6 +470,31 @@ pub trait Layouter<F: Field> {
         N: Fn() -> NR,
         NR: Into<String>;
 
+    /// Assign a [`TaggedTable`] into the next free rows of the table columns it shares
+    /// with the other tables in its tag column.
+    ///
+    /// Offsets within the closure are relative to the start of the table, and the
+    /// layouter assigns the table's tag to every row the closure assigns to. Unlike
+    /// [`Layouter::assign_table`], the table columns do not need to be filled
+    /// completely; every row that no table uses is zero.
+    ///
+    /// The default implementation returns [`Error::Synthesis`], for layouters that do
+    /// not support tagged tables.
+    fn assign_tagged_table<A, N, NR>(
+        &mut self,
+        name: N,
+        table: TaggedTable,
+        assignment: A,
+    ) -> Result<(), Error>
+    where
+        A: FnMut(Table<'_, F>) -> Result<(), Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        let _ = (name, table, assignment);
+        Err(Error::Synthesis)
+    }
+
     /// Constrains a [`Cell`] to equal an instance column's row value at an
     /// absolute position.
     fn constrain_instance(
@@ -475,6 +509,12 @@ pub trait Layouter<F: Field> {
     /// Returns `Value::unknown()` if the current synthesis phase is before the challenge can be queried.
     fn get_challenge(&self, challenge: Challenge) -> Value<F>;
 
+    /// Returns the number of rows that can be assigned, `n - (blinding_factors + 1)`, if
+    /// the circuit is being synthesized for a known `n`.
+    fn usable_rows(&self) -> Option<usize> {
+        None
+    }
+
     /// Gets the "root" of this assignment, bypassing the namespacing.
     ///
     /// Not intended for downstream consumption; use [`Layouter::namespace`] instead.
@@ -546,6 +586,20 @@ impl<'a, F: Field, L: Layouter<F> + 'a> Layouter<F> for NamespacedLayouter<'a, F
         self.0.assign_table(name, assignment)
     }
 
+    fn assign_tagged_table<A, N, NR>(
+        &mut self,
+        name: N,
+        table: TaggedTable,
+        assignment: A,
+    ) -> Result<(), Error>
+    where
+        A: FnMut(Table<'_, F>) -> Result<(), Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.0.assign_tagged_table(name, table, assignment)
+    }
+
     fn constrain_instance(
         &mut self,
         cell: Cell,
@@ -559,6 +613,10 @@ impl<'a, F: Field, L: Layouter<F> + 'a> Layouter<F> for NamespacedLayouter<'a, F
         self.0.get_challenge(challenge)
     }
 
+    fn usable_rows(&self) -> Option<usize> {
+        self.0.usable_rows()
+    }
+
     fn get_root(&mut self) -> &mut Self::Root {
         self.0.get_root()
     }
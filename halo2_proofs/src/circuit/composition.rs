@@ -0,0 +1,261 @@
+//! Composing circuits out of other circuits.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+use ff::Field;
+
+use super::{Cell, Layouter, Region, Table, Value};
+use crate::plonk::{Challenge, Circuit, Column, ConstraintSystem, Error, Instance, TaggedTable};
+
+/// A [`Circuit`] embedded as a sub-circuit of another circuit.
+///
+/// The sub-circuit is configured against the constraint system of the parent circuit,
+/// and synthesized with a namespace of the parent's layouter. Instead of being
+/// constrained to equal instance cells, the cells that the sub-circuit exposes with
+/// [`Layouter::constrain_instance`] are returned to the parent as [`ExposedCells`],
+/// which the parent can constrain as it needs to.
+///
+/// The sub-circuit's instance columns are allocated in the parent's constraint system,
+/// but the sub-circuit should not query them, or assign cells from them. The parent
+/// needs to provide a (possibly empty) instance column for each of them, unless they
+/// share the parent's instance columns.
+pub struct SubCircuit<F: Field, ConcreteCircuit: Circuit<F>> {
+    config: ConcreteCircuit::Config,
+    instance_columns: Vec<Column<Instance>>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field, ConcreteCircuit: Circuit<F>> Clone for SubCircuit<F, ConcreteCircuit> {
+    fn clone(&self) -> Self {
+        SubCircuit {
+            config: self.config.clone(),
+            instance_columns: self.instance_columns.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: Field, ConcreteCircuit: Circuit<F>> fmt::Debug for SubCircuit<F, ConcreteCircuit> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubCircuit")
+            .field("instance_columns", &self.instance_columns)
+            .finish()
+    }
+}
+
+impl<F: Field, ConcreteCircuit: Circuit<F>> SubCircuit<F, ConcreteCircuit> {
+    /// Configures the sub-circuit with its own columns.
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        Self::configure_inner(meta, false)
+    }
+
+    /// Configures the sub-circuit, allocating its advice and instance columns from the
+    /// columns that have already been allocated where possible. Fixed columns are
+    /// never shared, and neither are advice columns that an existing gate or lookup
+    /// queries without a selector or fixed column (such as an ungated range check).
+    ///
+    /// Regions of different sub-circuits never overlap in a shared column, but a gate
+    /// or lookup that is not multiplied by a selector constrains every cell of the
+    /// columns it queries. In particular, a lookup input such as
+    /// `query_advice(a, Rotation::cur())` would require every cell of a shared column
+    /// `a` to be in the table. Gates and lookups added after the sub-circuit is
+    /// configured must not query the columns it shares without a selector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the sub-circuit's gates or lookups queries a shared advice
+    /// column without a selector or fixed column.
+    pub fn configure_with_shared_columns(meta: &mut ConstraintSystem<F>) -> Self {
+        Self::configure_inner(meta, true)
+    }
+
+    fn configure_inner(meta: &mut ConstraintSystem<F>, share_columns: bool) -> Self {
+        let (config, instance_columns) =
+            meta.configure_sub_circuit::<ConcreteCircuit>(share_columns);
+        SubCircuit {
+            config,
+            instance_columns,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the configuration of the sub-circuit.
+    pub fn config(&self) -> &ConcreteCircuit::Config {
+        &self.config
+    }
+
+    /// Synthesizes `circuit` in a namespace of `layouter`, and returns the cells that it
+    /// exposes.
+    ///
+    /// The sub-circuit's regions are laid out by the parent circuit's floor planner,
+    /// along with the parent's own regions, and `ConcreteCircuit::FloorPlanner` is not
+    /// used: a floor planner lays out a whole circuit, not the regions of a layouter.
+    pub fn synthesize<L, N, NR>(
+        &self,
+        circuit: &ConcreteCircuit,
+        layouter: &mut L,
+        name: N,
+    ) -> Result<ExposedCells, Error>
+    where
+        L: Layouter<F>,
+        N: FnOnce() -> NR,
+        NR: Into<String>,
+    {
+        let mut exposed = ExposedCells::default();
+        let mut layouter = layouter.namespace(name);
+        circuit.synthesize(
+            self.config.clone(),
+            SubCircuitLayouter {
+                layouter: &mut layouter,
+                instance_columns: &self.instance_columns,
+                exposed: &mut exposed,
+                _marker: PhantomData,
+            },
+        )?;
+        Ok(exposed)
+    }
+}
+
+/// The cells that a sub-circuit exposes, indexed by the instance column and row that
+/// they would be constrained to if the sub-circuit were a standalone circuit.
+#[derive(Clone, Debug, Default)]
+pub struct ExposedCells {
+    cells: BTreeMap<(usize, usize), Cell>,
+}
+
+impl ExposedCells {
+    /// Returns the cell exposed at `row` of the sub-circuit's `column`th instance
+    /// column, if any.
+    pub fn cell(&self, column: usize, row: usize) -> Option<Cell> {
+        self.cells.get(&(column, row)).copied()
+    }
+
+    /// Returns the exposed cells, along with their instance column and row, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, Cell)> + '_ {
+        self.cells
+            .iter()
+            .map(|(&(column, row), &cell)| (column, row, cell))
+    }
+
+    /// Constrains the cell exposed at `row` of the sub-circuit's `column`th instance
+    /// column to equal `cell`, which can be a cell exposed by another sub-circuit.
+    ///
+    /// Returns [`Error::Synthesis`] if no cell is exposed there.
+    pub fn constrain_equal<F: Field>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        column: usize,
+        row: usize,
+        cell: Cell,
+    ) -> Result<(), Error> {
+        let exposed = self.cell(column, row).ok_or(Error::Synthesis)?;
+        layouter.assign_region(
+            || "link sub-circuit",
+            |mut region| region.constrain_equal(exposed, cell),
+        )
+    }
+}
+
+/// The layouter that a sub-circuit is synthesized with. It records the cells that the
+/// sub-circuit exposes instead of constraining them.
+struct SubCircuitLayouter<'a, F: Field, L: Layouter<F>> {
+    layouter: &'a mut L,
+    instance_columns: &'a [Column<Instance>],
+    exposed: &'a mut ExposedCells,
+    _marker: PhantomData<F>,
+}
+
+impl<'a, F: Field, L: Layouter<F>> Layouter<F> for SubCircuitLayouter<'a, F, L> {
+    // Namespaces within the sub-circuit need to go through this layouter, so that the
+    // cells they expose are recorded.
+    type Root = Self;
+
+    fn assign_region<A, AR, N, NR>(&mut self, name: N, assignment: A) -> Result<AR, Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.layouter.assign_region(name, assignment)
+    }
+
+    #[cfg(feature = "parallel_syn")]
+    fn assign_regions<A, AR, N, NR>(
+        &mut self,
+        name: N,
+        assignments: Vec<A>,
+    ) -> Result<Vec<AR>, Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error> + Send,
+        AR: Send,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.layouter.assign_regions(name, assignments)
+    }
+
+    fn assign_table<A, N, NR>(&mut self, name: N, assignment: A) -> Result<(), Error>
+    where
+        A: FnMut(Table<'_, F>) -> Result<(), Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.layouter.assign_table(name, assignment)
+    }
+
+    fn assign_tagged_table<A, N, NR>(
+        &mut self,
+        name: N,
+        table: TaggedTable,
+        assignment: A,
+    ) -> Result<(), Error>
+    where
+        A: FnMut(Table<'_, F>) -> Result<(), Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.layouter.assign_tagged_table(name, table, assignment)
+    }
+
+    fn constrain_instance(
+        &mut self,
+        cell: Cell,
+        column: Column<Instance>,
+        row: usize,
+    ) -> Result<(), Error> {
+        // The sub-circuit can only expose cells in the instance columns it allocated.
+        let column = self
+            .instance_columns
+            .iter()
+            .position(|c| *c == column)
+            .ok_or(Error::Synthesis)?;
+        self.exposed.cells.insert((column, row), cell);
+        Ok(())
+    }
+
+    fn get_challenge(&self, challenge: Challenge) -> Value<F> {
+        self.layouter.get_challenge(challenge)
+    }
+
+    fn usable_rows(&self) -> Option<usize> {
+        self.layouter.usable_rows()
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.layouter.get_root().push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.layouter.get_root().pop_namespace(gadget_name)
+    }
+}
@@ -1,5 +1,5 @@
 use std::cmp;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::marker::PhantomData;
 use std::ops::Range;
@@ -18,7 +18,7 @@ use crate::{
     multicore,
     plonk::{
         Advice, Any, Assigned, Assignment, Challenge, Circuit, Column, Error, Fixed, FloorPlanner,
-        Instance, Selector, TableColumn,
+        Instance, Selector, TableColumn, TaggedTable, TaggedTableError,
     },
 };
 
@@ -58,6 +58,8 @@ pub struct SingleChipLayouter<'a, F: Field, CS: Assignment<F> + 'a> {
     columns: HashMap<RegionColumn, usize>,
     /// Stores the table fixed columns.
     table_columns: Vec<TableColumn>,
+    /// Stores the table columns shared by the tables in each tag column.
+    tagged_tables: HashMap<TableColumn, TaggedColumns>,
     _marker: PhantomData<F>,
 }
 
@@ -79,6 +81,7 @@ impl<'a, F: Field, CS: Assignment<F> + 'a> SingleChipLayouter<'a, F, CS> {
             regions: vec![],
             columns: HashMap::default(),
             table_columns: vec![],
+            tagged_tables: HashMap::default(),
             _marker: PhantomData,
         };
         Ok(ret)
@@ -93,6 +96,7 @@ impl<'a, F: Field, CS: Assignment<F> + 'a> SingleChipLayouter<'a, F, CS> {
                 regions: self.regions.clone(),
                 columns: self.columns.clone(),
                 table_columns: self.table_columns.clone(),
+                tagged_tables: self.tagged_tables.clone(),
                 _marker: Default::default(),
             })
             .collect::<Vec<_>>())
@@ -423,6 +427,27 @@ impl<'a, F: Field, CS: Assignment<F> + 'a> Layouter<F> for SingleChipLayouter<'a
         Ok(())
     }
 
+    fn assign_tagged_table<A, N, NR>(
+        &mut self,
+        name: N,
+        table: TaggedTable,
+        assignment: A,
+    ) -> Result<(), Error>
+    where
+        A: FnMut(Table<'_, F>) -> Result<(), Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        assign_tagged_table(
+            self.cs,
+            &mut self.table_columns,
+            &mut self.tagged_tables,
+            name,
+            table,
+            assignment,
+        )
+    }
+
     fn constrain_instance(
         &mut self,
         cell: Cell,
@@ -441,6 +466,10 @@ impl<'a, F: Field, CS: Assignment<F> + 'a> Layouter<F> for SingleChipLayouter<'a
         self.cs.get_challenge(challenge)
     }
 
+    fn usable_rows(&self) -> Option<usize> {
+        self.cs.usable_rows()
+    }
+
     fn get_root(&mut self) -> &mut Self::Root {
         self
     }
@@ -687,6 +716,155 @@ impl<'r, 'a, F: Field, CS: Assignment<F> + 'a> TableLayouter<F>
     }
 }
 
+/// The table columns shared by the tables tagged in one tag column.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TaggedColumns {
+    /// The first row that no table uses. Row 0 is reserved for the row of zeroes that
+    /// disabled lookups match.
+    next_row: usize,
+    /// The table columns, including the tag column, that the tables use.
+    columns: HashSet<TableColumn>,
+}
+
+/// Assigns a tagged table into the next free rows of its tag column. Each table column
+/// is filled with zeroes when a tagged table first uses it, so that the rows that no
+/// table uses match disabled lookups.
+pub(crate) fn assign_tagged_table<F: Field, CS: Assignment<F>, A, N, NR>(
+    cs: &mut CS,
+    table_columns: &mut Vec<TableColumn>,
+    tagged_tables: &mut HashMap<TableColumn, TaggedColumns>,
+    name: N,
+    table: TaggedTable,
+    mut assignment: A,
+) -> Result<(), Error>
+where
+    A: FnMut(Table<'_, F>) -> Result<(), Error>,
+    N: Fn() -> NR,
+    NR: Into<String>,
+{
+    cs.enter_region(name);
+    let mut layouter = TaggedTableLayouter {
+        cs,
+        table_columns,
+        tagged_tables,
+        table,
+        start: 0,
+        rows: 0,
+        assigned: HashSet::default(),
+    };
+    layouter.claim_column::<F>(table.tag_column())?;
+    layouter.start = cmp::max(layouter.tagged_tables[&table.tag_column()].next_row, 1);
+    {
+        let layouter: &mut dyn TableLayouter<F> = &mut layouter;
+        assignment(layouter.into())
+    }?;
+
+    let tag = Value::known(Assigned::from(table.tag_value::<F>()));
+    for row in layouter.start..layouter.start + layouter.rows {
+        layouter
+            .cs
+            .assign_fixed(|| "tag", table.tag_column().inner(), row, || tag)?;
+    }
+    let next_row = layouter.start + layouter.rows;
+    layouter
+        .tagged_tables
+        .get_mut(&table.tag_column())
+        .unwrap()
+        .next_row = next_row;
+    cs.exit_region();
+
+    Ok(())
+}
+
+struct TaggedTableLayouter<'r, 'a, CS> {
+    cs: &'a mut CS,
+    table_columns: &'r mut Vec<TableColumn>,
+    tagged_tables: &'r mut HashMap<TableColumn, TaggedColumns>,
+    table: TaggedTable,
+    /// The row that this table starts at.
+    start: usize,
+    /// The number of rows that this table uses.
+    rows: usize,
+    assigned: HashSet<(TableColumn, usize)>,
+}
+
+impl<'r, 'a, CS> fmt::Debug for TaggedTableLayouter<'r, 'a, CS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaggedTableLayouter")
+            .field("table", &self.table)
+            .field("start", &self.start)
+            .field("rows", &self.rows)
+            .finish()
+    }
+}
+
+impl<'r, 'a, CS> TaggedTableLayouter<'r, 'a, CS> {
+    /// Makes `column` one of the columns shared by the tables in this table's tag
+    /// column, filling it with zeroes if no table has used it yet.
+    fn claim_column<F: Field>(&mut self, column: TableColumn) -> Result<(), Error>
+    where
+        CS: Assignment<F>,
+    {
+        let tag_column = self.table.tag_column();
+        match self
+            .tagged_tables
+            .iter()
+            .find(|(_, tagged)| tagged.columns.contains(&column))
+        {
+            Some((owner, _)) if *owner == tag_column => Ok(()),
+            Some(_) => Err(Error::InvalidTaggedTable(
+                TaggedTableError::ColumnInOtherTagColumn(column),
+            )),
+            None => {
+                if self.table_columns.contains(&column) {
+                    return Err(Error::InvalidTaggedTable(
+                        TaggedTableError::ColumnInUntaggedTable(column),
+                    ));
+                }
+                self.cs
+                    .fill_from_row(column.inner(), 0, Value::known(Assigned::Zero))?;
+                self.table_columns.push(column);
+                self.tagged_tables
+                    .entry(tag_column)
+                    .or_default()
+                    .columns
+                    .insert(column);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<'r, 'a, F: Field, CS: Assignment<F> + 'a> TableLayouter<F>
+    for TaggedTableLayouter<'r, 'a, CS>
+{
+    fn assign_cell<'v>(
+        &'v mut self,
+        annotation: &'v (dyn Fn() -> String + 'v),
+        column: TableColumn,
+        offset: usize,
+        to: &'v mut (dyn FnMut() -> Value<Assigned<F>> + 'v),
+    ) -> Result<(), Error> {
+        if column == self.table.tag_column() {
+            return Err(Error::InvalidTaggedTable(
+                TaggedTableError::TagColumnAssigned(column),
+            ));
+        }
+        if !self.assigned.insert((column, offset)) {
+            return Err(Error::InvalidTaggedTable(
+                TaggedTableError::CellAssignedTwice { column, offset },
+            ));
+        }
+        self.claim_column::<F>(column)?;
+
+        self.cs
+            .assign_fixed(annotation, column.inner(), self.start + offset, to)?;
+        self.rows = cmp::max(self.rows, offset + 1);
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use halo2curves::pasta::vesta;
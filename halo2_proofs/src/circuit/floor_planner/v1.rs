@@ -1,16 +1,17 @@
+use std::collections::HashMap;
 use std::fmt;
 
 use ff::Field;
 
 use crate::{
     circuit::{
-        floor_planner::single_pass::SimpleTableLayouter,
+        floor_planner::single_pass::{assign_tagged_table, SimpleTableLayouter, TaggedColumns},
         layouter::{RegionColumn, RegionLayouter, RegionShape, TableLayouter},
         Cell, Layouter, Region, RegionIndex, RegionStart, Table, Value,
     },
     plonk::{
         Advice, Any, Assigned, Assignment, Challenge, Circuit, Column, Error, Fixed, FloorPlanner,
-        Instance, Selector, TableColumn,
+        Instance, Selector, TableColumn, TaggedTable,
     },
 };
 
@@ -35,6 +36,8 @@ struct V1Plan<'a, F: Field, CS: Assignment<F> + 'a> {
     constants: Vec<(Assigned<F>, Cell)>,
     /// Stores the table fixed columns.
     table_columns: Vec<TableColumn>,
+    /// Stores the table columns shared by the tables in each tag column.
+    tagged_tables: HashMap<TableColumn, TaggedColumns>,
 }
 
 impl<'a, F: Field, CS: Assignment<F> + 'a> fmt::Debug for V1Plan<'a, F, CS> {
@@ -51,6 +54,7 @@ impl<'a, F: Field, CS: Assignment<F>> V1Plan<'a, F, CS> {
             regions: vec![],
             constants: vec![],
             table_columns: vec![],
+            tagged_tables: HashMap::default(),
         };
         Ok(ret)
     }
@@ -66,7 +70,7 @@ impl FloorPlanner for V1 {
         let mut plan = V1Plan::new(cs)?;
 
         // First pass: measure the regions within the circuit.
-        let mut measure = MeasurementPass::new();
+        let mut measure = MeasurementPass::new(plan.cs.usable_rows());
         {
             let pass = &mut measure;
             circuit
@@ -210,6 +214,30 @@ impl<'p, 'a, F: Field, CS: Assignment<F> + 'a> Layouter<F> for V1Pass<'p, 'a, F,
         }
     }
 
+    fn assign_tagged_table<A, N, NR>(
+        &mut self,
+        name: N,
+        table: TaggedTable,
+        assignment: A,
+    ) -> Result<(), Error>
+    where
+        A: FnMut(Table<'_, F>) -> Result<(), Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        match &mut self.0 {
+            Pass::Measurement(_) => Ok(()),
+            Pass::Assignment(pass) => assign_tagged_table(
+                pass.plan.cs,
+                &mut pass.plan.table_columns,
+                &mut pass.plan.tagged_tables,
+                name,
+                table,
+                assignment,
+            ),
+        }
+    }
+
     fn constrain_instance(
         &mut self,
         cell: Cell,
@@ -229,6 +257,15 @@ impl<'p, 'a, F: Field, CS: Assignment<F> + 'a> Layouter<F> for V1Pass<'p, 'a, F,
         }
     }
 
+    fn usable_rows(&self) -> Option<usize> {
+        // Regions that depend on the number of rows need to have the same shape in
+        // both passes.
+        match &self.0 {
+            Pass::Measurement(pass) => pass.usable_rows,
+            Pass::Assignment(pass) => pass.plan.cs.usable_rows(),
+        }
+    }
+
     fn get_root(&mut self) -> &mut Self::Root {
         self
     }
@@ -254,11 +291,15 @@ impl<'p, 'a, F: Field, CS: Assignment<F> + 'a> Layouter<F> for V1Pass<'p, 'a, F,
 #[derive(Debug)]
 pub struct MeasurementPass {
     regions: Vec<(String, RegionShape)>,
+    usable_rows: Option<usize>,
 }
 
 impl MeasurementPass {
-    fn new() -> Self {
-        MeasurementPass { regions: vec![] }
+    fn new(usable_rows: Option<usize>) -> Self {
+        MeasurementPass {
+            regions: vec![],
+            usable_rows,
+        }
     }
 
     fn assign_region<F: Field, A, AR, N, NR>(
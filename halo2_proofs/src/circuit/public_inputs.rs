@@ -0,0 +1,336 @@
+//! Helpers for exposing cells as public inputs.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use ff::Field;
+
+use super::{Cell, Layouter, Value};
+use crate::plonk::{
+    Advice, Any, Assigned, Assignment, Challenge, Circuit, Column, ConstraintSystem, Error, Fixed,
+    FloorPlanner, Instance, Selector,
+};
+
+/// Allocates the rows of an instance column to the cells that a circuit exposes as
+/// public inputs.
+///
+/// A circuit creates a `PublicInputs` at the start of [`Circuit::synthesize`] and
+/// passes it to the chips that expose cells. Rows are allocated in the order in which
+/// cells are exposed, so the instance column can be computed from the circuit with
+/// [`compute_instances`].
+///
+/// A `PublicInputs` does not know about rows constrained with
+/// [`Layouter::constrain_instance`], or allocated by another `PublicInputs` on the same
+/// column. Use [`PublicInputs::with_offset`] to allocate rows after those;
+/// [`compute_instances`] returns [`Error::InstanceRowConflict`] if two different cells
+/// end up constrained to the same row.
+///
+/// ```
+/// use halo2_proofs::{
+///     circuit::{compute_instances, Layouter, PublicInputs, SimpleFloorPlanner, Value},
+///     plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+/// };
+/// use halo2curves::pasta::Fp;
+///
+/// struct MyCircuit {
+///     a: Value<Fp>,
+/// }
+///
+/// impl Circuit<Fp> for MyCircuit {
+///     type Config = (Column<Advice>, Column<Instance>);
+///     type FloorPlanner = SimpleFloorPlanner;
+///
+///     fn without_witnesses(&self) -> Self {
+///         MyCircuit { a: Value::unknown() }
+///     }
+///
+///     fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+///         let advice = meta.advice_column();
+///         let instance = meta.instance_column();
+///         meta.enable_equality(advice);
+///         meta.enable_equality(instance);
+///         (advice, instance)
+///     }
+///
+///     fn synthesize(
+///         &self,
+///         (advice, instance): Self::Config,
+///         mut layouter: impl Layouter<Fp>,
+///     ) -> Result<(), Error> {
+///         let mut public_inputs = PublicInputs::new(instance);
+///         let cells = layouter.assign_region(
+///             || "values",
+///             |mut region| {
+///                 let a = region.assign_advice(|| "a", advice, 0, || self.a)?;
+///                 let b = region.assign_advice(|| "b", advice, 1, || self.a.map(|a| a + a))?;
+///                 Ok([a.cell(), b.cell()])
+///             },
+///         )?;
+///         public_inputs.expose(&mut layouter, cells[1], || "b")?;
+///         public_inputs.expose(&mut layouter, cells[0], || "a")?;
+///         Ok(())
+///     }
+/// }
+///
+/// let circuit = MyCircuit { a: Value::known(Fp::from(3)) };
+/// assert_eq!(
+///     compute_instances(&circuit).unwrap(),
+///     vec![vec![Fp::from(6), Fp::from(3)]]
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct PublicInputs {
+    column: Column<Instance>,
+    offset: usize,
+    labels: Vec<String>,
+}
+
+impl PublicInputs {
+    /// Allocates public inputs from the first row of `column`, which must be
+    /// equality-enabled.
+    pub fn new(column: Column<Instance>) -> Self {
+        Self::with_offset(column, 0)
+    }
+
+    /// Allocates public inputs from row `offset` of `column`, which must be
+    /// equality-enabled.
+    pub fn with_offset(column: Column<Instance>, offset: usize) -> Self {
+        PublicInputs {
+            column,
+            offset,
+            labels: vec![],
+        }
+    }
+
+    /// Returns the instance column that the public inputs are allocated in.
+    pub fn column(&self) -> Column<Instance> {
+        self.column
+    }
+
+    /// Returns the row from which the public inputs are allocated.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the labels of the public inputs exposed so far, indexed by their rows
+    /// relative to [`PublicInputs::offset`].
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Constrains `cell` to equal the next unallocated row of the instance column, and
+    /// returns that row.
+    pub fn expose<F: Field, A, AR>(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        cell: Cell,
+        label: A,
+    ) -> Result<usize, Error>
+    where
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        let row = self.offset + self.labels.len();
+        layouter
+            .namespace(|| label())
+            .constrain_instance(cell, self.column, row)?;
+        self.labels.push(label().into());
+        Ok(row)
+    }
+}
+
+/// Computes the instance columns of `circuit` from its witness, which can then be
+/// passed to `create_proof`, `verify_proof` or [`MockProver::run`].
+///
+/// Every row of an instance column that a cell is constrained to equal, with
+/// [`Layouter::constrain_instance`] or [`PublicInputs::expose`], takes the value of
+/// that cell. Each instance column ends at the last such row, and its other rows are
+/// zero. Returns [`Error::Synthesis`] if the value of such a cell is unknown, and
+/// [`Error::InstanceRowConflict`] if two different cells are constrained to equal the
+/// same row.
+///
+/// [`MockProver::run`]: crate::dev::MockProver::run
+pub fn compute_instances<F: Field, ConcreteCircuit: Circuit<F>>(
+    circuit: &ConcreteCircuit,
+) -> Result<Vec<Vec<F>>, Error> {
+    let mut cs = ConstraintSystem::default();
+    let config = ConcreteCircuit::configure(&mut cs);
+
+    let mut collector = InstanceCollector::default();
+    ConcreteCircuit::FloorPlanner::synthesize(
+        &mut collector,
+        circuit,
+        config,
+        cs.constants.clone(),
+    )?;
+
+    let mut instances = vec![vec![]; cs.num_instance_columns];
+    let mut sources = HashMap::new();
+    for (instance, cell) in collector.instance_copies {
+        if *sources.entry(instance).or_insert(cell) != cell {
+            return Err(Error::InstanceRowConflict {
+                column: instance.0,
+                row: instance.1,
+            });
+        }
+        let value = collector
+            .values
+            .get(&cell)
+            .copied()
+            .unwrap_or_else(Value::unknown)
+            .assign()?;
+        let column = &mut instances[instance.0.index()];
+        if column.len() <= instance.1 {
+            column.resize(instance.1 + 1, F::zero());
+        }
+        column[instance.1] = value;
+    }
+
+    Ok(instances)
+}
+
+/// An [`Assignment`] that records the values of the cells that are copied to instance
+/// columns.
+struct InstanceCollector<F> {
+    values: HashMap<(Column<Any>, usize), Value<F>>,
+    /// The instance cells, and the cells they are copied from.
+    instance_copies: Vec<((Column<Instance>, usize), (Column<Any>, usize))>,
+}
+
+impl<F> Default for InstanceCollector<F> {
+    fn default() -> Self {
+        InstanceCollector {
+            values: HashMap::new(),
+            instance_copies: vec![],
+        }
+    }
+}
+
+impl<F: Field> Assignment<F> for InstanceCollector<F> {
+    fn enter_region<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we only care about the values of cells.
+    }
+
+    fn exit_region(&mut self) {
+        // Do nothing; we only care about the values of cells.
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, _: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        Ok(())
+    }
+
+    fn fork(&mut self, ranges: &[Range<usize>]) -> Result<Vec<Self>, Error> {
+        Ok(ranges
+            .iter()
+            .map(|_| InstanceCollector::default())
+            .collect())
+    }
+
+    fn merge(&mut self, sub_cs: Vec<Self>) -> Result<(), Error> {
+        for sub_cs in sub_cs {
+            self.values.extend(sub_cs.values);
+            self.instance_copies.extend(sub_cs.instance_copies);
+        }
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<F>, Error> {
+        // The instance columns are what we are computing.
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let value = to().map(|v| v.into().evaluate());
+        self.values.insert((column.into(), row), value);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let value = to().map(|v| v.into().evaluate());
+        self.values.insert((column.into(), row), value);
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        let left = (left_column, left_row);
+        let right = (right_column, right_row);
+        for (instance, cell) in [(left, right), (right, left)] {
+            if let Ok(column) = Column::<Instance>::try_from(instance.0) {
+                self.instance_copies.push(((column, instance.1), cell));
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        // Lookup tables are never copied to instance columns.
+        Ok(())
+    }
+
+    fn get_challenge(&self, _: Challenge) -> Value<F> {
+        Value::unknown()
+    }
+
+    fn annotate_column<A, AR>(&mut self, _annotation: A, _column: Column<Any>)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // Do nothing
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+}
@@ -0,0 +1,596 @@
+//! A layouter that packs gate rows into groups of advice columns.
+//!
+//! Instead of designing a region for each chip, chips emit rows of values with a
+//! [`RowLayouter`], each of which can enable one of the gates configured in a
+//! [`RowConfig`]. The rows are then placed in a single region, one after another down
+//! the first group of advice columns, wrapping to the next group when one fills up. A
+//! group fills up at the last usable row of the circuit, `n - (blinding_factors + 1)`,
+//! which the layouter gets from the backend that the circuit is synthesized with.
+
+use std::cmp;
+use std::fmt;
+use std::marker::PhantomData;
+
+use ff::Field;
+
+use super::{AssignedCell, Layouter, Value};
+use crate::{
+    plonk::{
+        Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector, VirtualCells,
+    },
+    poly::Rotation,
+};
+
+/// The columns and gates that rows are placed in.
+#[derive(Clone, Debug)]
+pub struct RowConfig<F: Field> {
+    width: usize,
+    groups: Vec<Vec<Column<Advice>>>,
+    gates: Vec<GateConfig>,
+    _marker: PhantomData<F>,
+}
+
+#[derive(Clone, Debug)]
+struct GateConfig {
+    /// The selector that enables the gate in each group of columns.
+    selectors: Vec<Selector>,
+    /// Whether the gate queries the row after the one it is enabled in.
+    uses_next: bool,
+}
+
+/// A gate that rows can enable, created with [`RowConfig::create_gate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RowGate(usize);
+
+/// The cells of a row that a gate's constraints can query.
+#[derive(Debug)]
+pub struct RowQueries<'v, 'a, F: Field> {
+    cells: &'v mut VirtualCells<'a, F>,
+    columns: &'v [Column<Advice>],
+    uses_next: bool,
+}
+
+impl<'v, 'a, F: Field> RowQueries<'v, 'a, F> {
+    /// Queries the cell in `lane` of the row that the gate is enabled in.
+    pub fn cur(&mut self, lane: usize) -> Expression<F> {
+        self.cells.query_advice(self.columns[lane], Rotation::cur())
+    }
+
+    /// Queries the cell in `lane` of the row after the one that the gate is enabled in.
+    /// The row layouter keeps the two rows in the same group of columns.
+    pub fn next(&mut self, lane: usize) -> Expression<F> {
+        self.uses_next = true;
+        self.cells
+            .query_advice(self.columns[lane], Rotation::next())
+    }
+}
+
+impl<F: Field> RowConfig<F> {
+    /// Allocates `groups` groups of `width` equality-enabled advice columns.
+    pub fn configure(meta: &mut ConstraintSystem<F>, width: usize, groups: usize) -> Self {
+        assert!(width > 0 && groups > 0, "Rows need at least one column");
+        let groups = (0..groups)
+            .map(|_| {
+                (0..width)
+                    .map(|_| {
+                        let column = meta.advice_column();
+                        meta.enable_equality(column);
+                        column
+                    })
+                    .collect()
+            })
+            .collect();
+
+        RowConfig {
+            width,
+            groups,
+            gates: vec![],
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the number of cells in each row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Creates a gate that rows can enable. `constraints` queries the cells of the row
+    /// that enables the gate, and of the row after it, and is called once for each
+    /// group of columns.
+    pub fn create_gate(
+        &mut self,
+        meta: &mut ConstraintSystem<F>,
+        name: &'static str,
+        constraints: impl Fn(&mut RowQueries<'_, '_, F>) -> Vec<Expression<F>>,
+    ) -> RowGate {
+        let mut selectors = vec![];
+        let mut uses_next = false;
+        for columns in &self.groups {
+            let selector = meta.selector();
+            meta.create_gate(name, |meta| {
+                let mut queries = RowQueries {
+                    cells: &mut *meta,
+                    columns,
+                    uses_next: false,
+                };
+                let polys = constraints(&mut queries);
+                uses_next = queries.uses_next;
+                Constraints::with_selector(meta.query_selector(selector), polys)
+            });
+            selectors.push(selector);
+        }
+
+        self.gates.push(GateConfig {
+            selectors,
+            uses_next,
+        });
+        RowGate(self.gates.len() - 1)
+    }
+}
+
+/// A cell of a row emitted by a [`RowLayouter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RowCell {
+    row: usize,
+    lane: usize,
+}
+
+/// The value of a cell in a row.
+#[derive(Clone, Copy, Debug)]
+pub enum RowValue<F> {
+    /// A witness value.
+    Witness(Value<F>),
+    /// A copy of the value of a cell in an earlier row, which the cell is constrained
+    /// to equal.
+    Copy(RowCell),
+}
+
+impl<F> From<Value<F>> for RowValue<F> {
+    fn from(value: Value<F>) -> Self {
+        RowValue::Witness(value)
+    }
+}
+
+impl<F> From<RowCell> for RowValue<F> {
+    fn from(cell: RowCell) -> Self {
+        RowValue::Copy(cell)
+    }
+}
+
+#[derive(Debug)]
+struct Row<F> {
+    gate: Option<RowGate>,
+    values: Vec<Value<F>>,
+    copies: Vec<Option<RowCell>>,
+}
+
+/// Where a row is placed, as a group of columns and an offset within the region.
+#[derive(Clone, Copy, Debug)]
+struct Placement {
+    at: (usize, usize),
+    /// A copy of the row at the start of the next group of columns, in which its gate
+    /// is enabled instead, because the row after it did not fit in its group.
+    duplicate: Option<(usize, usize)>,
+}
+
+/// Collects the rows of a circuit, and places them with [`RowLayouter::assign`].
+///
+/// ```
+/// use halo2_proofs::{
+///     circuit::{
+///         rows::{RowConfig, RowGate, RowLayouter, RowValue},
+///         Layouter, SimpleFloorPlanner, Value,
+///     },
+///     dev::MockProver,
+///     plonk::{Circuit, ConstraintSystem, Error},
+/// };
+/// use halo2curves::pasta::Fp;
+///
+/// #[derive(Clone)]
+/// struct Config {
+///     rows: RowConfig<Fp>,
+///     mul_add: RowGate,
+/// }
+///
+/// struct MyCircuit;
+///
+/// impl Circuit<Fp> for MyCircuit {
+///     type Config = Config;
+///     type FloorPlanner = SimpleFloorPlanner;
+///
+///     fn without_witnesses(&self) -> Self {
+///         MyCircuit
+///     }
+///
+///     fn configure(meta: &mut ConstraintSystem<Fp>) -> Config {
+///         let mut rows = RowConfig::configure(meta, 4, 2);
+///         // d = a * b + c
+///         let mul_add = rows.create_gate(meta, "mul add", |row| {
+///             vec![row.cur(0) * row.cur(1) + row.cur(2) - row.cur(3)]
+///         });
+///         Config { rows, mul_add }
+///     }
+///
+///     fn synthesize(&self, config: Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+///         let mut rows = RowLayouter::new(&config.rows);
+///         let known = |v: u64| -> RowValue<Fp> { Value::known(Fp::from(v)).into() };
+///         // 2 * 3 + 4 = 10, then 10 * 10 + 0 = 100.
+///         let first = rows.gate_row(config.mul_add, [known(2), known(3), known(4), known(10)]);
+///         rows.gate_row(
+///             config.mul_add,
+///             [first[3].into(), first[3].into(), known(0), known(100)],
+///         );
+///         let assigned = rows.assign(&mut layouter)?;
+///         assert_eq!(assigned.utilization().rows, 2);
+///         Ok(())
+///     }
+/// }
+///
+/// let prover = MockProver::run(4, &MyCircuit, vec![]).unwrap();
+/// assert_eq!(prover.verify(), Ok(()));
+/// ```
+#[derive(Debug)]
+pub struct RowLayouter<'c, F: Field> {
+    config: &'c RowConfig<F>,
+    rows: Vec<Row<F>>,
+}
+
+impl<'c, F: Field> RowLayouter<'c, F> {
+    /// Creates a layouter with no rows.
+    pub fn new(config: &'c RowConfig<F>) -> Self {
+        RowLayouter {
+            config,
+            rows: vec![],
+        }
+    }
+
+    /// Emits a row that does not enable a gate, and returns its cells. Lanes without a
+    /// value are zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more values than lanes.
+    pub fn row(&mut self, values: impl IntoIterator<Item = RowValue<F>>) -> Vec<RowCell> {
+        self.push(None, values)
+    }
+
+    /// Emits a row that enables `gate`, and returns its cells. Lanes without a value are
+    /// zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more values than lanes.
+    pub fn gate_row(
+        &mut self,
+        gate: RowGate,
+        values: impl IntoIterator<Item = RowValue<F>>,
+    ) -> Vec<RowCell> {
+        self.push(Some(gate), values)
+    }
+
+    fn push(
+        &mut self,
+        gate: Option<RowGate>,
+        values: impl IntoIterator<Item = RowValue<F>>,
+    ) -> Vec<RowCell> {
+        let mut row = Row {
+            gate,
+            values: vec![],
+            copies: vec![],
+        };
+        for value in values {
+            let (value, copy) = match value {
+                RowValue::Witness(value) => (value, None),
+                RowValue::Copy(cell) => (self.value(cell), Some(cell)),
+            };
+            row.values.push(value);
+            row.copies.push(copy);
+        }
+        assert!(
+            row.values.len() <= self.config.width,
+            "A row has {} values, but only {} lanes",
+            row.values.len(),
+            self.config.width
+        );
+        row.values
+            .resize(self.config.width, Value::known(F::zero()));
+        row.copies.resize(self.config.width, None);
+
+        let index = self.rows.len();
+        self.rows.push(row);
+        (0..self.config.width)
+            .map(|lane| RowCell { row: index, lane })
+            .collect()
+    }
+
+    /// Returns the value of `cell`.
+    pub fn value(&self, cell: RowCell) -> Value<F> {
+        self.rows[cell.row].values[cell.lane]
+    }
+
+    /// Returns the number of rows emitted so far.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Returns whether no rows have been emitted yet.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Places the rows in groups of at most `rows_per_group` rows.
+    ///
+    /// A row whose gate queries the next row is kept in the same group as the next row.
+    /// If it is the last row of a group and the next row of another gate, it is copied
+    /// to the start of the next group, and its gate is enabled there.
+    fn place(&self, rows_per_group: usize) -> Result<(Vec<Placement>, RowUtilization), Error> {
+        assert!(rows_per_group >= 2, "Groups must have at least two rows");
+        let last = rows_per_group - 1;
+
+        let mut placements = Vec::with_capacity(self.rows.len());
+        let (mut group, mut offset) = (0, 0);
+        let mut duplicated_rows = 0;
+        let mut prev_uses_next = false;
+        for row in &self.rows {
+            let uses_next = row
+                .gate
+                .map_or(false, |gate| self.config.gates[gate.0].uses_next);
+
+            if offset == rows_per_group || (offset == last && uses_next && !prev_uses_next) {
+                group += 1;
+                offset = 0;
+            }
+            let at = (group, offset);
+            let duplicate = if offset == last && uses_next {
+                group += 1;
+                offset = 0;
+                duplicated_rows += 1;
+                Some((group, offset))
+            } else {
+                None
+            };
+            offset += 1;
+
+            if group >= self.config.groups.len() {
+                return Err(Error::Synthesis);
+            }
+            placements.push(Placement { at, duplicate });
+            prev_uses_next = uses_next;
+        }
+
+        let utilization = RowUtilization {
+            rows: self.rows.len(),
+            duplicated_rows,
+            groups_used: if self.rows.is_empty() { 0 } else { group + 1 },
+            groups: self.config.groups.len(),
+            rows_per_group,
+        };
+        Ok((placements, utilization))
+    }
+
+    /// Assigns the rows in a region of `layouter`, in groups of as many rows as the
+    /// circuit can use, and returns the assigned cells.
+    ///
+    /// The region needs the columns of the [`RowConfig`] to itself, so that it starts at
+    /// the first row. If `layouter` does not know the number of usable rows, as when it
+    /// only measures the circuit, the rows are all placed in the first group.
+    ///
+    /// Returns [`Error::Synthesis`] if the rows do not fit in the groups of columns.
+    pub fn assign(&self, layouter: &mut impl Layouter<F>) -> Result<AssignedRows<F>, Error> {
+        let rows_per_group = layouter
+            .usable_rows()
+            .unwrap_or_else(|| cmp::max(self.rows.len() + 1, 2));
+        let (placements, utilization) = self.place(rows_per_group)?;
+
+        let cells = layouter.assign_region(
+            || "rows",
+            |mut region| {
+                let mut cells: Vec<Vec<AssignedCell<F, F>>> = Vec::with_capacity(self.rows.len());
+                for (row, placement) in self.rows.iter().zip(placements.iter()) {
+                    let mut assign_at = |(group, offset): (usize, usize)| {
+                        self.config.groups[group]
+                            .iter()
+                            .zip(row.values.iter())
+                            .map(|(column, value)| {
+                                region.assign_advice(|| "row", *column, offset, || *value)
+                            })
+                            .collect::<Result<Vec<_>, _>>()
+                    };
+
+                    let assigned = assign_at(placement.at)?;
+                    let enabled_at = match placement.duplicate {
+                        Some(duplicate) => {
+                            let copy = assign_at(duplicate)?;
+                            for (cell, copy) in assigned.iter().zip(copy.iter()) {
+                                region.constrain_equal(cell.cell(), copy.cell())?;
+                            }
+                            duplicate
+                        }
+                        None => placement.at,
+                    };
+                    if let Some(gate) = row.gate {
+                        let (group, offset) = enabled_at;
+                        self.config.gates[gate.0].selectors[group].enable(&mut region, offset)?;
+                    }
+
+                    for (cell, copy) in assigned.iter().zip(row.copies.iter()) {
+                        if let Some(source) = copy {
+                            region.constrain_equal(
+                                cells[source.row][source.lane].cell(),
+                                cell.cell(),
+                            )?;
+                        }
+                    }
+                    cells.push(assigned);
+                }
+                Ok(cells)
+            },
+        )?;
+
+        Ok(AssignedRows { cells, utilization })
+    }
+}
+
+/// The rows assigned by [`RowLayouter::assign`].
+#[derive(Clone, Debug)]
+pub struct AssignedRows<F: Field> {
+    cells: Vec<Vec<AssignedCell<F, F>>>,
+    utilization: RowUtilization,
+}
+
+impl<F: Field> AssignedRows<F> {
+    /// Returns the assigned cell of `cell`. If its row was copied to the next group of
+    /// columns, this is the original cell.
+    pub fn cell(&self, cell: RowCell) -> &AssignedCell<F, F> {
+        &self.cells[cell.row][cell.lane]
+    }
+
+    /// Returns how much of the columns the rows use.
+    pub fn utilization(&self) -> &RowUtilization {
+        &self.utilization
+    }
+}
+
+/// How much of the groups of columns the rows use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RowUtilization {
+    /// The number of rows emitted.
+    pub rows: usize,
+    /// The number of rows that were copied to the start of the next group of columns.
+    pub duplicated_rows: usize,
+    /// The number of groups of columns that contain rows.
+    pub groups_used: usize,
+    /// The number of groups of columns.
+    pub groups: usize,
+    /// The maximum number of rows in each group of columns.
+    pub rows_per_group: usize,
+}
+
+impl RowUtilization {
+    /// Returns the fraction of the rows of all groups of columns that contain rows,
+    /// including duplicated ones.
+    pub fn ratio(&self) -> f64 {
+        (self.rows + self.duplicated_rows) as f64 / (self.groups * self.rows_per_group) as f64
+    }
+}
+
+impl fmt::Display for RowUtilization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} rows ({} duplicated) in {} of {} column groups of {} rows: {:.1}% utilization",
+            self.rows,
+            self.duplicated_rows,
+            self.groups_used,
+            self.groups,
+            self.rows_per_group,
+            self.ratio() * 100.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2curves::pasta::Fp;
+
+    use super::*;
+    use crate::{circuit::SimpleFloorPlanner, dev::MockProver, plonk::Circuit};
+
+    #[derive(Clone)]
+    struct SumConfig {
+        rows: RowConfig<Fp>,
+        accumulate: RowGate,
+    }
+
+    /// Sums `values` with a chain of rows, each of which adds a value to the running
+    /// sum in the row before.
+    struct SumCircuit {
+        values: Vec<u64>,
+        sum: u64,
+    }
+
+    impl Circuit<Fp> for SumCircuit {
+        type Config = SumConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            SumCircuit {
+                values: self.values.clone(),
+                sum: self.sum,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let mut rows = RowConfig::configure(meta, 2, 3);
+            let accumulate = rows.create_gate(meta, "accumulate", |row| {
+                vec![row.cur(0) + row.cur(1) - row.next(0)]
+            });
+            SumConfig { rows, accumulate }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let mut rows = RowLayouter::new(&config.rows);
+            let mut sum = Value::known(Fp::zero());
+            for value in &self.values {
+                let value = Value::known(Fp::from(*value));
+                rows.gate_row(config.accumulate, [sum.into(), value.into()]);
+                sum = sum + value;
+            }
+            rows.row([Value::known(Fp::from(self.sum)).into()]);
+
+            let assigned = rows.assign(&mut layouter)?;
+            assert_eq!(
+                *assigned.utilization(),
+                RowUtilization {
+                    rows: 13,
+                    duplicated_rows: 1,
+                    groups_used: 2,
+                    groups: 3,
+                    // 16 rows, less 5 blinding factors and the last row.
+                    rows_per_group: 10,
+                }
+            );
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn rows_wrap_across_groups() {
+        const K: u32 = 4;
+
+        // The tenth row is copied to the second group, so that it is next to the
+        // eleventh.
+        let circuit = SumCircuit {
+            values: (1..=12).collect(),
+            sum: 78,
+        };
+        let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let circuit = SumCircuit {
+            values: (1..=12).collect(),
+            sum: 77,
+        };
+        let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn utilization() {
+        let utilization = RowUtilization {
+            rows: 7,
+            duplicated_rows: 1,
+            groups_used: 2,
+            groups: 2,
+            rows_per_group: 4,
+        };
+        assert_eq!(
+            utilization.to_string(),
+            "7 rows (1 duplicated) in 2 of 2 column groups of 4 rows: 100.0% utilization"
+        );
+    }
+}
@@ -1,10 +1,12 @@
 //! Tools for developing circuits.
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt;
 use std::iter;
 use std::ops::{Add, DerefMut, Mul, Neg, Range};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -37,14 +39,25 @@ use metadata::Column as ColumnMetadata;
 mod util;
 
 mod failure;
-pub use failure::{FailureLocation, VerifyFailure};
+pub use failure::{FailureLocation, PhaseViolationKind, VerifyFailure};
 
 pub mod cost;
-pub use cost::CircuitCost;
+pub use cost::{minimum_k, CircuitCost};
 
 mod gates;
 pub use gates::CircuitGates;
 
+mod html;
+pub use html::HtmlLayout;
+
+mod json;
+
+mod lint;
+pub use lint::{lint, Lint};
+
+mod mutation;
+pub use mutation::{MutationReport, UnconstrainedCells};
+
 use crate::two_dim_vec_to_vec_of_slice;
 
 #[cfg(feature = "dev-graph")]
@@ -62,6 +75,8 @@ pub use crate::circuit::value_dev::unwrap_value;
 struct Region {
     /// The name of the region. Not required to be unique.
     name: String,
+    /// The namespaces that were active when this region was entered, outermost first.
+    namespace: Vec<String>,
     /// The columns involved in this region.
     columns: HashSet<Column<Any>>,
     /// The rows that this region starts and ends on, if known.
@@ -94,6 +109,17 @@ impl Region {
         }
         self.rows = Some((start, end));
     }
+
+    /// Returns the metadata describing this region, which was the `index`-th to be
+    /// assigned.
+    fn metadata(&self, index: usize) -> metadata::Region {
+        metadata::Region {
+            index,
+            name: self.name.clone(),
+            column_annotations: Some(self.annotations.clone()),
+            namespace: self.namespace.clone(),
+        }
+    }
 }
 
 /// The value of a particular cell within the circuit.
@@ -303,6 +329,8 @@ pub struct MockProver<'a, F: Group + Field> {
     /// The current region being assigned to. Will be `None` after the circuit has been
     /// synthesized.
     current_region: Option<Region>,
+    /// The stack of namespaces entered via `Layouter::namespace`, outermost first.
+    namespace: Vec<String>,
 
     // The fixed cells in the circuit, arranged as [column][row].
     fixed_vec: Arc<Vec<Vec<CellValue<F>>>>,
@@ -310,8 +338,21 @@ pub struct MockProver<'a, F: Group + Field> {
     // The advice cells in the circuit, arranged as [column][row].
     pub(crate) advice_vec: Arc<Vec<Vec<CellValue<F>>>>,
     pub(crate) advice: Vec<&'a mut [CellValue<F>]>,
-    // This field is used only if the "phase_check" feature is turned on.
-    advice_prev: Vec<Vec<CellValue<F>>>,
+    // Advice cells that were assigned in violation of their column's phase, along with
+    // the phase in which they were assigned, as (column, row, phase, kind).
+    phase_violations: Vec<(
+        Column<Advice>,
+        usize,
+        crate::plonk::sealed::Phase,
+        PhaseViolationKind,
+    )>,
+    // The last value assigned in the current phase to each cell of an earlier phase's
+    // column, keyed by (column index, row). It must match the value the cell was
+    // assigned in its own phase.
+    reassigned: HashMap<(usize, usize), (Column<Advice>, F)>,
+    // Whether a challenge that is not yet available was queried since the last region
+    // was exited.
+    unavailable_challenge_queried: AtomicBool,
     // The instance cells in the circuit, arranged as [column][row].
     instance: Vec<Vec<F>>,
 
@@ -319,6 +360,8 @@ pub struct MockProver<'a, F: Group + Field> {
     selectors: Vec<&'a mut [bool]>,
 
     challenges: Vec<F>,
+    // The `theta`, `beta` and `gamma` challenges that custom arguments use.
+    products: (F, F, F),
 
     /// For mock prover which is generated from `fork()`, this field is None.
     permutation: Option<permutation::keygen::Assembly>,
@@ -340,6 +383,7 @@ impl<'a, F: Field + Group> Assignment<F> for MockProver<'a, F> {
         assert!(self.current_region.is_none());
         self.current_region = Some(Region {
             name: name().into(),
+            namespace: self.namespace.clone(),
             columns: HashSet::default(),
             rows: None,
             annotations: HashMap::default(),
@@ -351,6 +395,9 @@ impl<'a, F: Field + Group> Assignment<F> for MockProver<'a, F> {
 
     fn exit_region(&mut self) {
         self.regions.push(self.current_region.take().unwrap());
+        // An unknown value is only attributed to an unavailable challenge in the region
+        // that queried it, or in the next region if it was queried outside of one.
+        *self.unavailable_challenge_queried.get_mut() = false;
     }
 
     fn annotate_column<A, AR>(&mut self, annotation: A, column: Column<Any>)
@@ -472,15 +519,21 @@ impl<'a, F: Field + Group> Assignment<F> for MockProver<'a, F> {
                 cs: self.cs.clone(),
                 regions: vec![],
                 current_region: None,
+                namespace: self.namespace.clone(),
                 fixed_vec: self.fixed_vec.clone(),
                 fixed,
                 advice_vec: self.advice_vec.clone(),
                 advice,
-                advice_prev: self.advice_prev.clone(),
+                phase_violations: vec![],
+                reassigned: HashMap::new(),
+                unavailable_challenge_queried: AtomicBool::new(
+                    self.unavailable_challenge_queried.load(Ordering::Relaxed),
+                ),
                 instance: self.instance.clone(),
                 selectors_vec: self.selectors_vec.clone(),
                 selectors,
                 challenges: self.challenges.clone(),
+                products: self.products,
                 permutation: None,
                 rw_rows: sub_range.clone(),
                 usable_rows: self.usable_rows.clone(),
@@ -503,8 +556,13 @@ impl<'a, F: Field + Group> Assignment<F> for MockProver<'a, F> {
                 .copy(left.column, left.row, right.column, right.row)?;
         }
 
-        for region in sub_cs.into_iter().map(|cs| cs.regions) {
-            self.regions.extend_from_slice(&region[..])
+        for sub_cs in sub_cs {
+            self.regions.extend_from_slice(&sub_cs.regions[..]);
+            self.phase_violations.extend(sub_cs.phase_violations);
+            self.reassigned.extend(sub_cs.reassigned);
+            if sub_cs.unavailable_challenge_queried.into_inner() {
+                *self.unavailable_challenge_queried.get_mut() = true;
+            }
         }
 
         Ok(())
@@ -566,27 +624,36 @@ impl<'a, F: Field + Group> Assignment<F> for MockProver<'a, F> {
                 .or_default();
         }
 
-        let assigned = CellValue::Assigned(to().into_field().evaluate().assign()?);
-        *self
+        let value = to().into_field().evaluate();
+        let cell = self
             .advice
             .get_mut(column.index())
             .and_then(|v| v.get_mut(row - self.rw_rows.start))
-            .ok_or(Error::BoundsFailure)? = assigned;
-
-        #[cfg(feature = "phase-check")]
-        // if false && self.current_phase.0 > column.column_type().phase.0 {
-        if false {
-            // Some circuits assign cells more than one times with different values
-            // So this check sometimes can be false alarm
-            if !self.advice_prev.is_empty() && self.advice_prev[column.index()][row] != assigned {
-                panic!("not same new {assigned:?} old {:?}, column idx {} row {} cur phase {:?} col phase {:?} region {:?}",
-                    self.advice_prev[column.index()][row],
-                    column.index(),
-                    row,
-                    self.current_phase,
-                    column.column_type().phase,
-                    self.current_region
-                )
+            .ok_or(Error::BoundsFailure)?;
+
+        if self.current_phase.0 > column.column_type().phase.0 {
+            // The column has already been committed to in an earlier phase, so its value
+            // must not change. The real prover ignores this assignment. A cell may be
+            // assigned more than once, so only the last value is checked once the phase
+            // has been synthesized.
+            if let Ok(value) = value.assign() {
+                self.reassigned
+                    .insert((column.index(), row), (column, value));
+            }
+        } else {
+            match value.assign() {
+                Ok(value) => *cell = CellValue::Assigned(value),
+                // The value depends on a challenge that is squeezed in this phase or a
+                // later one, so the real prover would be unable to compute it.
+                Err(_) if self.unavailable_challenge_queried.load(Ordering::Relaxed) => {
+                    self.phase_violations.push((
+                        column,
+                        row,
+                        self.current_phase,
+                        PhaseViolationKind::UnavailableChallenge,
+                    ));
+                }
+                Err(e) => return Err(e),
             }
         }
 
@@ -695,28 +762,44 @@ impl<'a, F: Field + Group> Assignment<F> for MockProver<'a, F> {
     }
 
     fn get_challenge(&self, challenge: Challenge) -> circuit::Value<F> {
+        // A challenge is only available in the phases after the one it is squeezed in.
+        if challenge.phase() >= self.current_phase.0 {
+            self.unavailable_challenge_queried
+                .store(true, Ordering::Relaxed);
+            return circuit::Value::unknown();
+        }
         match self.challenges.get(challenge.index()) {
             None => circuit::Value::unknown(),
             Some(v) => circuit::Value::known(*v),
         }
     }
 
-    fn push_namespace<NR, N>(&mut self, _: N)
+    fn usable_rows(&self) -> Option<usize> {
+        Some(self.usable_rows.end)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
     where
         NR: Into<String>,
         N: FnOnce() -> NR,
     {
-        // TODO: Do something with namespaces :)
+        self.namespace.push(name_fn().into());
     }
 
     fn pop_namespace(&mut self, _: Option<String>) {
-        // TODO: Do something with namespaces :)
+        self.namespace
+            .pop()
+            .expect("pop_namespace should never be called on the root");
     }
 }
 
 impl<'a, F: FieldExt> MockProver<'a, F> {
     /// Runs a synthetic keygen-and-prove operation on the given circuit, collecting data
     /// about the constraints and their assignments.
+    ///
+    /// A circuit with more than one phase is synthesized once per phase, as by the real
+    /// prover, so that cells assigned in violation of their phase can be reported. This
+    /// multiplies the time spent in synthesis by the number of phases.
     pub fn run<ConcreteCircuit: Circuit<F>>(
         k: u32,
         circuit: &ConcreteCircuit,
@@ -774,93 +857,69 @@ impl<'a, F: FieldExt> MockProver<'a, F> {
         let permutation = permutation::keygen::Assembly::new(n, &cs.permutation);
         let constants = cs.constants.clone();
 
-        // Use hash chain to derive deterministic challenges for testing
-        let challenges: Vec<F> = {
+        // Use hash chain to derive deterministic challenges for testing, followed by
+        // `theta`, `beta` and `gamma`
+        let (challenges, products) = {
             let mut hash: [u8; 64] = blake2b(b"Halo2-MockProver").as_bytes().try_into().unwrap();
-            iter::repeat_with(|| {
+            let mut challenges: Vec<F> = iter::repeat_with(|| {
                 hash = blake2b(&hash).as_bytes().try_into().unwrap();
                 F::from_bytes_wide(&hash)
             })
-            .take(cs.num_challenges)
-            .collect()
+            .take(cs.num_challenges + 3)
+            .collect();
+            let products = challenges.split_off(cs.num_challenges);
+            (challenges, (products[0], products[1], products[2]))
         };
 
-        #[cfg(feature = "phase-check")]
-        let current_phase = FirstPhase.to_sealed();
-        #[cfg(not(feature = "phase-check"))]
-        let current_phase = crate::plonk::sealed::Phase(cs.max_phase());
-
         let mut prover = MockProver {
             k,
             n: n as u32,
             cs,
             regions: vec![],
             current_region: None,
+            namespace: vec![],
             fixed_vec,
             fixed,
             advice_vec,
             advice,
-            advice_prev: vec![],
+            phase_violations: vec![],
+            reassigned: HashMap::new(),
+            unavailable_challenge_queried: AtomicBool::new(false),
             instance,
             selectors_vec,
             selectors,
-            challenges: challenges.clone(),
+            challenges,
+            products,
             permutation: Some(permutation),
             rw_rows: 0..usable_rows,
             usable_rows: 0..usable_rows,
-            current_phase,
+            current_phase: FirstPhase.to_sealed(),
         };
 
-        #[cfg(feature = "phase-check")]
-        {
-            // check1: phase1 should not assign expr including phase2 challenges
-            // check2: phase2 assigns same phase1 columns with phase1
-            let mut cur_challenges: Vec<F> = Vec::new();
-            let mut last_advice: Vec<Vec<CellValue<F>>> = Vec::new();
-            for current_phase in prover.cs.phases() {
-                prover.current_phase = current_phase;
-                prover.advice_prev = last_advice;
+        let syn_time = Instant::now();
+        if prover.cs.num_phases() > 1 {
+            // Synthesize the circuit once per phase, as the real prover does, so that each
+            // challenge is only available in the phases after the one it is squeezed in.
+            // The regions are recorded anew in each phase.
+            let phases: Vec<_> = prover.cs.phases().collect();
+            for phase in phases {
+                prover.current_phase = phase;
+                prover.regions.clear();
+                *prover.unavailable_challenge_queried.get_mut() = false;
                 ConcreteCircuit::FloorPlanner::synthesize(
                     &mut prover,
                     circuit,
                     config.clone(),
                     constants.clone(),
                 )?;
-
-                for (index, phase) in prover.cs.challenge_phase.iter().enumerate() {
-                    if current_phase == *phase {
-                        debug_assert_eq!(cur_challenges.len(), index);
-                        cur_challenges.push(challenges[index]);
-                    }
-                }
-                if !prover.advice_prev.is_empty() {
-                    let mut err = false;
-                    for (idx, advice_values) in prover.advice.iter().enumerate() {
-                        if prover.cs.advice_column_phase[idx].0 < current_phase.0
-                            && advice_values != &prover.advice_prev[idx]
-                        {
-                            log::error!(
-                                "PHASE ERR column{} not same after phase {:?}",
-                                idx,
-                                current_phase
-                            );
-                            err = true;
-                        }
-                    }
-                    if err {
-                        panic!("wrong phase assignment");
-                    }
-                }
-                last_advice = prover.advice_vec.as_ref().clone();
+                prover.check_reassigned_cells();
+                prover.fill_intermediate_columns();
             }
-        }
-
-        #[cfg(not(feature = "phase-check"))]
-        {
-            let syn_time = Instant::now();
+        } else {
             ConcreteCircuit::FloorPlanner::synthesize(&mut prover, circuit, config, constants)?;
-            log::info!("MockProver synthesize took {:?}", syn_time.elapsed());
+            prover.fill_intermediate_columns();
         }
+        log::info!("MockProver synthesize took {:?}", syn_time.elapsed());
 
         let (cs, selector_polys) = prover
             .cs
@@ -890,6 +949,69 @@ impl<'a, F: FieldExt> MockProver<'a, F> {
         Ok(prover)
     }
 
+    /// Checks that the cells of earlier phases' columns that were assigned in the
+    /// current phase were last assigned the same value as in their own phase.
+    fn check_reassigned_cells(&mut self) {
+        let mut reassigned: Vec<_> = self.reassigned.drain().collect();
+        reassigned.sort_unstable_by_key(|(cell, _)| *cell);
+        for ((index, row), (column, value)) in reassigned {
+            if self.advice[index][row] != CellValue::Assigned(value) {
+                self.phase_violations.push((
+                    column,
+                    row,
+                    self.current_phase,
+                    PhaseViolationKind::Reassigned,
+                ));
+            }
+        }
+    }
+
+    /// Fills in the intermediate columns of the current phase, which were allocated to
+    /// reduce the degree of gates, from the rest of the witness.
+    fn fill_intermediate_columns(&mut self) {
+        let n = self.n as i32;
+        for (column, expression) in self.cs.intermediate_columns.clone() {
+            if column.column_type().phase != self.current_phase {
+                continue;
+            }
+            for row in self.usable_rows.clone() {
+                let at = |rotation: Rotation| ((row as i32 + n + rotation.0) % n) as usize;
+                let value = expression.evaluate(
+                    &Value::Real,
+                    &|selector| {
+                        Value::Real(if self.selectors[selector.0][row] {
+                            F::one()
+                        } else {
+                            F::zero()
+                        })
+                    },
+                    &|query| self.fixed[query.column_index][at(query.rotation)].into(),
+                    &|query| self.advice[query.column_index][at(query.rotation)].into(),
+                    &|query| Value::Real(self.instance[query.column_index][at(query.rotation)]),
+                    &|challenge| Value::Real(self.challenges[challenge.index()]),
+                    &|a| -a,
+                    &|a, b| a + b,
+                    &|a, b| a * b,
+                    &|a, scalar| a * scalar,
+                );
+                self.advice[column.index()][row] = match value {
+                    Value::Real(value) => CellValue::Assigned(value),
+                    Value::Poison => CellValue::Poison(row),
+                };
+            }
+        }
+    }
+
+    /// Runs [`MockProver::run`] at the smallest `k` that fits the circuit, as found by
+    /// [`minimum_k`], and returns that `k` along with the prover.
+    pub fn run_at_minimum_k<ConcreteCircuit: Circuit<F>>(
+        circuit: &ConcreteCircuit,
+        instance: Vec<Vec<F>>,
+    ) -> Result<(u32, Self), Error> {
+        let k = minimum_k(circuit, &instance)?;
+        Self::run(k, circuit, instance).map(|prover| (k, prover))
+    }
+
     pub fn advice_values(&self, column: Column<Advice>) -> &[CellValue<F>] {
         &self.advice[column.index()]
     }
@@ -904,6 +1026,30 @@ impl<'a, F: FieldExt> MockProver<'a, F> {
         self.verify_at_rows(self.usable_rows.clone(), self.usable_rows.clone())
     }
 
+    /// Returns `Ok(())` if no failures occur in regions assigned within the namespace
+    /// path `prefix` (or any of its children), or a list of those failures otherwise.
+    ///
+    /// Namespaces are entered with [`Layouter::namespace`]; failures that occur outside
+    /// of any region are only reported when `prefix` is empty.
+    ///
+    /// [`Layouter::namespace`]: crate::circuit::Layouter#method.namespace
+    pub fn verify_in_namespace(&self, prefix: &[&str]) -> Result<(), Vec<VerifyFailure>> {
+        match self.verify() {
+            Ok(()) => Ok(()),
+            Err(errors) => {
+                let errors: Vec<_> = errors
+                    .into_iter()
+                    .filter(|failure| failure.is_in_namespace(prefix))
+                    .collect();
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(errors)
+                }
+            }
+        }
+    }
+
     /// Returns `Ok(())` if this `MockProver` is satisfied, or a list of errors indicating
     /// the reasons that the circuit is not satisfied.
     /// Constraints are only checked at `gate_row_ids`,
@@ -959,7 +1105,7 @@ impl<'a, F: FieldExt> MockProver<'a, F> {
                                 } else {
                                     Some(VerifyFailure::CellNotAssigned {
                                         gate: (gate_index, gate.name()).into(),
-                                        region: (r_i, r.name.clone(), r.annotations.clone()).into(),
+                                        region: r.metadata(r_i),
                                         gate_offset: *selector_row,
                                         column: cell.column,
                                         offset: cell_row as isize - r.rows.unwrap().0 as isize,
@@ -1127,6 +1273,10 @@ impl<'a, F: FieldExt> MockProver<'a, F> {
 
                         // In the real prover, the lookup expressions are never enforced on
                         // unusable rows, due to the (1 - (l_last(X) + l_blind(X))) term.
+                        //
+                        // Table rows are kept alongside their values so that failures can
+                        // point at the nearest entries; the fill row is kept once for the
+                        // same reason.
                         cached_table = self
                             .usable_rows
                             .clone()
@@ -1138,11 +1288,12 @@ impl<'a, F: FieldExt> MockProver<'a, F> {
                                     .collect();
 
                                 if t != fill_row {
-                                    Some(t)
+                                    Some((t, table_row))
                                 } else {
                                     None
                                 }
                             })
+                            .chain(Some((fill_row.clone(), self.usable_rows.end - 1)))
                             .collect();
                         cached_table.sort_unstable();
                     }
@@ -1172,22 +1323,13 @@ impl<'a, F: FieldExt> MockProver<'a, F> {
                     inputs
                         .iter()
                         .filter_map(move |(input, input_row)| {
-                            while i < table.len() && &table[i] < input {
+                            while i < table.len() && &table[i].0 < input {
                                 i += 1;
                             }
-                            if i == table.len() || &table[i] > input {
-                                assert!(table.binary_search(input).is_err());
+                            if i == table.len() || &table[i].0 > input {
+                                assert!(table.binary_search_by(|(t, _)| t.cmp(input)).is_err());
 
-                                Some(VerifyFailure::Lookup {
-                                    name: lookup.name,
-                                    lookup_index,
-                                    location: FailureLocation::find_expressions(
-                                        &self.cs,
-                                        &self.regions,
-                                        *input_row,
-                                        lookup.input_expressions.iter(),
-                                    ),
-                                })
+                                Some(self.lookup_failure(lookup_index, input, *input_row, table, i))
                             } else {
                                 None
                             }
@@ -1243,10 +1385,12 @@ impl<'a, F: FieldExt> MockProver<'a, F> {
         };
 
         let mut errors: Vec<_> = iter::empty()
+            .chain(self.phase_errors())
             .chain(selector_errors)
             .chain(gate_errors)
             .chain(lookup_errors)
             .chain(perm_errors)
+            .chain(self.custom_argument_errors())
             .collect();
         if errors.is_empty() {
             Ok(())
@@ -1333,12 +1477,7 @@ impl<'a, F: FieldExt> MockProver<'a, F> {
                                         } else {
                                             Some(VerifyFailure::CellNotAssigned {
                                                 gate: (gate_index, gate.name()).into(),
-                                                region: (
-                                                    r_i,
-                                                    r.name.clone(),
-                                                    r.annotations.clone(),
-                                                )
-                                                    .into(),
+                                                region: r.metadata(r_i),
                                                 gate_offset: *selector_row,
                                                 column: cell.column,
                                                 offset: cell_row as isize
@@ -1505,6 +1644,10 @@ impl<'a, F: FieldExt> MockProver<'a, F> {
 
                         // In the real prover, the lookup expressions are never enforced on
                         // unusable rows, due to the (1 - (l_last(X) + l_blind(X))) term.
+                        //
+                        // Table rows are kept alongside their values so that failures can
+                        // point at the nearest entries; the fill row is kept once for the
+                        // same reason.
                         cached_table = self
                             .usable_rows
                             .clone()
@@ -1517,11 +1660,15 @@ impl<'a, F: FieldExt> MockProver<'a, F> {
                                     .collect();
 
                                 if t != fill_row {
-                                    Some(t)
+                                    Some((t, table_row))
                                 } else {
                                     None
                                 }
                             })
+                            .chain(rayon::iter::once((
+                                fill_row.clone(),
+                                self.usable_rows.end - 1,
+                            )))
                             .collect();
                         cached_table.par_sort_unstable();
                     }
@@ -1550,20 +1697,18 @@ impl<'a, F: FieldExt> MockProver<'a, F> {
                     inputs
                         .par_iter()
                         .filter_map(move |(input, input_row)| {
-                            if table.binary_search(input).is_err() {
-                                Some(VerifyFailure::Lookup {
-                                    name: lookup.name,
-                                    lookup_index,
-                                    location: FailureLocation::find_expressions(
-                                        &self.cs,
-                                        &self.regions,
+                            table
+                                .binary_search_by(|(t, _)| t.cmp(input))
+                                .err()
+                                .map(|position| {
+                                    self.lookup_failure(
+                                        lookup_index,
+                                        input,
                                         *input_row,
-                                        lookup.input_expressions.iter(),
-                                    ),
+                                        table,
+                                        position,
+                                    )
                                 })
-                            } else {
-                                None
-                            }
                         })
                         .collect::<Vec<_>>()
                 });
@@ -1620,10 +1765,12 @@ impl<'a, F: FieldExt> MockProver<'a, F> {
         };
 
         let mut errors: Vec<_> = iter::empty()
+            .chain(self.phase_errors())
             .chain(selector_errors)
             .chain(gate_errors)
             .chain(lookup_errors)
             .chain(perm_errors)
+            .chain(self.custom_argument_errors())
             .collect();
         if errors.is_empty() {
             Ok(())
@@ -1642,6 +1789,179 @@ impl<'a, F: FieldExt> MockProver<'a, F> {
         }
     }
 
+    /// Returns the failures for the constraints of custom arguments, which are checked on
+    /// every row up to the row of `ArgumentExpression::Last`.
+    ///
+    /// The cells after the usable rows are zero in the columns of the circuit that the
+    /// arguments use.
+    fn custom_argument_errors(&self) -> Vec<VerifyFailure> {
+        let n = self.n as i32;
+        let circuit = |expression: &Expression<F>| -> Vec<F> {
+            (0..n)
+                .map(|row| {
+                    let value = expression.evaluate_lazy(
+                        &|scalar| Value::Real(scalar),
+                        &|_| panic!("virtual selectors are removed during optimization"),
+                        &|query| {
+                            let query = self.cs.fixed_queries[query.index];
+                            let column_index = query.0.index();
+                            let rotation = query.1 .0;
+                            self.fixed[column_index][(row + n + rotation) as usize % n as usize]
+                                .into()
+                        },
+                        &|query| {
+                            let query = self.cs.advice_queries[query.index];
+                            let column_index = query.0.index();
+                            let rotation = query.1 .0;
+                            self.advice[column_index][(row + n + rotation) as usize % n as usize]
+                                .into()
+                        },
+                        &|query| {
+                            let query = self.cs.instance_queries[query.index];
+                            let column_index = query.0.index();
+                            let rotation = query.1 .0;
+                            Value::Real(
+                                self.instance[column_index]
+                                    [(row + n + rotation) as usize % n as usize],
+                            )
+                        },
+                        &|challenge| Value::Real(self.challenges[challenge.index()]),
+                        &|a| -a,
+                        &|a, b| a + b,
+                        &|a, b| a * b,
+                        &|a, scalar| a * scalar,
+                        &Value::Real(F::zero()),
+                    );
+                    match value {
+                        Value::Real(value) => value,
+                        Value::Poison => F::zero(),
+                    }
+                })
+                .collect()
+        };
+
+        self.cs
+            .custom_arguments
+            .iter()
+            .enumerate()
+            .flat_map(|(argument_index, argument)| {
+                // Arguments committed in an advice phase do not see `theta`, `beta` and
+                // `gamma`.
+                let (theta, beta, gamma) = match argument.phase {
+                    Some(_) => (F::zero(), F::zero(), F::zero()),
+                    None => self.products,
+                };
+                match argument.check(
+                    self.n as usize,
+                    self.cs.blinding_factors(),
+                    &circuit,
+                    &self.challenges,
+                    theta,
+                    beta,
+                    gamma,
+                ) {
+                    Ok(failures) => failures
+                        .into_iter()
+                        .map(|(constraint_index, row)| {
+                            // Locate the failure by the columns that the inputs and the
+                            // constraint query.
+                            let mut expressions = argument.inputs.clone();
+                            argument.constraints[constraint_index]
+                                .clone()
+                                .for_each_circuit_expression_mut(&mut |expression| {
+                                    expressions.push(expression.clone())
+                                });
+                            VerifyFailure::CustomArgument {
+                                name: argument.name,
+                                argument_index,
+                                constraint_index,
+                                location: FailureLocation::find_expressions(
+                                    &self.cs,
+                                    &self.regions,
+                                    row,
+                                    expressions.iter(),
+                                ),
+                            }
+                        })
+                        .collect(),
+                    Err(_) => vec![VerifyFailure::CustomArgumentColumns {
+                        name: argument.name,
+                        argument_index,
+                    }],
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the failures for advice cells that were assigned in violation of their
+    /// column's phase during synthesis.
+    fn phase_errors(&self) -> impl Iterator<Item = VerifyFailure> + '_ {
+        self.phase_violations
+            .iter()
+            .map(
+                |&(column, row, phase, kind)| VerifyFailure::PhaseViolation {
+                    column: Column::<Any>::from(column).into(),
+                    row,
+                    phase: phase.0,
+                    kind,
+                },
+            )
+    }
+
+    /// Builds the failure for a lookup input that is missing from its table.
+    ///
+    /// `table` holds the table's tuples alongside their rows, sorted, and `position` is
+    /// the index at which `input` would be inserted into it.
+    fn lookup_failure(
+        &self,
+        lookup_index: usize,
+        input: &[Value<F>],
+        input_row: usize,
+        table: &[(Vec<Value<F>>, usize)],
+        position: usize,
+    ) -> VerifyFailure {
+        let n = self.n as i32;
+        let row = input_row as i32 + n;
+        let lookup = &self.cs.lookups[lookup_index];
+        let format_tuple = |tuple: &[Value<F>]| -> Vec<String> {
+            tuple.iter().copied().map(util::format_cell_value).collect()
+        };
+
+        let cell_values: BTreeMap<_, _> = lookup
+            .input_expressions
+            .iter()
+            .flat_map(|expression| {
+                util::expression_cell_values(
+                    expression,
+                    util::load_slice(n, row, &self.cs.fixed_queries, &self.fixed),
+                    util::load_slice(n, row, &self.cs.advice_queries, &self.advice),
+                    util::load_instance(n, row, &self.cs.instance_queries, &self.instance),
+                )
+            })
+            .collect();
+
+        let nearest_table_rows = position
+            .checked_sub(1)
+            .into_iter()
+            .chain(Some(position).filter(|&i| i < table.len()))
+            .map(|i| (table[i].1, format_tuple(&table[i].0)))
+            .collect();
+
+        VerifyFailure::Lookup {
+            name: lookup.name,
+            lookup_index,
+            location: FailureLocation::find_expressions(
+                &self.cs,
+                &self.regions,
+                input_row,
+                lookup.input_expressions.iter(),
+            ),
+            input: format_tuple(input),
+            cell_values: cell_values.into_iter().collect(),
+            nearest_table_rows,
+        }
+    }
+
     /// Panics if the circuit being checked by this `MockProver` is not satisfied.
     ///
     /// Any verification failures will be pretty-printed to stderr before the function
@@ -1725,14 +2045,23 @@ impl<'a, F: FieldExt> MockProver<'a, F> {
 
 #[cfg(test)]
 mod tests {
-    use halo2curves::pasta::Fp;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use halo2curves::pasta::{vesta, Fp};
 
-    use super::{FailureLocation, MockProver, VerifyFailure};
+    use rand_core::OsRng;
+
+    use super::{
+        cost::{ColumnUsage, GateCost, KzgMultiopen, LookupCost},
+        minimum_k, CircuitCost, CircuitGates, FailureLocation, HtmlLayout, MockProver,
+        MutationReport, PhaseViolationKind, UnconstrainedCells, VerifyFailure,
+    };
     use crate::{
         circuit::{Layouter, SimpleFloorPlanner, Value},
         plonk::{
-            sealed::SealedPhase, Advice, Any, Circuit, Column, ConstraintSystem, Error, Expression,
-            FirstPhase, Fixed, Instance, Selector, TableColumn,
+            sealed::SealedPhase, Advice, Any, Challenge, Circuit, Column, ConstraintSystem, Error,
+            Expression, FirstPhase, Fixed, Instance, NthPhase, SecondPhase, Selector, TableColumn,
+            TaggedTable,
         },
         poly::Rotation,
     };
@@ -1987,7 +2316,16 @@ mod tests {
                 location: FailureLocation::InRegion {
                     region: (1, "Faulty synthesis").into(),
                     offset: 1,
-                }
+                },
+                input: vec!["0x5".to_string(), "0x5".to_string()],
+                cell_values: vec![
+                    (((Any::advice(), 0).into(), 0).into(), "0x5".to_string()),
+                    (((Any::Fixed, 0).into(), 0).into(), "1".to_string()),
+                ],
+                nearest_table_rows: vec![
+                    (2, vec!["0x4".to_string(), "0x4".to_string()]),
+                    (3, vec!["0x6".to_string(), "0x6".to_string()]),
+                ],
             }])
         );
     }
@@ -2119,7 +2457,16 @@ mod tests {
                 location: FailureLocation::InRegion {
                     region: (2, "Faulty synthesis").into(),
                     offset: 1,
-                }
+                },
+                input: vec!["0x5".to_string()],
+                cell_values: vec![
+                    (((Any::advice(), 0).into(), 0).into(), "0x5".to_string()),
+                    (((Any::Fixed, 1).into(), 0).into(), "1".to_string()),
+                ],
+                nearest_table_rows: vec![
+                    (1, vec!["0x4".to_string()]),
+                    (2, vec!["0x6".to_string()]),
+                ],
             }])
         );
     }
@@ -2304,4 +2651,1273 @@ mod tests {
             },])
         )
     }
+
+    #[test]
+    fn failure_namespaces() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct FaultyCircuitConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            q: Selector,
+        }
+
+        impl FaultyCircuitConfig {
+            fn assign_pair(&self, mut layouter: impl Layouter<Fp>, b: u64) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "Pair",
+                    |mut region| {
+                        self.q.enable(&mut region, 0)?;
+                        region.assign_advice(|| "a", self.a, 0, || Value::known(Fp::one()))?;
+                        region.assign_advice(|| "b", self.b, 0, || Value::known(Fp::from(b)))?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        struct FaultyCircuit {}
+
+        impl Circuit<Fp> for FaultyCircuit {
+            type Config = FaultyCircuitConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let q = meta.selector();
+
+                meta.create_gate("Equality check", |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let b = cells.query_advice(b, Rotation::cur());
+                    let q = cells.query_selector(q);
+
+                    vec![q * (a - b)]
+                });
+
+                FaultyCircuitConfig { a, b, q }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {}
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                let mut outer = layouter.namespace(|| "outer");
+                config.assign_pair(outer.namespace(|| "good"), 1)?;
+                // BUG: b != a.
+                config.assign_pair(outer.namespace(|| "bad"), 2)?;
+                Ok(())
+            }
+        }
+
+        let prover = MockProver::run(K, &FaultyCircuit {}, vec![]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].namespace(), ["outer", "bad"]);
+        assert!(failures[0].is_in_namespace(&[]));
+        assert!(failures[0].is_in_namespace(&["outer"]));
+        assert!(!failures[0].is_in_namespace(&["outer", "good"]));
+
+        assert_eq!(prover.verify_in_namespace(&["outer", "bad"]), Err(failures));
+        assert_eq!(prover.verify_in_namespace(&["outer", "good"]), Ok(()));
+        assert_eq!(prover.verify_in_namespace(&["other"]), Ok(()));
+    }
+
+    #[test]
+    fn mutation_test() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct UnderConstrainedConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            c: Column<Advice>,
+            q: Selector,
+        }
+
+        struct UnderConstrainedCircuit {}
+
+        impl Circuit<Fp> for UnderConstrainedCircuit {
+            type Config = UnderConstrainedConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let c = meta.advice_column();
+                let q = meta.selector();
+
+                meta.create_gate("Equality check", |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let b = cells.query_advice(b, Rotation::cur());
+                    let q = cells.query_selector(q);
+
+                    vec![q * (a - b)]
+                });
+
+                UnderConstrainedConfig { a, b, c, q }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {}
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "Pair",
+                    |mut region| {
+                        config.q.enable(&mut region, 0)?;
+                        region.assign_advice(|| "a", config.a, 0, || Value::known(Fp::one()))?;
+                        region.assign_advice(|| "b", config.b, 0, || Value::known(Fp::one()))?;
+                        // BUG: c is assigned but never constrained.
+                        region.assign_advice(|| "c", config.c, 0, || Value::known(Fp::one()))?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let mut prover = MockProver::run(K, &UnderConstrainedCircuit {}, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let expected = MutationReport {
+            cells_tested: 3,
+            unconstrained: vec![UnconstrainedCells {
+                region: Some((0, "Pair").into()),
+                column: (Any::advice(), 2).into(),
+                annotation: None,
+                offsets: vec![0],
+            }],
+        };
+        assert_eq!(prover.mutation_test(), expected);
+        assert_eq!(prover.mutation_test_sampled(10, OsRng), expected);
+
+        // The witness is left untouched.
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn minimum_k_search() {
+        #[derive(Clone)]
+        struct RowsConfig {
+            a: Column<Advice>,
+            q: Selector,
+            table: TableColumn,
+            _instance: Column<Instance>,
+        }
+
+        struct RowsCircuit {
+            rows: usize,
+            table_rows: usize,
+        }
+
+        impl Circuit<Fp> for RowsCircuit {
+            type Config = RowsConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let q = meta.complex_selector();
+                let table = meta.lookup_table_column();
+                let instance = meta.instance_column();
+
+                meta.lookup("lookup", |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let q = cells.query_selector(q);
+                    vec![(q * a, table)]
+                });
+
+                RowsConfig {
+                    a,
+                    q,
+                    table,
+                    _instance: instance,
+                }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {
+                    rows: self.rows,
+                    table_rows: self.table_rows,
+                }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_table(
+                    || "table",
+                    |mut table| {
+                        for i in 0..self.table_rows {
+                            table.assign_cell(
+                                || "table",
+                                config.table,
+                                i,
+                                || Value::known(Fp::from(i as u64)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )?;
+
+                layouter.assign_region(
+                    || "rows",
+                    |mut region| {
+                        for offset in 0..self.rows {
+                            config.q.enable(&mut region, offset)?;
+                            region.assign_advice(
+                                || "a",
+                                config.a,
+                                offset,
+                                || Value::known(Fp::zero()),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        // Each column is queried once, so there are 5 blinding factors, and 2^k - 6 rows
+        // are usable.
+        let circuit = |rows, table_rows| RowsCircuit { rows, table_rows };
+        assert_eq!(minimum_k(&circuit(10, 1), &[vec![]]).unwrap(), 4);
+        assert_eq!(minimum_k(&circuit(11, 1), &[vec![]]).unwrap(), 5);
+        // The table is padded from its first unused row.
+        assert_eq!(minimum_k(&circuit(1, 9), &[vec![]]).unwrap(), 4);
+        assert_eq!(minimum_k(&circuit(1, 10), &[vec![]]).unwrap(), 5);
+        // Instance values must also fit in the usable rows.
+        assert_eq!(
+            minimum_k(&circuit(1, 1), &[vec![Fp::one(); 11]]).unwrap(),
+            5
+        );
+        // Even an almost empty circuit needs room for the blinding factors.
+        assert_eq!(minimum_k(&circuit(0, 1), &[vec![]]).unwrap(), 3);
+        assert!(matches!(
+            minimum_k(&circuit(1, 1), &[]),
+            Err(Error::InvalidInstances)
+        ));
+
+        // The minimum k is tight.
+        assert!(matches!(
+            MockProver::run(4, &circuit(11, 1), vec![vec![]]),
+            Err(Error::NotEnoughRowsAvailable { current_k: 4 })
+        ));
+        let (k, prover) = MockProver::run_at_minimum_k(&circuit(11, 1), vec![vec![]]).unwrap();
+        assert_eq!(k, 5);
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn circuit_cost() {
+        const K: usize = 4;
+
+        #[derive(Clone)]
+        struct CostConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            f: Column<Fixed>,
+            q: Selector,
+            table: TableColumn,
+        }
+
+        struct CostCircuit {}
+
+        impl Circuit<Fp> for CostCircuit {
+            type Config = CostConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let f = meta.fixed_column();
+                let q = meta.complex_selector();
+                let table = meta.lookup_table_column();
+                meta.annotate_lookup_column(table, || "table");
+
+                meta.create_gate("mul", |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let b = cells.query_advice(b, Rotation::cur());
+                    let f = cells.query_fixed(f, Rotation::cur());
+                    let q = cells.query_selector(q);
+                    vec![q * (a * b - f)]
+                });
+
+                meta.lookup("range", |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let q = cells.query_selector(q);
+                    vec![(q * a, table)]
+                });
+
+                CostConfig { a, b, f, q, table }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {}
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_table(
+                    || "table",
+                    |mut table| {
+                        for i in 0..4 {
+                            table.assign_cell(
+                                || "table",
+                                config.table,
+                                i,
+                                || Value::known(Fp::from(i as u64)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )?;
+
+                layouter.assign_region(
+                    || "mul",
+                    |mut region| {
+                        region.name_column(|| "a", config.a);
+                        for offset in 0..3 {
+                            config.q.enable(&mut region, offset)?;
+                            region.assign_advice(
+                                || "a",
+                                config.a,
+                                offset,
+                                || Value::known(Fp::one()),
+                            )?;
+                            region.assign_advice(
+                                || "b",
+                                config.b,
+                                offset,
+                                || Value::known(Fp::one()),
+                            )?;
+                            region.assign_fixed(
+                                || "f",
+                                config.f,
+                                offset,
+                                || Value::known(Fp::one()),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let cost = CircuitCost::<vesta::Point, _>::measure(K, &CostCircuit {});
+
+        assert_eq!(
+            cost.gates(),
+            &[GateCost {
+                name: "mul",
+                constraint_degrees: vec![3],
+                queries: 3,
+            }]
+        );
+        assert_eq!(
+            cost.lookups(),
+            &[LookupCost {
+                name: "range",
+                degree: 5,
+                queries: 2,
+            }]
+        );
+        assert_eq!(cost.max_degree_sources(), vec!["range"]);
+
+        // There are 5 blinding factors, leaving 10 usable rows. The table is padded to
+        // fill all of them.
+        let usage = |column_type, index, annotation: Option<&str>, used| ColumnUsage {
+            column: (column_type, index).into(),
+            annotation: annotation.map(String::from),
+            used,
+            wasted: 10 - used,
+        };
+        assert_eq!(
+            cost.column_usage(),
+            &[
+                usage(Any::advice(), 0, Some("a"), 3),
+                usage(Any::advice(), 1, None, 3),
+                usage(Any::Fixed, 0, None, 3),
+                usage(Any::Fixed, 1, Some("table"), 10),
+            ]
+        );
+
+        let ipa = usize::from(cost.proof_size(1));
+        let gwc = usize::from(cost.kzg_proof_size(1, KzgMultiopen::Gwc));
+        let shplonk = usize::from(cost.kzg_proof_size(1, KzgMultiopen::Shplonk));
+        assert!(shplonk <= gwc && gwc < ipa);
+
+        let json = cost.to_json(1);
+        assert!(json.starts_with("{\n  \"k\": 4,\n"));
+        assert!(json.contains("\"max_degree_sources\": [\n    \"range\"\n  ],"));
+        assert!(json.contains(&format!("\"prover_memory\": {}", cost.prover_memory(1))));
+    }
+
+    #[test]
+    fn json_export() {
+        const K: u32 = 3;
+
+        #[derive(Clone)]
+        struct IsZeroConfig {
+            a: Column<Advice>,
+            q: Selector,
+        }
+
+        struct IsZeroCircuit {}
+
+        impl Circuit<Fp> for IsZeroCircuit {
+            type Config = IsZeroConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let q = meta.selector();
+
+                meta.create_gate("is zero", |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let q = cells.query_selector(q);
+                    vec![q * a]
+                });
+
+                IsZeroConfig { a, q }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {}
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "r",
+                    |mut region| {
+                        region.name_column(|| "a", config.a);
+                        config.q.enable(&mut region, 0)?;
+                        region.assign_advice(|| "a", config.a, 0, || Value::known(Fp::one()))?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &IsZeroCircuit {}, vec![]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(
+            failures[0].to_json(),
+            r#"{
+  "kind": "ConstraintNotSatisfied",
+  "constraint": {
+    "gate": {
+      "index": 0,
+      "name": "is zero"
+    },
+    "index": 0,
+    "name": ""
+  },
+  "location": {
+    "region": {
+      "index": 0,
+      "name": "r",
+      "namespace": []
+    },
+    "offset": 0
+  },
+  "cell_values": [
+    {
+      "column": {
+        "type": "advice",
+        "index": 0,
+        "phase": 0
+      },
+      "rotation": 0,
+      "name": null,
+      "value": "1"
+    }
+  ]
+}"#
+        );
+
+        // There are 5 blinding factors, so only 2 rows are usable. The selector is
+        // compressed into the only fixed column.
+        let indent = |s: &str| s.replace('\n', "\n    ");
+        let expected = format!(
+            r#"{{
+  "k": 3,
+  "usable_rows": 2,
+  "satisfied": false,
+  "failures": [
+    {}
+  ],
+  "regions": [
+    {{
+      "index": 0,
+      "name": "r",
+      "namespace": [],
+      "rows": {{
+        "start": 0,
+        "end": 0
+      }},
+      "columns": [
+        {{
+          "type": "advice",
+          "index": 0,
+          "phase": 0
+        }}
+      ],
+      "annotations": [
+        {{
+          "column": {{
+            "type": "advice",
+            "index": 0,
+            "phase": 0
+          }},
+          "annotation": "a"
+        }}
+      ]
+    }}
+  ]"#,
+            indent(&failures[0].to_json())
+        );
+        assert_eq!(prover.to_json(false), format!("{}\n}}", expected));
+        assert_eq!(
+            prover.to_json(true),
+            format!(
+                r#"{},
+  "assignment": {{
+    "advice": [
+      [
+        "1",
+        null
+      ]
+    ],
+    "fixed": [
+      [
+        "1",
+        "0"
+      ]
+    ],
+    "instance": []
+  }}
+}}"#,
+                expected
+            )
+        );
+    }
+
+    #[test]
+    fn html_layout() {
+        const K: u32 = 3;
+
+        #[derive(Clone)]
+        struct CopyConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+        }
+
+        struct CopyCircuit {}
+
+        impl Circuit<Fp> for CopyCircuit {
+            type Config = CopyConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                meta.enable_equality(a);
+                meta.enable_equality(b);
+
+                CopyConfig { a, b }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {}
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "copy",
+                    |mut region| {
+                        region.name_column(|| "a", config.a);
+                        let a = region.assign_advice(
+                            || "a",
+                            config.a,
+                            0,
+                            || Value::known(Fp::one()),
+                        )?;
+                        let b = region.assign_advice(
+                            || "b",
+                            config.b,
+                            0,
+                            || Value::known(Fp::from(2)),
+                        )?;
+                        // BUG: a and b are constrained to be equal, but are not.
+                        region.constrain_equal(a.cell(), b.cell())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &CopyCircuit {}, vec![]).unwrap();
+        let html = HtmlLayout::default().render(&prover);
+
+        // Cells show their region, column annotation, row and value.
+        assert!(html.contains(
+            "<title>Region 0 (&#39;copy&#39;)\nColumn(&#39;Advice&#39;, 0) (&#39;a&#39;)\nRow 0 (offset 0)\nValue: 1</title>"
+        ));
+        assert!(html.contains(
+            "<title>Region 0 (&#39;copy&#39;)\nColumn(&#39;Advice&#39;, 1)\nRow 0 (offset 0)\nValue: 0x2</title>"
+        ));
+        // Both sides of the broken copy are marked as failed.
+        assert_eq!(html.matches("<rect class=\"failed\"").count(), 2);
+        assert!(html.contains("2 usable, 2 failures"));
+        // The copy itself is drawn, unless disabled.
+        assert_eq!(html.matches("<g class=\"copies\">").count(), 1);
+        assert_eq!(html.matches("<line x1=").count(), 1);
+        let html = HtmlLayout::default()
+            .show_equality_constraints(false)
+            .render(&prover);
+        assert_eq!(html.matches("<g class=\"copies\">").count(), 0);
+
+        // Cells outside the view are not drawn, and a reversed view is empty.
+        for view in [1..8, 6..2, 12..20] {
+            let html = HtmlLayout::default().view_height(view).render(&prover);
+            assert_eq!(html.matches("<rect class=\"cell").count(), 0);
+            assert_eq!(html.matches("<rect class=\"failed\"").count(), 0);
+        }
+    }
+
+    #[test]
+    fn gates_report() {
+        const K: u32 = 5;
+
+        #[derive(Clone)]
+        struct ReportConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            s: Selector,
+            table: TableColumn,
+        }
+
+        struct ReportCircuit {}
+
+        impl Circuit<Fp> for ReportCircuit {
+            type Config = ReportConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let s = meta.selector();
+                let s_lookup = meta.complex_selector();
+                let table = meta.lookup_table_column();
+                meta.enable_equality(a);
+                meta.annotate_lookup_any_column(b, || "b");
+                meta.annotate_lookup_column(table, || "range");
+                meta.annotate_selector(s, || "s_add");
+
+                meta.create_gate("add", |meta| {
+                    let a_cur = meta.query_advice(a, Rotation::cur());
+                    let a_next = meta.query_advice(a, Rotation::next());
+                    let b = meta.query_advice(b, Rotation::cur());
+                    let s = meta.query_selector(s);
+
+                    vec![
+                        ("sum", s.clone() * (a_cur + b.clone() - a_next)),
+                        (
+                            "bool",
+                            s * b.clone() * (Expression::Constant(Fp::one()) - b),
+                        ),
+                    ]
+                });
+
+                meta.lookup("range", |meta| {
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let s = meta.query_selector(s_lookup);
+
+                    vec![(s * a, table)]
+                });
+
+                ReportConfig { a, b, s, table }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self {}
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_table(
+                    || "range",
+                    |mut table| {
+                        for i in 0..8 {
+                            table.assign_cell(
+                                || "range",
+                                config.table,
+                                i,
+                                || Value::known(Fp::from(i as u64)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )?;
+
+                layouter.assign_region(
+                    || "add",
+                    |mut region| {
+                        region.name_column(|| "a", config.a);
+                        config.s.enable(&mut region, 0)?;
+                        region.assign_advice(|| "a", config.a, 0, || Value::known(Fp::one()))?;
+                        region.assign_advice(|| "b", config.b, 0, || Value::known(Fp::one()))?;
+                        region.assign_advice(|| "a", config.a, 1, || Value::known(Fp::from(2)))?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        // Only the annotations given within `configure` are known to `collect`.
+        let gates = CircuitGates::collect::<Fp, ReportCircuit>();
+        assert!(gates
+            .to_markdown()
+            .contains("| 0 | sum | `s_add * (A0 + b - A0[next])` |"));
+
+        let prover = MockProver::run(K, &ReportCircuit {}, vec![]).unwrap();
+        let gates = gates.with_region_annotations(&prover);
+        assert_eq!(
+            gates.to_markdown(),
+            r#"## Columns
+
+| Name | Column |
+| --- | --- |
+| `a` | Column('Advice', 0) |
+| `b` | Column('Advice', 1) |
+| `range` | Column('Fixed', 0) |
+| `s_add` | Selector 0 |
+| `S1` | Selector 1 |
+
+## Gates
+
+### add
+
+| # | Constraint | Polynomial |
+| --- | --- | --- |
+| 0 | sum | `s_add * (a + b - a[next])` |
+| 1 | bool | `s_add * b * (1 - b)` |
+
+## Lookups
+
+### range
+
+| Input | Table |
+| --- | --- |
+| `S1 * a` | `range` |
+
+## Equality constraints
+
+- `a`
+"#
+        );
+
+        let latex = gates.to_latex();
+        assert!(latex.contains(
+            r"\[ \mathrm{s\_add}(X) \cdot (\mathrm{a}(X) + \mathrm{b}(X) - \mathrm{a}(\omega X)) = 0 \]"
+        ));
+        assert!(latex.contains(r"\[ \mathrm{S1}(X) \cdot \mathrm{a}(X) \in \mathrm{range}(X) \]"));
+        assert!(latex.contains(r"Equality constraints are enabled on $\mathrm{a}$."));
+    }
+
+    #[test]
+    fn phase_violation() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct PhaseConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            theta: Challenge,
+            late: Challenge,
+        }
+
+        #[derive(Default)]
+        struct PhaseCircuit {
+            // The number of times this circuit has been synthesized.
+            synthesized: AtomicU64,
+            // BUG: assign `a` a different value in each phase.
+            reassign: bool,
+            // BUG: assign `b` a challenge that is only available after its phase.
+            use_late_challenge: bool,
+            // Assign `a` twice with different values, which is fine as long as the last
+            // value is the same in every phase.
+            overwrite: bool,
+            // BUG: assign `a` an unknown value in a region of its own.
+            assign_unknown: bool,
+        }
+
+        impl Circuit<Fp> for PhaseCircuit {
+            type Config = PhaseConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column_in(SecondPhase);
+                let theta = meta.challenge_usable_after(FirstPhase);
+                let late = meta.challenge_usable_after(SecondPhase);
+
+                PhaseConfig { a, b, theta, late }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self::default()
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                let synthesized = self.synthesized.fetch_add(1, Ordering::Relaxed);
+                let theta = layouter.get_challenge(config.theta);
+                let late = layouter.get_challenge(config.late);
+
+                layouter.assign_region(
+                    || "phases",
+                    |mut region| {
+                        if self.overwrite {
+                            region.assign_advice(
+                                || "a",
+                                config.a,
+                                0,
+                                || Value::known(Fp::zero()),
+                            )?;
+                        }
+                        let a = if self.reassign { synthesized } else { 1 };
+                        region.assign_advice(|| "a", config.a, 0, || Value::known(Fp::from(a)))?;
+                        let b = if self.use_late_challenge { late } else { theta };
+                        region.assign_advice(|| "b", config.b, 0, || b)?;
+                        Ok(())
+                    },
+                )?;
+
+                if self.assign_unknown {
+                    layouter.assign_region(
+                        || "unknown",
+                        |mut region| {
+                            region.assign_advice(|| "a", config.a, 0, Value::<Fp>::unknown)?;
+                            Ok(())
+                        },
+                    )?;
+                }
+
+                Ok(())
+            }
+        }
+
+        let prover = MockProver::run(K, &PhaseCircuit::default(), vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let circuit = PhaseCircuit {
+            reassign: true,
+            ..Default::default()
+        };
+        let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+        assert_eq!(
+            prover.verify(),
+            Err(vec![VerifyFailure::PhaseViolation {
+                column: (
+                    Any::Advice(Advice {
+                        phase: FirstPhase.to_sealed()
+                    }),
+                    0
+                )
+                    .into(),
+                row: 0,
+                phase: 1,
+                kind: PhaseViolationKind::Reassigned,
+            }])
+        );
+
+        let circuit = PhaseCircuit {
+            use_late_challenge: true,
+            ..Default::default()
+        };
+        let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert_eq!(
+            failures,
+            vec![VerifyFailure::PhaseViolation {
+                column: (
+                    Any::Advice(Advice {
+                        phase: SecondPhase.to_sealed()
+                    }),
+                    1
+                )
+                    .into(),
+                row: 0,
+                phase: 1,
+                kind: PhaseViolationKind::UnavailableChallenge,
+            }]
+        );
+        assert_eq!(
+            failures[0].to_string(),
+            "Cell (Column('Advice { phase: Phase(1) }', 1), row 0) was assigned in phase 1 a value that depends on a challenge that is not yet available"
+        );
+
+        // Overwriting a cell is not a violation, although its first value differs from
+        // the value it ends up with in its phase.
+        let circuit = PhaseCircuit {
+            overwrite: true,
+            ..Default::default()
+        };
+        let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // The unavailable challenge queried before the first region does not account
+        // for an unknown value in the second one.
+        let circuit = PhaseCircuit {
+            assign_unknown: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            MockProver::run(K, &circuit, vec![]),
+            Err(Error::Synthesis)
+        ));
+    }
+
+    #[test]
+    fn four_phases() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct RoundsConfig {
+            columns: [Column<Advice>; 4],
+            challenges: [Challenge; 3],
+            s: Selector,
+        }
+
+        struct RoundsCircuit;
+
+        impl Circuit<Fp> for RoundsCircuit {
+            type Config = RoundsConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column_in(NthPhase::<0>);
+                let alpha = meta.challenge_usable_after(NthPhase::<0>);
+                let b = meta.advice_column_in(NthPhase::<1>);
+                let beta = meta.challenge_usable_after(NthPhase::<1>);
+                let c = meta.advice_column_in(NthPhase::<2>);
+                let gamma = meta.challenge_usable_after(NthPhase::<2>);
+                let d = meta.advice_column_in(NthPhase::<3>);
+                let s = meta.selector();
+
+                // Each round multiplies the previous one by the challenge squeezed after it.
+                meta.create_gate("rounds", |meta| {
+                    let s = meta.query_selector(s);
+                    let [a, b, c, d] =
+                        [a, b, c, d].map(|column| meta.query_advice(column, Rotation::cur()));
+                    let [alpha, beta, gamma] =
+                        [alpha, beta, gamma].map(|challenge| meta.query_challenge(challenge));
+                    vec![
+                        s.clone() * (b.clone() - a * alpha),
+                        s.clone() * (c.clone() - b * beta),
+                        s * (d - c * gamma),
+                    ]
+                });
+
+                RoundsConfig {
+                    columns: [a, b, c, d],
+                    challenges: [alpha, beta, gamma],
+                    s,
+                }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                let challenges = config
+                    .challenges
+                    .map(|challenge| layouter.get_challenge(challenge));
+
+                layouter.assign_region(
+                    || "rounds",
+                    |mut region| {
+                        config.s.enable(&mut region, 0)?;
+                        let mut value = Value::known(Fp::from(3));
+                        region.assign_advice(|| "a", config.columns[0], 0, || value)?;
+                        for (column, challenge) in config.columns[1..].iter().zip(challenges) {
+                            value = value * challenge;
+                            region.assign_advice(|| "round", *column, 0, || value)?;
+                        }
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &RoundsCircuit, vec![]).unwrap();
+        assert_eq!(prover.cs.phases().count(), 4);
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn reduced_gate_degree() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct PowerConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            s: Selector,
+        }
+
+        struct PowerCircuit {
+            a: u64,
+            b: u64,
+        }
+
+        impl Circuit<Fp> for PowerCircuit {
+            type Config = PowerConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let s = meta.selector();
+                meta.set_max_gate_degree(3);
+
+                meta.create_gate("fifth power", |meta| {
+                    let a = meta.query_advice(a, Rotation::cur());
+                    let b = meta.query_advice(b, Rotation::cur());
+                    let s = meta.query_selector(s);
+                    vec![s * (a.clone() * a.clone() * a.clone() * a.clone() * a - b)]
+                });
+
+                PowerConfig { a, b, s }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self { a: 0, b: 0 }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "power",
+                    |mut region| {
+                        config.s.enable(&mut region, 0)?;
+                        region.assign_advice(
+                            || "a",
+                            config.a,
+                            0,
+                            || Value::known(Fp::from(self.a)),
+                        )?;
+                        region.assign_advice(
+                            || "b",
+                            config.b,
+                            0,
+                            || Value::known(Fp::from(self.b)),
+                        )?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        // The intermediate columns are filled in by the prover.
+        let prover = MockProver::run(K, &PowerCircuit { a: 2, b: 32 }, vec![]).unwrap();
+        assert_eq!(prover.cs.degree(), 3);
+        assert_eq!(prover.cs.intermediate_columns().len(), 3);
+        assert_eq!(prover.verify(), Ok(()));
+
+        let prover = MockProver::run(K, &PowerCircuit { a: 2, b: 31 }, vec![]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert_eq!(failures.len(), 1);
+        assert!(matches!(
+            failures[0],
+            VerifyFailure::ConstraintNotSatisfied { .. }
+        ));
+    }
+
+    #[test]
+    fn tagged_tables() {
+        const K: u32 = 5;
+
+        #[derive(Clone)]
+        struct TaggedConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            q_bit: Selector,
+            q_square: Selector,
+            bits: TaggedTable,
+            squares: TaggedTable,
+            value: TableColumn,
+            square: TableColumn,
+        }
+
+        struct TaggedCircuit {
+            bit: u64,
+            root: u64,
+        }
+
+        impl Circuit<Fp> for TaggedCircuit {
+            type Config = TaggedConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let q_bit = meta.complex_selector();
+                let q_square = meta.complex_selector();
+                let tag = meta.lookup_table_column();
+                let value = meta.lookup_table_column();
+                let square = meta.lookup_table_column();
+                let bits = meta.tagged_lookup_table(tag);
+                let squares = meta.tagged_lookup_table(tag);
+
+                meta.lookup_tagged("bit", bits, |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let q = cells.query_selector(q_bit);
+                    (q.clone(), vec![(q * a, value)])
+                });
+                meta.lookup_tagged("square", squares, |cells| {
+                    let a = cells.query_advice(a, Rotation::cur());
+                    let b = cells.query_advice(b, Rotation::cur());
+                    let q = cells.query_selector(q_square);
+                    (q.clone(), vec![(q.clone() * a, value), (q * b, square)])
+                });
+
+                TaggedConfig {
+                    a,
+                    b,
+                    q_bit,
+                    q_square,
+                    bits,
+                    squares,
+                    value,
+                    square,
+                }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self { bit: 0, root: 0 }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_tagged_table(
+                    || "bits",
+                    config.bits,
+                    |mut table| {
+                        for i in 0..2 {
+                            table.assign_cell(
+                                || "bit",
+                                config.value,
+                                i,
+                                || Value::known(Fp::from(i as u64)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )?;
+                layouter.assign_tagged_table(
+                    || "squares",
+                    config.squares,
+                    |mut table| {
+                        for i in 0..4 {
+                            table.assign_cell(
+                                || "value",
+                                config.value,
+                                i,
+                                || Value::known(Fp::from(i as u64)),
+                            )?;
+                            table.assign_cell(
+                                || "square",
+                                config.square,
+                                i,
+                                || Value::known(Fp::from((i * i) as u64)),
+                            )?;
+                        }
+                        Ok(())
+                    },
+                )?;
+
+                layouter.assign_region(
+                    || "inputs",
+                    |mut region| {
+                        config.q_bit.enable(&mut region, 0)?;
+                        region.assign_advice(
+                            || "bit",
+                            config.a,
+                            0,
+                            || Value::known(Fp::from(self.bit)),
+                        )?;
+                        config.q_square.enable(&mut region, 1)?;
+                        region.assign_advice(
+                            || "root",
+                            config.a,
+                            1,
+                            || Value::known(Fp::from(self.root)),
+                        )?;
+                        region.assign_advice(
+                            || "square",
+                            config.b,
+                            1,
+                            || Value::known(Fp::from(self.root * self.root)),
+                        )?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &TaggedCircuit { bit: 1, root: 3 }, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // 2 is in the shared value column, but only in the rows tagged as squares.
+        let prover = MockProver::run(K, &TaggedCircuit { bit: 2, root: 3 }, vec![]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert_eq!(failures.len(), 1);
+        assert!(matches!(
+            failures[0],
+            VerifyFailure::Lookup { name: "bit", .. }
+        ));
+    }
 }
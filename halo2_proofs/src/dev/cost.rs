@@ -2,7 +2,8 @@
 
 use std::ops::Range;
 use std::{
-    collections::{HashMap, HashSet},
+    cmp,
+    collections::{BTreeSet, HashMap, HashSet},
     iter,
     marker::PhantomData,
     ops::{Add, Mul},
@@ -11,11 +12,12 @@ use std::{
 use ff::{Field, PrimeField};
 use group::prime::PrimeGroup;
 
+use super::{json::Json, metadata};
 use crate::{
     circuit::Value,
     plonk::{
         Advice, Any, Assigned, Assignment, Challenge, Circuit, Column, ConstraintSystem, Error,
-        Fixed, FloorPlanner, Instance, Selector,
+        Expression, Fixed, FloorPlanner, Instance, Selector,
     },
     poly::Rotation,
 };
@@ -27,24 +29,121 @@ pub struct CircuitCost<G: PrimeGroup, ConcreteCircuit: Circuit<G::Scalar>> {
     k: usize,
     /// Maximum degree of the circuit.
     max_deg: usize,
-    /// Number of advice columns.
+    /// Number of blinding factors.
+    blinding_factors: usize,
+    /// Number of columns of each type, including the fixed columns that selectors are
+    /// compressed into.
     advice_columns: usize,
+    instance_columns: usize,
+    fixed_columns: usize,
     /// Number of direct queries for each column type.
     instance_queries: usize,
     advice_queries: usize,
     fixed_queries: usize,
-    /// Number of lookup arguments.
-    lookups: usize,
+    /// The custom gates.
+    gates: Vec<GateCost>,
+    /// The lookup arguments.
+    lookups: Vec<LookupCost>,
+    /// The custom arguments.
+    custom_arguments: Vec<CustomArgumentCost>,
     /// Number of columns in the global permutation.
     permutation_cols: usize,
+    /// Degree required by the global permutation.
+    permutation_deg: usize,
     /// Number of distinct sets of points in the multiopening argument.
     point_sets: usize,
+    /// Number of distinct points in the multiopening argument.
+    points: usize,
+    /// Cell usage of each advice and fixed column.
+    columns: Vec<ColumnUsage>,
 
     _marker: PhantomData<(G, ConcreteCircuit)>,
 }
 
+/// The degree and queries of a custom gate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GateCost {
+    /// The name of the gate.
+    pub name: &'static str,
+    /// The degree of each of the gate's constraints.
+    pub constraint_degrees: Vec<usize>,
+    /// The number of distinct cells queried by the gate, not counting selectors.
+    pub queries: usize,
+}
+
+impl GateCost {
+    /// Returns the degree of the gate, which is that of its highest-degree constraint.
+    pub fn degree(&self) -> usize {
+        self.constraint_degrees.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// The degree and queries of a lookup argument.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LookupCost {
+    /// The name of the lookup argument.
+    pub name: &'static str,
+    /// The degree required by the lookup argument.
+    pub degree: usize,
+    /// The number of distinct cells queried by the input and table expressions, not
+    /// counting selectors.
+    pub queries: usize,
+}
+
+/// The degree, columns and evaluations of a custom argument.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CustomArgumentCost {
+    /// The name of the custom argument.
+    pub name: &'static str,
+    /// The degree required by the custom argument.
+    pub degree: usize,
+    /// The number of columns that the prover commits to for the argument.
+    pub columns: usize,
+    /// The number of evaluations of the argument's columns in the proof, one for each
+    /// rotation at which each column is opened.
+    pub evaluations: usize,
+}
+
+/// The number of cells of an advice or fixed column that the circuit uses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnUsage {
+    /// The column.
+    pub column: metadata::Column,
+    /// The annotation of the column, if any.
+    pub annotation: Option<String>,
+    /// The number of usable rows in which a cell of the column is assigned.
+    pub used: usize,
+    /// The number of usable rows in which the column is left unassigned.
+    pub wasted: usize,
+}
+
+/// The multiopening argument used with the KZG commitment scheme.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KzgMultiopen {
+    /// The multiopening argument from [GWC19](https://eprint.iacr.org/2019/953).
+    Gwc,
+    /// The multiopening argument from [BDFG20](https://eprint.iacr.org/2020/081).
+    Shplonk,
+}
+
+impl KzgMultiopen {
+    /// Returns whether the instance columns are opened in the multiopening argument,
+    /// rather than evaluated by the verifier.
+    fn queries_instance(&self) -> bool {
+        match self {
+            KzgMultiopen::Gwc => true,
+            KzgMultiopen::Shplonk => false,
+        }
+    }
+}
+
 struct Assembly {
     selectors: Vec<Vec<bool>>,
+    /// Whether each cell of the advice and fixed columns is assigned.
+    advice: Vec<Vec<bool>>,
+    fixed: Vec<Vec<bool>>,
+    usable_rows: usize,
+    annotations: HashMap<metadata::Column, String>,
 }
 
 impl<F: Field> Assignment<F> for Assembly {
@@ -82,11 +181,158 @@ impl<F: Field> Assignment<F> for Assembly {
         Ok(Value::unknown())
     }
 
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Advice>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.advice[column.index()][row] = true;
+
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Fixed>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.fixed[column.index()][row] = true;
+
+        Ok(())
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        from_row: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        for row in from_row..self.usable_rows {
+            self.fixed[column.index()][row] = true;
+        }
+
+        Ok(())
+    }
+
+    fn get_challenge(&self, _: Challenge) -> Value<F> {
+        Value::unknown()
+    }
+
+    fn usable_rows(&self) -> Option<usize> {
+        Some(self.usable_rows)
+    }
+
+    fn annotate_column<A, AR>(&mut self, annotation: A, column: Column<Any>)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.annotations.insert(column.into(), annotation().into());
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+}
+
+/// An [`Assignment`] that records how many rows the circuit's layout occupies.
+struct RowCounter<F> {
+    instance: Vec<Vec<F>>,
+    /// One more than the largest row used so far.
+    rows: usize,
+}
+
+impl<F> RowCounter<F> {
+    fn use_row(&mut self, row: usize) {
+        self.rows = cmp::max(self.rows, row + 1);
+    }
+}
+
+impl<F: Field> Assignment<F> for RowCounter<F> {
+    fn enter_region<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we only care about the rows that are used.
+    }
+
+    fn exit_region(&mut self) {
+        // Do nothing; we only care about the rows that are used.
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.use_row(row);
+        Ok(())
+    }
+
+    fn fork(&mut self, ranges: &[Range<usize>]) -> Result<Vec<Self>, Error> {
+        // The last sub-range must end strictly before the usable rows do.
+        if let Some(last) = ranges.last() {
+            self.use_row(last.end);
+        }
+
+        Ok(ranges
+            .iter()
+            .map(|_| RowCounter {
+                instance: self.instance.clone(),
+                rows: 0,
+            })
+            .collect())
+    }
+
+    fn merge(&mut self, sub_cs: Vec<Self>) -> Result<(), Error> {
+        for sub_cs in sub_cs {
+            self.rows = cmp::max(self.rows, sub_cs.rows);
+        }
+        Ok(())
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        self.instance
+            .get(column.index())
+            .and_then(|column| column.get(row))
+            .map(|v| Value::known(*v))
+            .ok_or(Error::BoundsFailure)
+    }
+
     fn assign_advice<V, VR, A, AR>(
         &mut self,
         _: A,
         _: Column<Advice>,
-        _: usize,
+        row: usize,
         _: V,
     ) -> Result<(), Error>
     where
@@ -95,6 +341,7 @@ impl<F: Field> Assignment<F> for Assembly {
         A: FnOnce() -> AR,
         AR: Into<String>,
     {
+        self.use_row(row);
         Ok(())
     }
 
@@ -102,7 +349,7 @@ impl<F: Field> Assignment<F> for Assembly {
         &mut self,
         _: A,
         _: Column<Fixed>,
-        _: usize,
+        row: usize,
         _: V,
     ) -> Result<(), Error>
     where
@@ -111,19 +358,30 @@ impl<F: Field> Assignment<F> for Assembly {
         A: FnOnce() -> AR,
         AR: Into<String>,
     {
+        self.use_row(row);
         Ok(())
     }
 
-    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+    fn copy(
+        &mut self,
+        _: Column<Any>,
+        left_row: usize,
+        _: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.use_row(left_row);
+        self.use_row(right_row);
         Ok(())
     }
 
     fn fill_from_row(
         &mut self,
         _: Column<Fixed>,
-        _: usize,
+        from_row: usize,
         _: Value<Assigned<F>>,
     ) -> Result<(), Error> {
+        // Lookup tables are padded from the first unused row, which must be usable.
+        self.use_row(from_row);
         Ok(())
     }
 
@@ -152,6 +410,43 @@ impl<F: Field> Assignment<F> for Assembly {
     }
 }
 
+/// Returns the smallest `k` for which `circuit` fits, given its `instance` columns.
+///
+/// The circuit is synthesized once without a row limit, and `k` is chosen so that
+/// every row used by its regions, lookup tables and constants, as well as every
+/// instance value, lies within the usable rows that remain after the blinding factors.
+/// `k` is also large enough for [`ConstraintSystem::minimum_rows`].
+///
+/// Use [`MockProver::run_at_minimum_k`] to also check that the circuit can be
+/// synthesized at this `k`.
+///
+/// [`MockProver::run_at_minimum_k`]: crate::dev::MockProver::run_at_minimum_k
+pub fn minimum_k<F: Field, ConcreteCircuit: Circuit<F>>(
+    circuit: &ConcreteCircuit,
+    instance: &[Vec<F>],
+) -> Result<u32, Error> {
+    let mut cs = ConstraintSystem::default();
+    let config = ConcreteCircuit::configure(&mut cs);
+
+    if instance.len() != cs.num_instance_columns {
+        return Err(Error::InvalidInstances);
+    }
+
+    let mut counter = RowCounter {
+        instance: instance.to_vec(),
+        rows: 0,
+    };
+    ConcreteCircuit::FloorPlanner::synthesize(&mut counter, circuit, config, cs.constants.clone())?;
+
+    let usable_rows = instance
+        .iter()
+        .map(|column| column.len())
+        .fold(counter.rows, cmp::max);
+    let n = cmp::max(usable_rows + cs.blinding_factors() + 1, cs.minimum_rows());
+
+    Ok(n.next_power_of_two().trailing_zeros())
+}
+
 impl<G: PrimeGroup, ConcreteCircuit: Circuit<G::Scalar>> CircuitCost<G, ConcreteCircuit> {
     /// Measures a circuit with parameter constant `k`.
     ///
@@ -160,8 +455,13 @@ impl<G: PrimeGroup, ConcreteCircuit: Circuit<G::Scalar>> CircuitCost<G, Concrete
         // Collect the layout details.
         let mut cs = ConstraintSystem::default();
         let config = ConcreteCircuit::configure(&mut cs);
+        let usable_rows = (1 << k) - (cs.blinding_factors() + 1);
         let mut assembly = Assembly {
             selectors: vec![vec![false; 1 << k]; cs.num_selectors],
+            advice: vec![vec![false; 1 << k]; cs.num_advice_columns],
+            fixed: vec![vec![false; 1 << k]; cs.num_fixed_columns],
+            usable_rows,
+            annotations: HashMap::new(),
         };
         ConcreteCircuit::FloorPlanner::synthesize(
             &mut assembly,
@@ -206,6 +506,22 @@ impl<G: PrimeGroup, ConcreteCircuit: Circuit<G::Scalar>> CircuitCost<G, Concrete
         point_sets.insert(vec![-1, 0]); // permuted_input_poly
         point_sets.insert(vec![0]); // permuted_table_poly
 
+        // Include the columns of custom arguments in point sets.
+        for argument in cs.custom_arguments.iter() {
+            for column in 0..argument.num_columns {
+                let mut query_set: Vec<_> = argument
+                    .queries
+                    .iter()
+                    .filter(|(queried, _)| *queried == column)
+                    .map(|(_, rotation)| rotation.0)
+                    .collect();
+                query_set.sort_unstable();
+                if !query_set.is_empty() {
+                    point_sets.insert(query_set);
+                }
+            }
+        }
+
         // Include permutation polynomials in point sets.
         point_sets.insert(vec![0, 1]); // permutation_product_poly
         let max_deg = cs.degree();
@@ -215,55 +531,280 @@ impl<G: PrimeGroup, ConcreteCircuit: Circuit<G::Scalar>> CircuitCost<G, Concrete
             point_sets.insert(vec![-((cs.blinding_factors() + 1) as i32), 0, 1]);
         }
 
+        let points = point_sets.iter().flatten().collect::<HashSet<_>>().len();
+
+        let gates = cs
+            .gates
+            .iter()
+            .map(|gate| GateCost {
+                name: gate.name(),
+                constraint_degrees: gate.polynomials().iter().map(|p| p.degree()).collect(),
+                queries: distinct_queries(gate.polynomials()),
+            })
+            .collect();
+
+        let lookups = cs
+            .lookups
+            .iter()
+            .map(|lookup| LookupCost {
+                name: lookup.name,
+                degree: lookup.required_degree(),
+                queries: distinct_queries(
+                    lookup
+                        .input_expressions
+                        .iter()
+                        .chain(lookup.table_expressions.iter()),
+                ),
+            })
+            .collect();
+
+        let custom_arguments = cs
+            .custom_arguments
+            .iter()
+            .map(|argument| CustomArgumentCost {
+                name: argument.name,
+                degree: argument.required_degree(),
+                columns: argument.num_columns,
+                evaluations: argument.queries.len(),
+            })
+            .collect();
+
+        // Report the columns that the circuit assigns, before selectors were compressed
+        // into additional fixed columns.
+        let column_usage = |column_type: Any, index: usize, cells: &[bool]| {
+            let column: metadata::Column = (column_type, index).into();
+            let used = cells[..usable_rows].iter().filter(|&&cell| cell).count();
+            ColumnUsage {
+                column,
+                annotation: assembly
+                    .annotations
+                    .get(&column)
+                    .or_else(|| cs.general_column_annotations.get(&column))
+                    .cloned(),
+                used,
+                wasted: usable_rows - used,
+            }
+        };
+        let columns = iter::empty()
+            .chain(assembly.advice.iter().enumerate().map(|(index, cells)| {
+                let phase = cs.advice_column_phase[index];
+                column_usage(Any::Advice(Advice { phase }), index, cells)
+            }))
+            .chain(
+                assembly
+                    .fixed
+                    .iter()
+                    .enumerate()
+                    .map(|(index, cells)| column_usage(Any::Fixed, index, cells)),
+            )
+            .collect();
+
         CircuitCost {
             k,
             max_deg,
+            blinding_factors: cs.blinding_factors(),
             advice_columns: cs.num_advice_columns,
+            instance_columns: cs.num_instance_columns,
+            fixed_columns: cs.num_fixed_columns,
             instance_queries: cs.instance_queries.len(),
             advice_queries: cs.advice_queries.len(),
             fixed_queries: cs.fixed_queries.len(),
-            lookups: cs.lookups.len(),
+            gates,
+            lookups,
+            custom_arguments,
             permutation_cols,
+            permutation_deg: cs.permutation.required_degree(),
             point_sets: point_sets.len(),
+            points,
+            columns,
             _marker: PhantomData::default(),
         }
     }
 
+    /// Returns the degree and queries of each custom gate.
+    pub fn gates(&self) -> &[GateCost] {
+        &self.gates
+    }
+
+    /// Returns the degree and queries of each lookup argument.
+    pub fn lookups(&self) -> &[LookupCost] {
+        &self.lookups
+    }
+
+    /// Returns the degree, columns and evaluations of each custom argument.
+    pub fn custom_arguments(&self) -> &[CustomArgumentCost] {
+        &self.custom_arguments
+    }
+
+    /// Returns the number of cells used and wasted in each advice and fixed column.
+    pub fn column_usage(&self) -> &[ColumnUsage] {
+        &self.columns
+    }
+
+    /// Returns the names of the gates, lookup arguments and custom arguments that set
+    /// the maximum degree of the circuit. `"permutation"` is included if the global permutation does.
+    ///
+    /// This is empty if the maximum degree was set with
+    /// [`ConstraintSystem::set_minimum_degree`].
+    pub fn max_degree_sources(&self) -> Vec<&'static str> {
+        iter::empty()
+            .chain(
+                self.gates
+                    .iter()
+                    .filter(|gate| gate.degree() == self.max_deg)
+                    .map(|gate| gate.name),
+            )
+            .chain(
+                self.lookups
+                    .iter()
+                    .filter(|lookup| lookup.degree == self.max_deg)
+                    .map(|lookup| lookup.name),
+            )
+            .chain(
+                self.custom_arguments
+                    .iter()
+                    .filter(|argument| argument.degree == self.max_deg)
+                    .map(|argument| argument.name),
+            )
+            .chain((self.permutation_deg == self.max_deg).then(|| "permutation"))
+            .collect()
+    }
+
     fn permutation_chunks(&self) -> usize {
         let chunk_size = self.max_deg - 2;
         (self.permutation_cols + chunk_size - 1) / chunk_size
     }
 
+    /// Returns `k` of the extended domain on which the quotient polynomial is evaluated.
+    fn extended_k(&self) -> usize {
+        let n = 1 << self.k;
+        let quotient_poly_degree = self.max_deg - 1;
+        let mut extended_k = self.k;
+        while (1 << extended_k) < n * quotient_poly_degree {
+            extended_k += 1;
+        }
+        extended_k
+    }
+
     /// Returns the marginal proof size per instance of this circuit.
+    ///
+    /// This assumes that the multiopening argument queries the instance columns, as it
+    /// does with the IPA commitment scheme. See [`Self::kzg_marginal_proof_size`] for
+    /// the KZG commitment scheme.
     pub fn marginal_proof_size(&self) -> MarginalProofSize<G> {
+        self.marginal_proof_size_with(true)
+    }
+
+    /// Returns the marginal proof size per instance of this circuit, when it is proven
+    /// with the KZG commitment scheme and the given multiopening argument.
+    pub fn kzg_marginal_proof_size(&self, multiopen: KzgMultiopen) -> MarginalProofSize<G> {
+        self.marginal_proof_size_with(multiopen.queries_instance())
+    }
+
+    fn marginal_proof_size_with(&self, query_instance: bool) -> MarginalProofSize<G> {
         let chunks = self.permutation_chunks();
+        let lookups = self.lookups.len();
+        let (custom_argument_columns, custom_argument_evaluations) =
+            self.custom_argument_columns_and_evaluations();
 
         MarginalProofSize {
             // Cells:
             // - 1 commitment per advice column per instance
-            // - 1 eval per instance column query per instance
+            // - 1 eval per instance column query per instance, if the multiopening
+            //   argument queries the instance columns (otherwise the verifier evaluates
+            //   them itself)
             // - 1 eval per advice column query per instance
-            instance: ProofContribution::new(0, self.instance_queries),
+            instance: ProofContribution::new(
+                0,
+                if query_instance {
+                    self.instance_queries
+                } else {
+                    0
+                },
+            ),
             advice: ProofContribution::new(self.advice_columns, self.advice_queries),
 
             // Lookup arguments:
             // - 3 commitments per lookup argument per instance
             // - 5 evals per lookup argument per instance
-            lookups: ProofContribution::new(3 * self.lookups, 5 * self.lookups),
+            lookups: ProofContribution::new(3 * lookups, 5 * lookups),
 
             // Global permutation argument:
             // - chunks commitments per instance
             // - 2*chunks + (chunks - 1) evals per instance
-            equality: ProofContribution::new(chunks, 3 * chunks - 1),
+            equality: ProofContribution::new(chunks, (3 * chunks).saturating_sub(1)),
+
+            // Custom arguments:
+            // - 1 commitment per column per instance
+            // - 1 eval per column rotation per instance
+            custom_arguments: ProofContribution::new(
+                custom_argument_columns,
+                custom_argument_evaluations,
+            ),
 
             _marker: PhantomData::default(),
         }
     }
 
+    /// Returns the total number of columns and of column evaluations of the custom
+    /// arguments.
+    fn custom_argument_columns_and_evaluations(&self) -> (usize, usize) {
+        self.custom_arguments
+            .iter()
+            .fold((0, 0), |(columns, evaluations), argument| {
+                (
+                    columns + argument.columns,
+                    evaluations + argument.evaluations,
+                )
+            })
+    }
+
     /// Returns the proof size for the given number of instances of this circuit.
     pub fn proof_size(&self, instances: usize) -> ProofSize<G> {
-        let marginal = self.marginal_proof_size();
+        self.proof_size_with(
+            instances,
+            self.marginal_proof_size(),
+            // Multiopening argument:
+            // - f_commitment
+            // - 1 eval per set of points in multiopen argument
+            ProofContribution::new(1, self.point_sets),
+            // Polycommit:
+            // - s_poly commitment
+            // - inner product argument (2 * k round commitments)
+            // - a
+            // - xi
+            ProofContribution::new(1 + 2 * self.k, 2),
+        )
+    }
 
+    /// Returns the proof size for the given number of instances of this circuit, when
+    /// it is proven with the KZG commitment scheme and the given multiopening argument.
+    pub fn kzg_proof_size(&self, instances: usize, multiopen: KzgMultiopen) -> ProofSize<G> {
+        self.proof_size_with(
+            instances,
+            self.kzg_marginal_proof_size(multiopen),
+            match multiopen {
+                // Multiopening argument:
+                // - 1 witness commitment per distinct point
+                KzgMultiopen::Gwc => ProofContribution::new(self.points, 0),
+                // Multiopening argument:
+                // - h commitment
+                // - linearisation witness commitment
+                KzgMultiopen::Shplonk => ProofContribution::new(2, 0),
+            },
+            // Polycommit:
+            // - nothing beyond the multiopening argument
+            ProofContribution::new(0, 0),
+        )
+    }
+
+    fn proof_size_with(
+        &self,
+        instances: usize,
+        marginal: MarginalProofSize<G>,
+        multiopen: ProofContribution,
+        polycomm: ProofContribution,
+    ) -> ProofSize<G> {
         ProofSize {
             // Cells:
             // - marginal cost per instance
@@ -282,26 +823,232 @@ impl<G: PrimeGroup, ConcreteCircuit: Circuit<G::Scalar>> CircuitCost<G, Concrete
             equality: marginal.equality * instances
                 + ProofContribution::new(0, self.permutation_cols),
 
+            // Custom arguments:
+            // - marginal cost per instance
+            custom_arguments: marginal.custom_arguments * instances,
+
             // Vanishing argument:
             // - 1 + (max_deg - 1) commitments
             // - 1 random_poly eval
             vanishing: ProofContribution::new(self.max_deg, 1),
 
-            // Multiopening argument:
-            // - f_commitment
-            // - 1 eval per set of points in multiopen argument
-            multiopen: ProofContribution::new(1, self.point_sets),
-
-            // Polycommit:
-            // - s_poly commitment
-            // - inner product argument (2 * k round commitments)
-            // - a
-            // - xi
-            polycomm: ProofContribution::new(1 + 2 * self.k, 2),
+            multiopen,
+            polycomm,
 
             _marker: PhantomData::default(),
         }
     }
+
+    /// Returns an estimate, in bytes, of the prover's peak memory use while it evaluates
+    /// the quotient polynomial for the given number of instances of this circuit.
+    ///
+    /// The quotient polynomial is evaluated on the extended domain one coset of `2^k`
+    /// points at a time. At the peak, the prover holds every polynomial in coefficient
+    /// form, the current coset of each polynomial needed by the gates, the permutation
+    /// argument and a single lookup argument, and the quotient polynomial's values on
+    /// the whole extended domain.
+    pub fn prover_memory(&self, instances: usize) -> usize {
+        let n = 1 << self.k;
+        let scalar = <G::Scalar as PrimeField>::Repr::default().as_ref().len();
+        let chunks = self.permutation_chunks();
+        let lookups = self.lookups.len();
+        let (custom_argument_columns, _) = self.custom_argument_columns_and_evaluations();
+
+        // - fixed, permutation, l_0, l_last and l_active_row polynomials
+        // - advice, instance, permutation product, lookup and custom argument
+        //   polynomials per instance
+        let coefficients = self.fixed_columns
+            + self.permutation_cols
+            + 3
+            + instances
+                * (self.advice_columns
+                    + self.instance_columns
+                    + chunks
+                    + 3 * lookups
+                    + custom_argument_columns);
+
+        // - fixed, l_0, l_last and l_active_row cosets
+        // - advice and instance cosets per instance
+        // - permutation and permutation product cosets
+        // - product, permuted input and permuted table cosets of one lookup
+        // - column cosets of every custom argument
+        // - the values being accumulated
+        let cosets = self.fixed_columns
+            + 3
+            + instances * (self.advice_columns + self.instance_columns)
+            + self.permutation_cols
+            + chunks
+            + 3 * cmp::min(lookups, 1)
+            + custom_argument_columns
+            + 1;
+
+        ((coefficients + cosets) * n + (1 << self.extended_k())) * scalar
+    }
+
+    /// Returns everything measured about this circuit as pretty-printed JSON, including
+    /// the proof sizes and prover memory for the given number of instances.
+    pub fn to_json(&self, instances: usize) -> String {
+        let max_degree_sources = self.max_degree_sources();
+
+        let json = Json::Object(vec![
+            ("k", self.k.into()),
+            ("extended_k", self.extended_k().into()),
+            ("max_degree", self.max_deg.into()),
+            ("max_degree_sources", max_degree_sources.into()),
+            ("blinding_factors", self.blinding_factors.into()),
+            (
+                "columns",
+                Json::Object(vec![
+                    ("advice", self.advice_columns.into()),
+                    ("instance", self.instance_columns.into()),
+                    ("fixed", self.fixed_columns.into()),
+                    ("permutation", self.permutation_cols.into()),
+                ]),
+            ),
+            (
+                "queries",
+                Json::Object(vec![
+                    ("advice", self.advice_queries.into()),
+                    ("instance", self.instance_queries.into()),
+                    ("fixed", self.fixed_queries.into()),
+                ]),
+            ),
+            (
+                "gates",
+                Json::Array(
+                    self.gates
+                        .iter()
+                        .map(|gate| {
+                            Json::Object(vec![
+                                ("name", gate.name.into()),
+                                ("degree", gate.degree().into()),
+                                ("constraint_degrees", gate.constraint_degrees.clone().into()),
+                                ("queries", gate.queries.into()),
+                                ("sets_max_degree", (gate.degree() == self.max_deg).into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "lookups",
+                Json::Array(
+                    self.lookups
+                        .iter()
+                        .map(|lookup| {
+                            Json::Object(vec![
+                                ("name", lookup.name.into()),
+                                ("degree", lookup.degree.into()),
+                                ("queries", lookup.queries.into()),
+                                ("sets_max_degree", (lookup.degree == self.max_deg).into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "custom_arguments",
+                Json::Array(
+                    self.custom_arguments
+                        .iter()
+                        .map(|argument| {
+                            Json::Object(vec![
+                                ("name", argument.name.into()),
+                                ("degree", argument.degree.into()),
+                                ("columns", argument.columns.into()),
+                                ("evaluations", argument.evaluations.into()),
+                                ("sets_max_degree", (argument.degree == self.max_deg).into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "permutation",
+                Json::Object(vec![
+                    ("degree", self.permutation_deg.into()),
+                    ("chunks", self.permutation_chunks().into()),
+                ]),
+            ),
+            (
+                "column_usage",
+                Json::Array(
+                    self.columns
+                        .iter()
+                        .map(|usage| {
+                            Json::Object(vec![
+                                ("column", usage.column.into()),
+                                ("annotation", usage.annotation.clone().into()),
+                                ("used", usage.used.into()),
+                                ("wasted", usage.wasted.into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            ("instances", instances.into()),
+            (
+                "proof_size",
+                Json::Object(vec![
+                    ("ipa", self.proof_size(instances).to_json()),
+                    (
+                        "kzg_gwc",
+                        self.kzg_proof_size(instances, KzgMultiopen::Gwc).to_json(),
+                    ),
+                    (
+                        "kzg_shplonk",
+                        self.kzg_proof_size(instances, KzgMultiopen::Shplonk)
+                            .to_json(),
+                    ),
+                ]),
+            ),
+            ("prover_memory", self.prover_memory(instances).into()),
+        ]);
+
+        format!("{:#}", json)
+    }
+}
+
+/// Returns the number of distinct cells queried by the given expressions, not counting
+/// selectors.
+fn distinct_queries<'a, F: Field + 'a>(
+    expressions: impl IntoIterator<Item = &'a Expression<F>>,
+) -> usize {
+    let cell = |column_type: Any, column_index: usize, rotation: Rotation| {
+        let column: metadata::Column = (column_type, column_index).into();
+        iter::once((column, rotation.0)).collect::<BTreeSet<_>>()
+    };
+
+    expressions
+        .into_iter()
+        .flat_map(|expression| {
+            expression.evaluate(
+                &|_| BTreeSet::default(),
+                &|_| BTreeSet::default(),
+                &|query| cell(Any::Fixed, query.column_index, query.rotation),
+                &|query| {
+                    cell(
+                        Any::Advice(Advice { phase: query.phase }),
+                        query.column_index,
+                        query.rotation,
+                    )
+                },
+                &|query| cell(Any::Instance, query.column_index, query.rotation),
+                &|_| BTreeSet::default(),
+                &|a| a,
+                &|mut a, mut b| {
+                    a.append(&mut b);
+                    a
+                },
+                &|mut a, mut b| {
+                    a.append(&mut b);
+                    a
+                },
+                &|a, _| a,
+            )
+        })
+        .collect::<BTreeSet<_>>()
+        .len()
 }
 
 /// (commitments, evaluations)
@@ -322,6 +1069,13 @@ impl ProofContribution {
     fn len(&self, point: usize, scalar: usize) -> usize {
         self.commitments * point + self.evaluations * scalar
     }
+
+    fn to_json(&self) -> Json {
+        Json::Object(vec![
+            ("commitments", self.commitments.into()),
+            ("evaluations", self.evaluations.into()),
+        ])
+    }
 }
 
 impl Add for ProofContribution {
@@ -353,6 +1107,7 @@ pub struct MarginalProofSize<G: PrimeGroup> {
     advice: ProofContribution,
     lookups: ProofContribution,
     equality: ProofContribution,
+    custom_arguments: ProofContribution,
     _marker: PhantomData<G>,
 }
 
@@ -365,6 +1120,7 @@ impl<G: PrimeGroup> From<MarginalProofSize<G>> for usize {
             + proof.advice.len(point, scalar)
             + proof.lookups.len(point, scalar)
             + proof.equality.len(point, scalar)
+            + proof.custom_arguments.len(point, scalar)
     }
 }
 
@@ -376,12 +1132,31 @@ pub struct ProofSize<G: PrimeGroup> {
     fixed: ProofContribution,
     lookups: ProofContribution,
     equality: ProofContribution,
+    custom_arguments: ProofContribution,
     vanishing: ProofContribution,
     multiopen: ProofContribution,
     polycomm: ProofContribution,
     _marker: PhantomData<G>,
 }
 
+impl<G: PrimeGroup> ProofSize<G> {
+    fn to_json(self) -> Json {
+        let mut fields = vec![
+            ("instance", self.instance.to_json()),
+            ("advice", self.advice.to_json()),
+            ("fixed", self.fixed.to_json()),
+            ("lookups", self.lookups.to_json()),
+            ("equality", self.equality.to_json()),
+            ("custom_arguments", self.custom_arguments.to_json()),
+            ("vanishing", self.vanishing.to_json()),
+            ("multiopen", self.multiopen.to_json()),
+            ("polycomm", self.polycomm.to_json()),
+        ];
+        fields.push(("bytes", usize::from(self).into()));
+        Json::Object(fields)
+    }
+}
+
 impl<G: PrimeGroup> From<ProofSize<G>> for usize {
     fn from(proof: ProofSize<G>) -> Self {
         let point = G::Repr::default().as_ref().len();
@@ -392,6 +1167,7 @@ impl<G: PrimeGroup> From<ProofSize<G>> for usize {
             + proof.fixed.len(point, scalar)
             + proof.lookups.len(point, scalar)
             + proof.equality.len(point, scalar)
+            + proof.custom_arguments.len(point, scalar)
             + proof.vanishing.len(point, scalar)
             + proof.multiopen.len(point, scalar)
             + proof.polycomm.len(point, scalar)
@@ -4,16 +4,13 @@ use std::fmt::{self, Debug};
 use group::ff::Field;
 use halo2curves::FieldExt;
 
+use super::json::Json;
 use super::metadata::{DebugColumn, DebugVirtualCell};
 use super::MockProver;
-use super::{
-    metadata,
-    util::{self, AnyQuery},
-    Region,
-};
+use super::{metadata, util, Region};
 use crate::dev::metadata::Constraint;
 use crate::{
-    dev::{Instance, Value},
+    dev::Instance,
     plonk::{Any, Column, ConstraintSystem, Expression, Gate},
     poly::Rotation,
 };
@@ -50,6 +47,14 @@ impl fmt::Display for FailureLocation {
 }
 
 impl FailureLocation {
+    /// Returns the region in which the failure occurred, if any.
+    fn region(&self) -> Option<&metadata::Region> {
+        match self {
+            Self::InRegion { region, .. } => Some(region),
+            Self::OutsideRegion { .. } => None,
+        }
+    }
+
     /// Returns a `DebugColumn` from Column metadata and `&self`.
     pub(super) fn get_debug_column(&self, metadata: metadata::Column) -> DebugColumn {
         match self {
@@ -114,7 +119,7 @@ impl FailureLocation {
                 (start..=end).contains(&failure_row) && !failure_columns.is_disjoint(&r.columns)
             })
             .map(|(r_i, r)| FailureLocation::InRegion {
-                region: (r_i, r.name.clone(), r.annotations.clone()).into(),
+                region: r.metadata(r_i),
                 offset: failure_row - r.rows.unwrap().0,
             })
             .unwrap_or_else(|| FailureLocation::OutsideRegion { row: failure_row })
@@ -178,6 +183,13 @@ pub enum VerifyFailure {
         /// - The input expressions use a column queried at a non-zero `Rotation`, and the
         ///   lookup is active on a row adjacent to an unrelated region.
         location: FailureLocation,
+        /// The input tuple, as evaluated at the failing row.
+        input: Vec<String>,
+        /// The values of the virtual cells queried by the input expressions.
+        cell_values: Vec<(metadata::VirtualCell, String)>,
+        /// The table rows adjacent to `input` in the sorted table: the greatest tuple
+        /// below it and the least tuple above it, where they exist. Rows are absolute.
+        nearest_table_rows: Vec<(usize, Vec<String>)>,
     },
     /// A permutation did not preserve the original value of a cell.
     Permutation {
@@ -186,6 +198,51 @@ pub enum VerifyFailure {
         /// The location at which the permutation is not satisfied.
         location: FailureLocation,
     },
+    /// A constraint of a custom argument was not satisfied for a particular row.
+    CustomArgument {
+        /// The name of the custom argument that is not satisfied.
+        name: &'static str,
+        /// The index of the custom argument that is not satisfied. These indices are
+        /// assigned in the order in which `ConstraintSystem::custom_argument` and
+        /// `ConstraintSystem::custom_argument_in` are called during `Circuit::configure`.
+        argument_index: usize,
+        /// The index of the constraint within the custom argument.
+        constraint_index: usize,
+        /// The location at which the constraint is not satisfied. This is the region
+        /// whose columns the inputs of the argument query, if any.
+        location: FailureLocation,
+    },
+    /// A custom argument failed to compute its columns from the witness.
+    CustomArgumentColumns {
+        /// The name of the custom argument.
+        name: &'static str,
+        /// The index of the custom argument.
+        argument_index: usize,
+    },
+    /// An advice cell was assigned in violation of its column's phase.
+    PhaseViolation {
+        /// The column containing the cell.
+        column: metadata::Column,
+        /// The row of the cell.
+        row: usize,
+        /// The phase in which the cell was assigned.
+        phase: u8,
+        /// How the assignment violates the column's phase.
+        kind: PhaseViolationKind,
+    },
+}
+
+/// The ways in which an advice cell can be assigned in violation of its column's phase,
+/// as reported by [`VerifyFailure::PhaseViolation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PhaseViolationKind {
+    /// The cell was last assigned a different value in a later phase than in its own
+    /// phase, after its column had been committed to.
+    Reassigned,
+    /// The cell was assigned an unknown value in the region that queried a challenge
+    /// that is not yet available (or in the region after such a query made outside of
+    /// any region).
+    UnavailableChallenge,
 }
 
 impl fmt::Display for VerifyFailure {
@@ -235,12 +292,28 @@ impl fmt::Display for VerifyFailure {
                 name,
                 lookup_index,
                 location,
+                input,
+                cell_values,
+                nearest_table_rows,
             } => {
-                write!(
+                writeln!(
                     f,
                     "Lookup {}(index: {}) is not satisfied {}",
                     name, lookup_index, location
-                )
+                )?;
+                writeln!(f, "- input = ({})", input.join(", "))?;
+                let ann_map = match location {
+                    FailureLocation::InRegion { region, offset: _ } => &region.column_annotations,
+                    _ => &None,
+                };
+                for (vc, value) in cell_values {
+                    let dvc = DebugVirtualCell::from((vc, ann_map.as_ref()));
+                    writeln!(f, "- {} = {}", dvc, value)?;
+                }
+                for (row, values) in nearest_table_rows {
+                    writeln!(f, "- table row {} = ({})", row, values.join(", "))?;
+                }
+                Ok(())
             }
             Self::Permutation { column, location } => {
                 write!(
@@ -250,6 +323,47 @@ impl fmt::Display for VerifyFailure {
                     location
                 )
             }
+            Self::CustomArgument {
+                name,
+                argument_index,
+                constraint_index,
+                location,
+            } => write!(
+                f,
+                "Constraint {} of custom argument {}(index: {}) is not satisfied {}",
+                constraint_index, name, argument_index, location
+            ),
+            Self::CustomArgumentColumns {
+                name,
+                argument_index,
+            } => write!(
+                f,
+                "Custom argument {}(index: {}) failed to compute its columns",
+                name, argument_index
+            ),
+            Self::PhaseViolation {
+                column,
+                row,
+                phase,
+                kind,
+            } => match kind {
+                PhaseViolationKind::Reassigned => {
+                    let column_phase = match column.column_type {
+                        Any::Advice(advice) => advice.phase.0,
+                        _ => unreachable!("only advice columns have phases"),
+                    };
+                    write!(
+                        f,
+                        "Cell ({}, row {}) from phase {} was assigned a different value in phase {}",
+                        column, row, column_phase, phase
+                    )
+                }
+                PhaseViolationKind::UnavailableChallenge => write!(
+                    f,
+                    "Cell ({}, row {}) was assigned in phase {} a value that depends on a challenge that is not yet available",
+                    column, row, phase
+                ),
+            },
         }
     }
 }
@@ -443,12 +557,20 @@ fn render_constraint_not_satisfied<F: Field>(
 ///     | Assigned cell values:
 ///     |   x0 = 0x5
 ///     |   x1 = 1
+///     |
+///     | L0 = 0x5
+///
+///   Nearest table rows:
+///     row 1: (0x4)
+///     row 2: (0x6)
 /// ```
 fn render_lookup<F: FieldExt>(
     prover: &MockProver<F>,
     name: &str,
     lookup_index: usize,
     location: &FailureLocation,
+    input_values: &[String],
+    nearest_table_rows: &[(usize, Vec<String>)],
 ) {
     let n = prover.n as i32;
     let cs = &prover.cs;
@@ -510,28 +632,6 @@ fn render_lookup<F: FieldExt>(
         )
     });
 
-    fn cell_value<'a, F: FieldExt, Q: Into<AnyQuery> + Copy>(
-        load: impl Fn(Q) -> Value<F> + 'a,
-    ) -> impl Fn(Q) -> BTreeMap<metadata::VirtualCell, String> + 'a {
-        move |query| {
-            let AnyQuery {
-                column_type,
-                column_index,
-                rotation,
-                ..
-            } = query.into();
-            Some((
-                ((column_type, column_index).into(), rotation.0).into(),
-                match load(query) {
-                    Value::Real(v) => util::format_value(v),
-                    Value::Poison => unreachable!(),
-                },
-            ))
-            .into_iter()
-            .collect()
-        }
-    }
-
     eprintln!("error: lookup input does not exist in table");
     eprint!("  (");
     for i in 0..lookup.input_expressions.len() {
@@ -547,39 +647,12 @@ fn render_lookup<F: FieldExt>(
     eprintln!();
     eprintln!("  Lookup '{}' inputs:", name);
     for (i, input) in lookup.input_expressions.iter().enumerate() {
-        // Fetch the cell values (since we don't store them in VerifyFailure::Lookup).
-        let cell_values = input.evaluate(
-            &|_| BTreeMap::default(),
-            &|_| panic!("virtual selectors are removed during optimization"),
-            &cell_value(&util::load_slice(
-                n,
-                row,
-                &cs.fixed_queries,
-                prover.fixed.as_slice(),
-            )),
-            &cell_value(&util::load_slice(
-                n,
-                row,
-                &cs.advice_queries,
-                &prover.advice,
-            )),
-            &cell_value(&util::load_instance(
-                n,
-                row,
-                &cs.instance_queries,
-                &prover.instance,
-            )),
-            &|_| BTreeMap::default(),
-            &|a| a,
-            &|mut a, mut b| {
-                a.append(&mut b);
-                a
-            },
-            &|mut a, mut b| {
-                a.append(&mut b);
-                a
-            },
-            &|a, _| a,
+        // Fetch the values of the cells queried by this input.
+        let cell_values = util::expression_cell_values(
+            input,
+            util::load_slice(n, row, &cs.fixed_queries, prover.fixed.as_slice()),
+            util::load_slice(n, row, &cs.advice_queries, &prover.advice),
+            util::load_instance(n, row, &cs.instance_queries, &prover.instance),
         );
 
         // Collect the necessary rendering information:
@@ -619,10 +692,57 @@ fn render_lookup<F: FieldExt>(
         for (i, (_, value)) in cell_values.iter().enumerate() {
             eprintln!("    |   x{} = {}", i, value);
         }
+        eprintln!("    |");
+        eprintln!("    | L{} = {}", i, input_values[i]);
+    }
+
+    // Show where the input would sit in the table, to help tell an off-by-one input
+    // from a missing table row.
+    eprintln!();
+    eprintln!("  Nearest table rows:");
+    if nearest_table_rows.is_empty() {
+        eprintln!("    (table is empty)");
+    }
+    for (row, values) in nearest_table_rows {
+        eprintln!("    row {}: ({})", row, values.join(", "));
     }
 }
 
 impl VerifyFailure {
+    /// Returns the path of namespaces in which the region containing this failure was
+    /// assigned, outermost first.
+    ///
+    /// This is empty for failures that occur outside of any region, and for
+    /// `ConstraintPoisoned`, `CustomArgumentColumns` and `PhaseViolation`, which are not
+    /// tied to a region.
+    pub fn namespace(&self) -> &[String] {
+        let region = match self {
+            Self::CellNotAssigned { region, .. } => Some(region),
+            Self::ConstraintNotSatisfied { location, .. }
+            | Self::Lookup { location, .. }
+            | Self::Permutation { location, .. }
+            | Self::CustomArgument { location, .. } => location.region(),
+            Self::ConstraintPoisoned { .. }
+            | Self::CustomArgumentColumns { .. }
+            | Self::PhaseViolation { .. } => None,
+        };
+        region.map(|region| region.namespace()).unwrap_or(&[])
+    }
+
+    /// Returns `true` if this failure occurred in a region assigned within the given
+    /// namespace path, or within one of its children.
+    ///
+    /// An empty `prefix` matches every failure.
+    pub fn is_in_namespace(&self, prefix: &[&str]) -> bool {
+        let namespace = self.namespace();
+        prefix.len() <= namespace.len() && prefix.iter().zip(namespace.iter()).all(|(a, b)| a == b)
+    }
+
+    /// Returns this failure as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        format!("{:#}", Json::from(self))
+    }
+
     /// Emits this failure in pretty-printed format to stderr.
     pub(super) fn emit<F: FieldExt>(&self, prover: &MockProver<F>) {
         match self {
@@ -651,7 +771,17 @@ impl VerifyFailure {
                 name,
                 lookup_index,
                 location,
-            } => render_lookup(prover, name, *lookup_index, location),
+                input,
+                nearest_table_rows,
+                ..
+            } => render_lookup(
+                prover,
+                name,
+                *lookup_index,
+                location,
+                input,
+                nearest_table_rows,
+            ),
             _ => eprintln!("{}", self),
         }
     }
@@ -52,7 +52,12 @@ pub(super) fn render_cell_layout(
     let offset = match location {
         FailureLocation::InRegion { region, offset } => {
             col_headers
-                .push_str(format!("{}Cell layout in region '{}':\n", prefix, region.name).as_str());
+                .push_str(format!("{}Cell layout in region '{}'", prefix, region.name).as_str());
+            if !region.namespace.is_empty() {
+                col_headers
+                    .push_str(format!(" (namespace '{}')", region.namespace.join("/")).as_str());
+            }
+            col_headers.push_str(":\n");
             col_headers.push_str(format!("{}  | Offset |", prefix).as_str());
             Some(*offset as i32)
         }
@@ -1,23 +1,111 @@
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Write},
 };
 
 use ff::PrimeField;
 
 use crate::{
-    dev::util,
+    arithmetic::FieldExt,
+    dev::{metadata, util, MockProver},
     plonk::{
         sealed::{self, SealedPhase},
-        Circuit, ConstraintSystem, FirstPhase,
+        Advice, Any, Circuit, ConstraintSystem, Expression, FirstPhase,
     },
 };
 
+/// A polynomial, with the constant terms and factors that have no effect removed, that
+/// can be rendered with the names of its columns.
+#[derive(Clone, Debug)]
+enum Expr {
+    Constant(String),
+    Selector(usize),
+    Query(metadata::Column, i32),
+    Challenge(usize),
+    Negated(Box<Expr>),
+    Sum(Box<Expr>, Box<Expr>),
+    Product(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn new<F: PrimeField>(expression: &Expression<F>) -> Self {
+        expression.evaluate(
+            &|c| Expr::Constant(util::format_value(c)),
+            &|selector| Expr::Selector(selector.0),
+            &|query| Expr::Query((Any::Fixed, query.column_index).into(), query.rotation.0),
+            &|query| {
+                Expr::Query(
+                    (
+                        Any::Advice(Advice { phase: query.phase }),
+                        query.column_index,
+                    )
+                        .into(),
+                    query.rotation.0,
+                )
+            },
+            &|query| Expr::Query((Any::Instance, query.column_index).into(), query.rotation.0),
+            &|challenge| Expr::Challenge(challenge.index()),
+            &Expr::negated,
+            &Expr::sum,
+            &Expr::product,
+            &|a, s| Expr::product(a, Expr::Constant(util::format_value(s))),
+        )
+    }
+
+    fn is_constant(&self, value: &str) -> bool {
+        matches!(self, Expr::Constant(c) if c == value)
+    }
+
+    fn negated(a: Expr) -> Expr {
+        match a {
+            Expr::Negated(a) => *a,
+            Expr::Constant(c) if c == "0" => Expr::Constant(c),
+            Expr::Constant(c) if c == "1" => Expr::Constant("-1".into()),
+            Expr::Constant(c) if c == "-1" => Expr::Constant("1".into()),
+            a => Expr::Negated(Box::new(a)),
+        }
+    }
+
+    fn sum(a: Expr, b: Expr) -> Expr {
+        if a.is_constant("0") {
+            b
+        } else if b.is_constant("0") {
+            a
+        } else {
+            Expr::Sum(Box::new(a), Box::new(b))
+        }
+    }
+
+    fn product(a: Expr, b: Expr) -> Expr {
+        if a.is_constant("0") || b.is_constant("0") {
+            Expr::Constant("0".into())
+        } else if a.is_constant("1") {
+            b
+        } else if b.is_constant("1") {
+            a
+        } else if a.is_constant("-1") {
+            Expr::negated(b)
+        } else if b.is_constant("-1") {
+            Expr::negated(a)
+        } else {
+            Expr::Product(Box::new(a), Box::new(b))
+        }
+    }
+}
+
+/// The notation in which polynomials are rendered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Notation {
+    Markdown,
+    Latex,
+}
+
 #[derive(Debug)]
 struct Constraint {
     name: &'static str,
     expression: String,
     queries: BTreeSet<String>,
+    polynomial: Expr,
 }
 
 #[derive(Debug)]
@@ -26,6 +114,13 @@ struct Gate {
     constraints: Vec<Constraint>,
 }
 
+#[derive(Debug)]
+struct Lookup {
+    name: &'static str,
+    inputs: Vec<Expr>,
+    tables: Vec<Expr>,
+}
+
 /// A struct for collecting and displaying the gates within a circuit.
 ///
 /// # Examples
@@ -96,6 +191,12 @@ struct Gate {
 #[derive(Debug)]
 pub struct CircuitGates {
     gates: Vec<Gate>,
+    lookups: Vec<Lookup>,
+    columns: Vec<metadata::Column>,
+    num_selectors: usize,
+    permutation: Vec<metadata::Column>,
+    column_names: BTreeMap<metadata::Column, String>,
+    selector_names: BTreeMap<usize, String>,
     total_negations: usize,
     total_additions: usize,
     total_multiplications: usize,
@@ -207,6 +308,7 @@ impl CircuitGates {
                             },
                             &|a, _| a,
                         ),
+                        polynomial: Expr::new(constraint),
                     })
                     .collect(),
             })
@@ -235,14 +337,331 @@ impl CircuitGates {
                 (acc_n + n, acc_a + a, acc_m + m)
             });
 
+        let lookups = cs
+            .lookups
+            .iter()
+            .map(|lookup| Lookup {
+                name: lookup.name,
+                inputs: lookup.input_expressions().iter().map(Expr::new).collect(),
+                tables: lookup.table_expressions().iter().map(Expr::new).collect(),
+            })
+            .collect();
+
+        let columns: Vec<metadata::Column> = (0..cs.num_instance_columns)
+            .map(|index| (Any::Instance, index).into())
+            .chain(
+                cs.advice_column_phase
+                    .iter()
+                    .enumerate()
+                    .map(|(index, &phase)| (Any::Advice(Advice { phase }), index).into()),
+            )
+            .chain((0..cs.num_fixed_columns).map(|index| (Any::Fixed, index).into()))
+            .collect();
+
         CircuitGates {
             gates,
+            lookups,
+            columns,
+            num_selectors: cs.num_selectors,
+            permutation: cs
+                .permutation
+                .get_columns()
+                .into_iter()
+                .map(metadata::Column::from)
+                .collect(),
+            column_names: cs.general_column_annotations.into_iter().collect(),
+            selector_names: cs.selector_annotations.into_iter().collect(),
             total_negations,
             total_additions,
             total_multiplications,
         }
     }
 
+    /// Names columns using the annotations given to them with [`Region::name_column`]
+    /// while synthesizing the circuit checked by the given prover.
+    ///
+    /// A column is only named this way if it was given the same annotation in every
+    /// region that annotated it, and was not already annotated within
+    /// [`Circuit::configure`].
+    ///
+    /// [`Region::name_column`]: crate::circuit::Region::name_column
+    pub fn with_region_annotations<F: FieldExt>(mut self, prover: &MockProver<F>) -> Self {
+        let mut annotations = BTreeMap::<metadata::Column, BTreeSet<&String>>::new();
+        for region in &prover.regions {
+            for (column, annotation) in &region.annotations {
+                annotations.entry(*column).or_default().insert(annotation);
+            }
+        }
+        for (column, annotations) in annotations {
+            if annotations.len() == 1 {
+                self.column_names
+                    .entry(column)
+                    .or_insert_with(|| annotations.into_iter().next().unwrap().clone());
+            }
+        }
+        self
+    }
+
+    /// Returns a Markdown report of the gates, lookups and equality-constrained columns
+    /// of this circuit.
+    ///
+    /// Columns and selectors are referred to by their annotations, where they have them,
+    /// and rotations are written as `a[next]`, `a[prev]` or `a[2]`. Constant terms and
+    /// factors that have no effect are removed from the polynomials.
+    pub fn to_markdown(&self) -> String {
+        let mut ret = String::new();
+        let w = &mut ret;
+
+        writeln!(w, "## Columns").unwrap();
+        writeln!(w).unwrap();
+        writeln!(w, "| Name | Column |").unwrap();
+        writeln!(w, "| --- | --- |").unwrap();
+        for column in &self.columns {
+            writeln!(
+                w,
+                "| {} | {} |",
+                markdown_code(&self.column_name(column)),
+                column
+            )
+            .unwrap();
+        }
+        for selector in 0..self.num_selectors {
+            writeln!(
+                w,
+                "| {} | Selector {} |",
+                markdown_code(&self.selector_name(selector)),
+                selector
+            )
+            .unwrap();
+        }
+
+        writeln!(w).unwrap();
+        writeln!(w, "## Gates").unwrap();
+        for gate in &self.gates {
+            writeln!(w).unwrap();
+            writeln!(w, "### {}", gate.name).unwrap();
+            writeln!(w).unwrap();
+            writeln!(w, "| # | Constraint | Polynomial |").unwrap();
+            writeln!(w, "| --- | --- | --- |").unwrap();
+            for (i, constraint) in gate.constraints.iter().enumerate() {
+                writeln!(
+                    w,
+                    "| {} | {} | {} |",
+                    i,
+                    constraint.name,
+                    markdown_code(&self.render(&constraint.polynomial, Notation::Markdown)),
+                )
+                .unwrap();
+            }
+        }
+
+        if !self.lookups.is_empty() {
+            writeln!(w).unwrap();
+            writeln!(w, "## Lookups").unwrap();
+            for (i, lookup) in self.lookups.iter().enumerate() {
+                writeln!(w).unwrap();
+                writeln!(w, "### {}", lookup_name(i, lookup)).unwrap();
+                writeln!(w).unwrap();
+                writeln!(w, "| Input | Table |").unwrap();
+                writeln!(w, "| --- | --- |").unwrap();
+                for (input, table) in lookup.inputs.iter().zip(lookup.tables.iter()) {
+                    writeln!(
+                        w,
+                        "| {} | {} |",
+                        markdown_code(&self.render(input, Notation::Markdown)),
+                        markdown_code(&self.render(table, Notation::Markdown)),
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        if !self.permutation.is_empty() {
+            writeln!(w).unwrap();
+            writeln!(w, "## Equality constraints").unwrap();
+            writeln!(w).unwrap();
+            for column in &self.permutation {
+                writeln!(w, "- {}", markdown_code(&self.column_name(column))).unwrap();
+            }
+        }
+
+        ret
+    }
+
+    /// Returns the same report as [`CircuitGates::to_markdown`], as a LaTeX fragment.
+    ///
+    /// Queries are written as evaluations at `X` or a rotation of it, such as
+    /// `\mathrm{a}(\omega X)`. The fragment uses `\subsection*`, `\paragraph` and the
+    /// `tabular` and `itemize` environments, so it can be included in any document.
+    pub fn to_latex(&self) -> String {
+        let mut ret = String::new();
+        let w = &mut ret;
+
+        writeln!(w, "\\subsection*{{Columns}}").unwrap();
+        writeln!(w, "\\begin{{tabular}}{{ll}}").unwrap();
+        writeln!(w, "Name & Column \\\\").unwrap();
+        writeln!(w, "\\hline").unwrap();
+        for column in &self.columns {
+            writeln!(
+                w,
+                "${}$ & {} \\\\",
+                latex_name(&self.column_name(column)),
+                latex_escape(&column.to_string()),
+            )
+            .unwrap();
+        }
+        for selector in 0..self.num_selectors {
+            writeln!(
+                w,
+                "${}$ & Selector {} \\\\",
+                latex_name(&self.selector_name(selector)),
+                selector,
+            )
+            .unwrap();
+        }
+        writeln!(w, "\\end{{tabular}}").unwrap();
+
+        writeln!(w).unwrap();
+        writeln!(w, "\\subsection*{{Gates}}").unwrap();
+        for gate in &self.gates {
+            writeln!(w).unwrap();
+            writeln!(w, "\\paragraph{{{}}}", latex_escape(gate.name)).unwrap();
+            writeln!(w, "\\begin{{itemize}}").unwrap();
+            for constraint in &gate.constraints {
+                if constraint.name.is_empty() {
+                    writeln!(w, "\\item").unwrap();
+                } else {
+                    writeln!(w, "\\item {}", latex_escape(constraint.name)).unwrap();
+                }
+                writeln!(
+                    w,
+                    "\\[ {} = 0 \\]",
+                    self.render(&constraint.polynomial, Notation::Latex)
+                )
+                .unwrap();
+            }
+            writeln!(w, "\\end{{itemize}}").unwrap();
+        }
+
+        if !self.lookups.is_empty() {
+            writeln!(w).unwrap();
+            writeln!(w, "\\subsection*{{Lookups}}").unwrap();
+            for (i, lookup) in self.lookups.iter().enumerate() {
+                let tuple = |expressions: &[Expr]| {
+                    let rendered: Vec<_> = expressions
+                        .iter()
+                        .map(|expr| self.render(expr, Notation::Latex))
+                        .collect();
+                    if rendered.len() == 1 {
+                        rendered[0].clone()
+                    } else {
+                        format!("({})", rendered.join(", "))
+                    }
+                };
+                writeln!(w).unwrap();
+                writeln!(
+                    w,
+                    "\\paragraph{{{}}}",
+                    latex_escape(&lookup_name(i, lookup))
+                )
+                .unwrap();
+                writeln!(
+                    w,
+                    "\\[ {} \\in {} \\]",
+                    tuple(&lookup.inputs),
+                    tuple(&lookup.tables)
+                )
+                .unwrap();
+            }
+        }
+
+        if !self.permutation.is_empty() {
+            writeln!(w).unwrap();
+            writeln!(w, "\\subsection*{{Equality constraints}}").unwrap();
+            writeln!(
+                w,
+                "Equality constraints are enabled on ${}$.",
+                self.permutation
+                    .iter()
+                    .map(|column| latex_name(&self.column_name(column)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+            .unwrap();
+        }
+
+        ret
+    }
+
+    fn column_name(&self, column: &metadata::Column) -> String {
+        self.column_names.get(column).cloned().unwrap_or_else(|| {
+            let prefix = match column.column_type {
+                Any::Advice(_) => "A",
+                Any::Fixed => "F",
+                Any::Instance => "I",
+            };
+            format!("{}{}", prefix, column.index)
+        })
+    }
+
+    fn selector_name(&self, selector: usize) -> String {
+        self.selector_names
+            .get(&selector)
+            .cloned()
+            .unwrap_or_else(|| format!("S{}", selector))
+    }
+
+    /// Renders a polynomial in the given notation.
+    fn render(&self, expr: &Expr, notation: Notation) -> String {
+        let latex = notation == Notation::Latex;
+        // Renders a term that binds more loosely than negation or multiplication.
+        let factor = |expr: &Expr| match expr {
+            Expr::Sum(..) | Expr::Negated(_) => format!("({})", self.render(expr, notation)),
+            _ => self.render(expr, notation),
+        };
+
+        match expr {
+            Expr::Constant(c) if latex && c.starts_with("0x") => format!("\\mathtt{{{}}}", c),
+            Expr::Constant(c) => c.clone(),
+            Expr::Selector(selector) => query(&self.selector_name(*selector), 0, notation),
+            Expr::Query(column, rotation) => query(&self.column_name(column), *rotation, notation),
+            Expr::Challenge(index) if latex => format!("c_{{{}}}", index),
+            Expr::Challenge(index) => format!("C{}", index),
+            Expr::Negated(a) => match **a {
+                Expr::Sum(..) => format!("-({})", self.render(a, notation)),
+                _ => format!("-{}", self.render(a, notation)),
+            },
+            Expr::Sum(a, b) => match &**b {
+                Expr::Negated(b) => match **b {
+                    Expr::Sum(..) => format!(
+                        "{} - ({})",
+                        self.render(a, notation),
+                        self.render(b, notation)
+                    ),
+                    _ => format!(
+                        "{} - {}",
+                        self.render(a, notation),
+                        self.render(b, notation)
+                    ),
+                },
+                Expr::Constant(c) if c.starts_with('-') => {
+                    format!("{} - {}", self.render(a, notation), &c[1..])
+                }
+                b => format!(
+                    "{} + {}",
+                    self.render(a, notation),
+                    self.render(b, notation)
+                ),
+            },
+            Expr::Product(a, b) => format!(
+                "{}{}{}",
+                factor(a),
+                if latex { " \\cdot " } else { " * " },
+                factor(b)
+            ),
+        }
+    }
+
     /// Prints the queries in this circuit to a CSV grid.
     pub fn queries_to_csv(&self) -> String {
         let mut queries = BTreeSet::new();
@@ -278,6 +697,59 @@ impl CircuitGates {
     }
 }
 
+/// Renders a query of the named column or selector at the given rotation.
+fn query(name: &str, rotation: i32, notation: Notation) -> String {
+    match notation {
+        Notation::Markdown => match rotation {
+            0 => name.to_owned(),
+            1 => format!("{}[next]", name),
+            -1 => format!("{}[prev]", name),
+            _ => format!("{}[{}]", name, rotation),
+        },
+        Notation::Latex => match rotation {
+            0 => format!("{}(X)", latex_name(name)),
+            1 => format!("{}(\\omega X)", latex_name(name)),
+            _ => format!("{}(\\omega^{{{}}} X)", latex_name(name), rotation),
+        },
+    }
+}
+
+fn lookup_name(index: usize, lookup: &Lookup) -> String {
+    if lookup.name.is_empty() {
+        format!("Lookup {}", index)
+    } else {
+        lookup.name.to_owned()
+    }
+}
+
+/// Formats text as a Markdown code span that can be used within a table.
+fn markdown_code(s: &str) -> String {
+    format!("`{}`", s.replace('|', "\\|"))
+}
+
+/// Escapes text for inclusion in LaTeX.
+fn latex_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Formats the name of a column or selector for use in LaTeX math mode.
+fn latex_name(name: &str) -> String {
+    format!("\\mathrm{{{}}}", latex_escape(name).replace(' ', "\\ "))
+}
+
 impl fmt::Display for CircuitGates {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         for gate in &self.gates {
@@ -98,7 +98,8 @@ impl CircuitLayout {
         // Collect the layout details.
         let mut cs = ConstraintSystem::default();
         let config = ConcreteCircuit::configure(&mut cs);
-        let mut layout = Layout::new(k, n, cs.num_selectors);
+        let usable_rows = n - (cs.blinding_factors() + 1);
+        let mut layout = Layout::new(k, n, usable_rows, cs.num_selectors);
         ConcreteCircuit::FloorPlanner::synthesize(
             &mut layout,
             circuit,
@@ -350,10 +351,11 @@ struct Layout {
     equality: Vec<(Column<Any>, usize, Column<Any>, usize)>,
     /// Selector assignments used for optimization pass
     selectors: Vec<Vec<bool>>,
+    usable_rows: usize,
 }
 
 impl Layout {
-    fn new(k: u32, n: usize, num_selectors: usize) -> Self {
+    fn new(k: u32, n: usize, usable_rows: usize, num_selectors: usize) -> Self {
         Layout {
             k,
             regions: vec![],
@@ -366,6 +368,7 @@ impl Layout {
             equality: vec![],
             /// Selector assignments used for optimization pass
             selectors: vec![vec![false; n]; num_selectors],
+            usable_rows,
         }
     }
 
@@ -502,6 +505,10 @@ impl<F: Field> Assignment<F> for Layout {
         Value::unknown()
     }
 
+    fn usable_rows(&self) -> Option<usize> {
+        Some(self.usable_rows)
+    }
+
     fn annotate_column<A, AR>(&mut self, _annotation: A, _column: Column<Any>)
     where
         A: FnOnce() -> AR,
@@ -0,0 +1,550 @@
+//! Interactive HTML rendering of circuit layouts.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+use std::ops::Range;
+
+use ff::Field;
+
+use super::{metadata, util, CellValue, FailureLocation, MockProver, VerifyFailure};
+use crate::{
+    arithmetic::FieldExt,
+    plonk::{Advice, Any},
+};
+
+/// The width of a cell, in SVG user units.
+const CELL_WIDTH: usize = 24;
+/// The height of a cell, in SVG user units.
+const CELL_HEIGHT: usize = 12;
+/// The height of the column headers above the grid.
+const HEADER_HEIGHT: usize = 40;
+
+/// Renderer for circuit layouts as standalone, interactive HTML pages.
+///
+/// Unlike [`CircuitLayout`], which draws a static image of a circuit's layout, this
+/// renders the state of a [`MockProver`] as an SVG image embedded in an HTML page with
+/// no external dependencies. The page can be zoomed with the mouse wheel and panned by
+/// dragging, which keeps large circuits readable.
+///
+/// - Columns are grouped by type: instance columns, then advice columns by phase, then
+///   fixed columns, then the fixed columns that selectors were compressed into.
+/// - Hovering over a cell shows its region and namespace, the annotation of its column,
+///   its row and its value. Cells that are assigned more than once are shaded darker.
+/// - Cells involved in a [`VerifyFailure`] are outlined in red, and hovering over them
+///   shows the failures.
+/// - Equality constraints are drawn as lines between the constrained cells, and can be
+///   hidden from the page.
+///
+/// Only assigned cells are drawn, along with the non-zero cells of instance and
+/// selector columns.
+///
+/// [`CircuitLayout`]: crate::dev::CircuitLayout
+///
+/// # Examples
+///
+/// ```ignore
+/// use halo2_proofs::dev::{HtmlLayout, MockProver};
+///
+/// let prover = MockProver::run(k, &circuit, instance).unwrap();
+/// let html = HtmlLayout::default()
+///     .view_height(0..1024)
+///     .render(&prover);
+/// std::fs::write("layout.html", html).unwrap();
+/// ```
+#[derive(Debug, Default)]
+pub struct HtmlLayout {
+    hide_equality_constraints: bool,
+    view_height: Option<Range<usize>>,
+}
+
+/// A column of the rendered grid.
+struct GridColumn {
+    column: metadata::Column,
+    /// A short name for the column, such as `A0`.
+    label: String,
+    /// The name of the group of columns to which this column belongs.
+    group: String,
+}
+
+impl HtmlLayout {
+    /// Draws lines between equality-constrained cells.
+    ///
+    /// The default is to draw them. They can also be hidden from within the page, but
+    /// omitting them keeps the page small for circuits with many copies.
+    pub fn show_equality_constraints(mut self, show: bool) -> Self {
+        self.hide_equality_constraints = !show;
+        self
+    }
+
+    /// Sets the view height for this layout, as a range of rows. Cells outside of it are
+    /// not drawn, and an empty (or reversed) range draws no rows.
+    ///
+    /// The default is to render every row of the circuit.
+    pub fn view_height(mut self, height: Range<usize>) -> Self {
+        self.view_height = Some(height);
+        self
+    }
+
+    /// Renders the layout and assignment of the circuit checked by the given prover,
+    /// returning the HTML page.
+    pub fn render<F: FieldExt>(&self, prover: &MockProver<F>) -> String {
+        let n = prover.n as usize;
+        // Only the rows in the view are drawn. A reversed range is an empty view.
+        let view = match &self.view_height {
+            Some(view) => view.start.min(view.end).min(n)..view.end.min(n),
+            None => 0..n,
+        };
+        let y = |row: usize| HEADER_HEIGHT + (row - view.start) * CELL_HEIGHT;
+
+        // Figure out what order to render the columns in.
+        let columns = grid_columns(prover);
+        let position: HashMap<metadata::Column, usize> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| (column.column, i))
+            .collect();
+        let x = |column: &metadata::Column| position[column] * CELL_WIDTH;
+
+        let width = columns.len() * CELL_WIDTH;
+        let height = y(view.end) + CELL_HEIGHT;
+
+        let annotation = |region: Option<usize>, column: &metadata::Column| {
+            region
+                .and_then(|r_i| prover.regions[r_i].annotations.get(column))
+                .or_else(|| prover.cs.general_column_annotations.get(column))
+        };
+
+        // The region, and number of extra assignments, of each cell assigned in a region.
+        let mut cell_regions = HashMap::new();
+        for (r_i, region) in prover.regions.iter().enumerate() {
+            for (&(column, row), &count) in &region.cells {
+                cell_regions.insert((metadata::Column::from(column), row), (r_i, count));
+            }
+        }
+
+        let failures = prover.verify().err().unwrap_or_default();
+        let failed_cells = failed_cells(prover, &failures);
+
+        let mut svg = String::new();
+        let w = &mut svg;
+
+        // Shade the columns by group, and mark the unusable rows.
+        writeln!(w, "<g class=\"columns\">").unwrap();
+        for column in &columns {
+            writeln!(
+                w,
+                "<rect class=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+                column_class(&column.column, &column.group),
+                x(&column.column),
+                HEADER_HEIGHT,
+                CELL_WIDTH,
+                y(view.end) - HEADER_HEIGHT,
+            )
+            .unwrap();
+        }
+        if view.end > prover.usable_rows.end {
+            let start = view.start.max(prover.usable_rows.end);
+            writeln!(
+                w,
+                "<rect class=\"unusable\" x=\"0\" y=\"{}\" width=\"{}\" height=\"{}\"><title>Unusable rows</title></rect>",
+                y(start),
+                width,
+                y(view.end) - y(start),
+            )
+            .unwrap();
+        }
+        writeln!(w, "</g>").unwrap();
+
+        // Render the regions, as one box per contiguous run of columns.
+        writeln!(w, "<g class=\"regions\">").unwrap();
+        for (r_i, region) in prover.regions.iter().enumerate() {
+            let (start, end) = match region.rows {
+                Some((start, end)) if start < view.end && end >= view.start => {
+                    (start.max(view.start), end.min(view.end - 1))
+                }
+                _ => continue,
+            };
+            let title = escape(&region.metadata(r_i).to_string());
+
+            let mut positions: Vec<usize> = region
+                .columns
+                .iter()
+                .map(|&column| position[&metadata::Column::from(column)])
+                .collect();
+            positions.sort_unstable();
+
+            let mut runs: Vec<Range<usize>> = vec![];
+            for p in positions {
+                match runs.last_mut() {
+                    Some(run) if run.end == p => run.end += 1,
+                    _ => runs.push(p..p + 1),
+                }
+            }
+            for run in runs {
+                writeln!(
+                    w,
+                    "<rect class=\"region\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"><title>{}</title></rect>",
+                    run.start * CELL_WIDTH,
+                    y(start),
+                    run.len() * CELL_WIDTH,
+                    y(end + 1) - y(start),
+                    title,
+                )
+                .unwrap();
+                writeln!(
+                    w,
+                    "<text class=\"label\" x=\"{}\" y=\"{}\">{}</text>",
+                    run.start * CELL_WIDTH + 2,
+                    y(start) + CELL_HEIGHT - 2,
+                    escape(&region.name),
+                )
+                .unwrap();
+            }
+        }
+        writeln!(w, "</g>").unwrap();
+
+        // Render the cells, with their details in tooltips.
+        writeln!(w, "<g class=\"cells\">").unwrap();
+        for column in &columns {
+            for (row, value) in drawn_cells(prover, column, view.clone()) {
+                let region = cell_regions.get(&(column.column, row));
+                let mut title = match region {
+                    Some(&(r_i, _)) => prover.regions[r_i].metadata(r_i).to_string(),
+                    None => "Outside any region".to_owned(),
+                };
+                write!(title, "\n{}", column.column).unwrap();
+                if let Some(annotation) = annotation(region.map(|&(r_i, _)| r_i), &column.column) {
+                    write!(title, " ('{}')", annotation).unwrap();
+                }
+                write!(title, "\nRow {}", row).unwrap();
+                if let Some(&(r_i, _)) = region {
+                    let offset = row - prover.regions[r_i].rows.unwrap().0;
+                    write!(title, " (offset {})", offset).unwrap();
+                }
+                write!(title, "\nValue: {}", util::format_value(value)).unwrap();
+                let class = match region {
+                    Some(&(_, count)) if count > 0 => {
+                        write!(title, "\nAssigned {} times", count + 1).unwrap();
+                        "cell reassigned"
+                    }
+                    _ => "cell",
+                };
+
+                writeln!(
+                    w,
+                    "<rect class=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"><title>{}</title></rect>",
+                    class,
+                    x(&column.column),
+                    y(row),
+                    CELL_WIDTH,
+                    CELL_HEIGHT,
+                    escape(&title),
+                )
+                .unwrap();
+            }
+        }
+        writeln!(w, "</g>").unwrap();
+
+        // Outline the cells involved in failures.
+        writeln!(w, "<g class=\"failures\">").unwrap();
+        for ((column, row), messages) in &failed_cells {
+            if !view.contains(row) || !position.contains_key(column) {
+                continue;
+            }
+            writeln!(
+                w,
+                "<rect class=\"failed\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"><title>{}</title></rect>",
+                x(column),
+                y(*row),
+                CELL_WIDTH,
+                CELL_HEIGHT,
+                escape(&messages.join("\n\n")),
+            )
+            .unwrap();
+        }
+        writeln!(w, "</g>").unwrap();
+
+        // Draw lines between equality-constrained cells.
+        if !self.hide_equality_constraints {
+            writeln!(w, "<g class=\"copies\">").unwrap();
+            for ((l_col, l_row), (r_col, r_row)) in copies(prover) {
+                if !view.contains(&l_row) && !view.contains(&r_row) {
+                    continue;
+                }
+                // Clamp the ends of edges that leave the view to its border.
+                let clamp = |row: usize| {
+                    if row < view.start {
+                        HEADER_HEIGHT
+                    } else if row >= view.end {
+                        y(view.end)
+                    } else {
+                        y(row) + CELL_HEIGHT / 2
+                    }
+                };
+                writeln!(
+                    w,
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"><title>{}</title></line>",
+                    x(&l_col) + CELL_WIDTH / 2,
+                    clamp(l_row),
+                    x(&r_col) + CELL_WIDTH / 2,
+                    clamp(r_row),
+                    escape(&format!(
+                        "{} row {} = {} row {}",
+                        l_col, l_row, r_col, r_row
+                    )),
+                )
+                .unwrap();
+            }
+            writeln!(w, "</g>").unwrap();
+        }
+
+        // Add a line showing the end of the usable rows.
+        if view.contains(&prover.usable_rows.end) {
+            writeln!(
+                w,
+                "<line class=\"usable\" x1=\"0\" y1=\"{0}\" x2=\"{1}\" y2=\"{0}\"><title>{2} usable rows</title></line>",
+                y(prover.usable_rows.end),
+                width,
+                prover.usable_rows.end,
+            )
+            .unwrap();
+        }
+
+        // Label the column groups and columns.
+        writeln!(w, "<g class=\"header\">").unwrap();
+        let mut group_start = 0;
+        for (i, column) in columns.iter().enumerate() {
+            if i + 1 == columns.len() || columns[i + 1].group != column.group {
+                writeln!(
+                    w,
+                    "<text x=\"{}\" y=\"14\">{}</text>",
+                    group_start * CELL_WIDTH + 2,
+                    escape(&column.group),
+                )
+                .unwrap();
+                group_start = i + 1;
+            }
+            let mut title = column.column.to_string();
+            if let Some(annotation) = annotation(None, &column.column) {
+                write!(title, " ('{}')", annotation).unwrap();
+            }
+            writeln!(
+                w,
+                "<text x=\"{}\" y=\"34\">{}<title>{}</title></text>",
+                x(&column.column) + 2,
+                column.label,
+                escape(&title),
+            )
+            .unwrap();
+        }
+        writeln!(w, "</g>").unwrap();
+
+        let summary = format!(
+            "k = {}, rows {}..{} of {}, {} usable, {} failure{}",
+            prover.k,
+            view.start,
+            view.end,
+            n,
+            prover.usable_rows.end,
+            failures.len(),
+            if failures.len() == 1 { "" } else { "s" },
+        );
+
+        format!(
+            include_str!("html/layout.html"),
+            summary = summary,
+            width = width,
+            height = height,
+            svg = svg,
+        )
+    }
+}
+
+/// The group of the fixed columns that selectors were compressed into.
+const SELECTORS: &str = "Selectors";
+
+/// Returns the columns of the circuit in the order in which they are rendered.
+fn grid_columns<F: FieldExt>(prover: &MockProver<F>) -> Vec<GridColumn> {
+    let cs = &prover.cs;
+    let mut columns = vec![];
+
+    for index in 0..cs.num_instance_columns {
+        columns.push(GridColumn {
+            column: (Any::Instance, index).into(),
+            label: format!("I{}", index),
+            group: "Instance".to_owned(),
+        });
+    }
+
+    let phases: BTreeSet<_> = cs.advice_column_phase.iter().collect();
+    for phase in phases {
+        for (index, _) in cs
+            .advice_column_phase
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| *p == phase)
+        {
+            columns.push(GridColumn {
+                column: (Any::Advice(Advice { phase: *phase }), index).into(),
+                label: format!("A{}", index),
+                group: format!("Advice (phase {})", phase.0),
+            });
+        }
+    }
+
+    let selectors: BTreeSet<usize> = cs.selector_map.iter().map(|c| c.index()).collect();
+    for selector in [false, true] {
+        for index in (0..cs.num_fixed_columns).filter(|i| selectors.contains(i) == selector) {
+            columns.push(GridColumn {
+                column: (Any::Fixed, index).into(),
+                label: format!("F{}", index),
+                group: if selector { SELECTORS } else { "Fixed" }.to_owned(),
+            });
+        }
+    }
+
+    columns
+}
+
+/// Returns the rows and values of the cells of a column that are drawn: the assigned
+/// cells, except for zeroes in instance and selector columns.
+fn drawn_cells<F: FieldExt>(
+    prover: &MockProver<F>,
+    column: &GridColumn,
+    rows: Range<usize>,
+) -> Vec<(usize, F)> {
+    let index = column.column.index;
+    let cells = match column.column.column_type {
+        Any::Instance => {
+            return rows
+                .map(|row| (row, prover.instance[index][row]))
+                .filter(|(_, value)| !value.is_zero_vartime())
+                .collect();
+        }
+        Any::Advice(_) => &prover.advice[index],
+        Any::Fixed => &prover.fixed[index],
+    };
+
+    let selector = column.group == SELECTORS;
+    rows.filter_map(|row| match cells[row] {
+        CellValue::Assigned(value) if !(selector && value.is_zero_vartime()) => Some((row, value)),
+        _ => None,
+    })
+    .collect()
+}
+
+/// Returns the CSS class used to shade a column.
+fn column_class(column: &metadata::Column, group: &str) -> &'static str {
+    match column.column_type {
+        Any::Instance => "instance",
+        Any::Advice(_) => "advice",
+        Any::Fixed if group == SELECTORS => "selector",
+        Any::Fixed => "fixed",
+    }
+}
+
+/// Returns the cells involved in each failure, along with the failures' descriptions.
+fn failed_cells<F: FieldExt>(
+    prover: &MockProver<F>,
+    failures: &[VerifyFailure],
+) -> BTreeMap<(metadata::Column, usize), Vec<String>> {
+    let n = prover.n as i32;
+    let region_start = |region: &metadata::Region| {
+        prover.regions[region.index]
+            .rows
+            .map_or(0, |(start, _)| start)
+    };
+    let location_row = |location: &FailureLocation| match location {
+        FailureLocation::InRegion { region, offset } => region_start(region) + offset,
+        FailureLocation::OutsideRegion { row } => *row,
+    };
+    let rotated = |row: usize, rotation: i32| ((row as i32 + rotation + n) % n) as usize;
+
+    let mut cells = BTreeMap::<_, Vec<String>>::new();
+    for failure in failures {
+        let failed: Vec<(metadata::Column, usize)> = match failure {
+            VerifyFailure::CellNotAssigned {
+                region,
+                column,
+                offset,
+                ..
+            } => {
+                let row = region_start(region) as isize + offset;
+                if row < 0 {
+                    vec![]
+                } else {
+                    vec![((*column).into(), row as usize)]
+                }
+            }
+            VerifyFailure::ConstraintNotSatisfied {
+                location,
+                cell_values,
+                ..
+            }
+            | VerifyFailure::Lookup {
+                location,
+                cell_values,
+                ..
+            } => {
+                let row = location_row(location);
+                cell_values
+                    .iter()
+                    .map(|(cell, _)| (cell.column, rotated(row, cell.rotation)))
+                    .collect()
+            }
+            VerifyFailure::Permutation { column, location } => {
+                vec![(*column, location_row(location))]
+            }
+            VerifyFailure::PhaseViolation { column, row, .. } => vec![(*column, *row)],
+            VerifyFailure::ConstraintPoisoned { .. }
+            | VerifyFailure::CustomArgument { .. }
+            | VerifyFailure::CustomArgumentColumns { .. } => vec![],
+        };
+
+        for cell in failed {
+            cells
+                .entry(cell)
+                .or_default()
+                .push(failure.to_string().trim_end().to_owned());
+        }
+    }
+    cells
+}
+
+/// Returns the pairs of cells that are constrained to be equal, as edges of the cycles of
+/// the permutation.
+fn copies<F: FieldExt>(
+    prover: &MockProver<F>,
+) -> BTreeSet<((metadata::Column, usize), (metadata::Column, usize))> {
+    let mut copies = BTreeSet::new();
+    if let Some(permutation) = prover.permutation.as_ref() {
+        for (i, mapping) in permutation.mapping.iter().enumerate() {
+            for (row, &(j, next_row)) in mapping.iter().enumerate() {
+                if (i, row) == (j, next_row) {
+                    continue;
+                }
+                let left = (metadata::Column::from(permutation.columns[i]), row);
+                let right = (metadata::Column::from(permutation.columns[j]), next_row);
+                // Both directions of a 2-cycle are the same edge.
+                copies.insert((left.min(right), left.max(right)));
+            }
+        }
+    }
+    copies
+}
+
+/// Escapes text for inclusion in HTML.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,427 @@
+//! A minimal JSON writer for the machine-readable output of the developer tools.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+use super::{metadata, util, CellValue, FailureLocation, MockProver, VerifyFailure};
+use crate::{arithmetic::FieldExt, plonk::Any};
+
+/// A JSON value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(i128),
+    String(String),
+    Array(Vec<Json>),
+    /// An object, whose fields are written in the order they are given.
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+        f.write_char('"')?;
+        for c in s.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        // `{:#}` pretty-prints the value, with two spaces of indentation per level.
+        let newline = |f: &mut fmt::Formatter<'_>, indent: usize| {
+            if f.alternate() {
+                write!(f, "\n{:1$}", "", indent * 2)
+            } else {
+                Ok(())
+            }
+        };
+
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => Self::write_string(f, s),
+            Json::Array(values) if values.is_empty() => f.write_str("[]"),
+            Json::Array(values) => {
+                f.write_char('[')?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    newline(f, indent + 1)?;
+                    value.write(f, indent + 1)?;
+                }
+                newline(f, indent)?;
+                f.write_char(']')
+            }
+            Json::Object(fields) if fields.is_empty() => f.write_str("{}"),
+            Json::Object(fields) => {
+                f.write_char('{')?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    newline(f, indent + 1)?;
+                    Self::write_string(f, name)?;
+                    f.write_str(if f.alternate() { ": " } else { ":" })?;
+                    value.write(f, indent + 1)?;
+                }
+                newline(f, indent)?;
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        Json::Bool(b)
+    }
+}
+
+macro_rules! json_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Json {
+                fn from(n: $t) -> Self {
+                    Json::Number(n as i128)
+                }
+            }
+        )*
+    };
+}
+
+json_from_int!(u32, u64, usize, isize, i32, i64);
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.to_owned())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Json::String(s)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(values: Vec<T>) -> Self {
+        Json::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<metadata::Column> for Json {
+    fn from(column: metadata::Column) -> Self {
+        let (column_type, phase) = match column.column_type {
+            Any::Advice(advice) => ("advice", Some(advice.phase.0 as u32)),
+            Any::Fixed => ("fixed", None),
+            Any::Instance => ("instance", None),
+        };
+        let mut fields = vec![
+            ("type", Json::from(column_type)),
+            ("index", Json::from(column.index)),
+        ];
+        if let Some(phase) = phase {
+            fields.push(("phase", Json::from(phase)));
+        }
+        Json::Object(fields)
+    }
+}
+
+impl From<&metadata::Region> for Json {
+    fn from(region: &metadata::Region) -> Self {
+        Json::Object(vec![
+            ("index", region.index.into()),
+            ("name", region.name.clone().into()),
+            ("namespace", region.namespace.clone().into()),
+        ])
+    }
+}
+
+impl From<metadata::Gate> for Json {
+    fn from(gate: metadata::Gate) -> Self {
+        Json::Object(vec![
+            ("index", gate.index.into()),
+            ("name", gate.name.into()),
+        ])
+    }
+}
+
+impl From<metadata::Constraint> for Json {
+    fn from(constraint: metadata::Constraint) -> Self {
+        Json::Object(vec![
+            ("gate", constraint.gate.into()),
+            ("index", constraint.index.into()),
+            ("name", constraint.name.into()),
+        ])
+    }
+}
+
+impl From<&FailureLocation> for Json {
+    fn from(location: &FailureLocation) -> Self {
+        match location {
+            FailureLocation::InRegion { region, offset } => Json::Object(vec![
+                ("region", region.into()),
+                ("offset", (*offset).into()),
+            ]),
+            FailureLocation::OutsideRegion { row } => Json::Object(vec![("row", (*row).into())]),
+        }
+    }
+}
+
+/// Converts the values of virtual cells, as reported in a [`VerifyFailure`].
+fn cell_values(cell_values: &[(metadata::VirtualCell, String)]) -> Json {
+    Json::Array(
+        cell_values
+            .iter()
+            .map(|(cell, value)| {
+                Json::Object(vec![
+                    ("column", cell.column.into()),
+                    ("rotation", cell.rotation.into()),
+                    (
+                        "name",
+                        Some(cell.name).filter(|name| !name.is_empty()).into(),
+                    ),
+                    ("value", value.clone().into()),
+                ])
+            })
+            .collect(),
+    )
+}
+
+impl From<&VerifyFailure> for Json {
+    fn from(failure: &VerifyFailure) -> Self {
+        match failure {
+            VerifyFailure::CellNotAssigned {
+                gate,
+                region,
+                gate_offset,
+                column,
+                offset,
+            } => Json::Object(vec![
+                ("kind", "CellNotAssigned".into()),
+                ("gate", (*gate).into()),
+                ("region", region.into()),
+                ("gate_offset", (*gate_offset).into()),
+                ("column", metadata::Column::from(*column).into()),
+                ("offset", (*offset).into()),
+            ]),
+            VerifyFailure::ConstraintNotSatisfied {
+                constraint,
+                location,
+                cell_values: values,
+            } => Json::Object(vec![
+                ("kind", "ConstraintNotSatisfied".into()),
+                ("constraint", (*constraint).into()),
+                ("location", location.into()),
+                ("cell_values", cell_values(values)),
+            ]),
+            VerifyFailure::ConstraintPoisoned { constraint } => Json::Object(vec![
+                ("kind", "ConstraintPoisoned".into()),
+                ("constraint", (*constraint).into()),
+            ]),
+            VerifyFailure::Lookup {
+                name,
+                lookup_index,
+                location,
+                input,
+                cell_values: values,
+                nearest_table_rows,
+            } => Json::Object(vec![
+                ("kind", "Lookup".into()),
+                ("name", (*name).into()),
+                ("lookup_index", (*lookup_index).into()),
+                ("location", location.into()),
+                ("input", input.clone().into()),
+                ("cell_values", cell_values(values)),
+                (
+                    "nearest_table_rows",
+                    Json::Array(
+                        nearest_table_rows
+                            .iter()
+                            .map(|(row, values)| {
+                                Json::Object(vec![
+                                    ("row", (*row).into()),
+                                    ("values", values.clone().into()),
+                                ])
+                            })
+                            .collect(),
+                    ),
+                ),
+            ]),
+            VerifyFailure::Permutation { column, location } => Json::Object(vec![
+                ("kind", "Permutation".into()),
+                ("column", (*column).into()),
+                ("location", location.into()),
+            ]),
+            VerifyFailure::CustomArgument {
+                name,
+                argument_index,
+                constraint_index,
+                location,
+            } => Json::Object(vec![
+                ("kind", "CustomArgument".into()),
+                ("name", (*name).into()),
+                ("argument_index", (*argument_index).into()),
+                ("constraint_index", (*constraint_index).into()),
+                ("location", location.into()),
+            ]),
+            VerifyFailure::CustomArgumentColumns {
+                name,
+                argument_index,
+            } => Json::Object(vec![
+                ("kind", "CustomArgumentColumns".into()),
+                ("name", (*name).into()),
+                ("argument_index", (*argument_index).into()),
+            ]),
+            VerifyFailure::PhaseViolation {
+                column,
+                row,
+                phase,
+                kind,
+            } => Json::Object(vec![
+                ("kind", "PhaseViolation".into()),
+                ("column", (*column).into()),
+                ("row", (*row).into()),
+                ("phase", u32::from(*phase).into()),
+                ("violation", format!("{:?}", kind).into()),
+            ]),
+        }
+    }
+}
+
+impl<'a, F: FieldExt> MockProver<'a, F> {
+    /// Returns the result of [`MockProver::verify`] as pretty-printed JSON, along with
+    /// the regions that were assigned.
+    ///
+    /// If `include_assignment` is `true`, the values of every advice, fixed and instance
+    /// cell in the usable rows are also included, with `null` for unassigned cells. The
+    /// fixed columns include those that selectors were compressed into.
+    ///
+    /// The output is deterministic, so that it can be consumed by other tools or
+    /// compared between runs of the same circuit.
+    pub fn to_json(&self, include_assignment: bool) -> String {
+        let failures = self.verify().err().unwrap_or_default();
+
+        let regions = self
+            .regions
+            .iter()
+            .enumerate()
+            .map(|(index, region)| {
+                let metadata = region.metadata(index);
+                let mut columns: Vec<metadata::Column> =
+                    region.columns.iter().map(|&column| column.into()).collect();
+                columns.sort();
+                let annotations: BTreeMap<_, _> = region.annotations.iter().collect();
+
+                Json::Object(vec![
+                    ("index", index.into()),
+                    ("name", metadata.name.clone().into()),
+                    ("namespace", metadata.namespace.clone().into()),
+                    (
+                        "rows",
+                        region
+                            .rows
+                            .map(|(start, end)| {
+                                Json::Object(vec![("start", start.into()), ("end", end.into())])
+                            })
+                            .into(),
+                    ),
+                    ("columns", columns.into()),
+                    (
+                        "annotations",
+                        Json::Array(
+                            annotations
+                                .into_iter()
+                                .map(|(column, annotation)| {
+                                    Json::Object(vec![
+                                        ("column", (*column).into()),
+                                        ("annotation", annotation.clone().into()),
+                                    ])
+                                })
+                                .collect(),
+                        ),
+                    ),
+                ])
+            })
+            .collect();
+
+        let mut fields = vec![
+            ("k", self.k.into()),
+            ("usable_rows", self.usable_rows.end.into()),
+            ("satisfied", failures.is_empty().into()),
+            (
+                "failures",
+                Json::Array(failures.iter().map(Json::from).collect()),
+            ),
+            ("regions", Json::Array(regions)),
+        ];
+
+        if include_assignment {
+            let cells = |column: &[CellValue<F>]| {
+                Json::Array(
+                    column[self.usable_rows.clone()]
+                        .iter()
+                        .map(|cell| match cell {
+                            CellValue::Assigned(value) => util::format_value(*value).into(),
+                            _ => Json::Null,
+                        })
+                        .collect(),
+                )
+            };
+            fields.push((
+                "assignment",
+                Json::Object(vec![
+                    (
+                        "advice",
+                        Json::Array(self.advice.iter().map(|column| cells(column)).collect()),
+                    ),
+                    (
+                        "fixed",
+                        Json::Array(self.fixed.iter().map(|column| cells(column)).collect()),
+                    ),
+                    (
+                        "instance",
+                        Json::Array(
+                            self.instance
+                                .iter()
+                                .map(|column| {
+                                    Json::Array(
+                                        column[self.usable_rows.clone()]
+                                            .iter()
+                                            .map(|&value| util::format_value(value).into())
+                                            .collect(),
+                                    )
+                                })
+                                .collect(),
+                        ),
+                    ),
+                ]),
+            ));
+        }
+
+        format!("{:#}", Json::Object(fields))
+    }
+}
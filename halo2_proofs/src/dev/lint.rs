@@ -0,0 +1,848 @@
+//! Checks for common soundness mistakes in circuits.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use std::iter;
+
+use ff::Field;
+
+use super::{
+    metadata::{self, DebugColumn},
+    util, CellValue, MockProver, Value,
+};
+use crate::{
+    arithmetic::FieldExt,
+    plonk::{Any, Circuit, Column, Error},
+};
+
+/// A likely mistake in a circuit, found by [`lint`] or [`MockProver::lint`].
+///
+/// Lints do not necessarily make a circuit unsatisfiable: they flag patterns that have
+/// historically led to constraints that are either too weak or only hold by accident of
+/// the layout chosen by the floor planner.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Lint {
+    /// A constraint queries advice cells, but no selector or fixed column.
+    ///
+    /// Such a constraint is active on every row, including the unusable rows holding
+    /// the prover's blinding factors, and will be reported by `MockProver::verify` as
+    /// [`VerifyFailure::ConstraintPoisoned`](super::VerifyFailure::ConstraintPoisoned).
+    UngatedConstraint {
+        /// The constraint that is not gated.
+        constraint: metadata::Constraint,
+    },
+    /// An enabled gate queries an advice cell that is not covered by any region.
+    ///
+    /// Nothing assigns such a cell, so whether the gate holds depends on how the floor
+    /// planner happened to lay out the surrounding regions.
+    QueryOutsideRegion {
+        /// The gate that queries the cell.
+        gate: metadata::Gate,
+        /// The region in which the gate is enabled.
+        region: metadata::Region,
+        /// The offset (relative to the start of the region) at which the gate is
+        /// enabled.
+        gate_offset: usize,
+        /// The column of the queried cell.
+        column: metadata::Column,
+        /// The rotation at which the cell is queried.
+        rotation: i32,
+    },
+    /// A column has equality enabled, but is never used in a copy constraint.
+    ///
+    /// Columns enabled with [`ConstraintSystem::enable_constant`] are not reported, as
+    /// they are only copied from when the circuit uses constants.
+    ///
+    /// [`ConstraintSystem::enable_constant`]: crate::plonk::ConstraintSystem::enable_constant
+    UnusedEqualityColumn {
+        /// The equality-enabled column.
+        column: metadata::Column,
+        /// The annotation of the column, if any.
+        annotation: Option<String>,
+    },
+    /// A lookup's input, evaluated on a row where no cell is assigned and no selector
+    /// is enabled, is not present in the table.
+    ///
+    /// Every such row within the usable area then fails the lookup. This usually means
+    /// that the inputs are not gated by a selector, or that they do not fall back to a
+    /// default value that is in the table.
+    LookupDefaultNotInTable {
+        /// The name of the lookup.
+        name: &'static str,
+        /// The index of the lookup.
+        lookup_index: usize,
+        /// The input tuple on an unassigned row.
+        default: Vec<String>,
+    },
+    /// A fixed column is never assigned.
+    ///
+    /// Columns enabled with [`ConstraintSystem::enable_constant`] are not reported, as
+    /// they are only assigned when the circuit uses constants.
+    ///
+    /// [`ConstraintSystem::enable_constant`]: crate::plonk::ConstraintSystem::enable_constant
+    UnassignedFixedColumn {
+        /// The fixed column.
+        column: metadata::Column,
+        /// The annotation of the column, if any.
+        annotation: Option<String>,
+    },
+}
+
+fn fmt_column(
+    f: &mut fmt::Formatter<'_>,
+    column: &metadata::Column,
+    annotation: &Option<String>,
+) -> fmt::Result {
+    match annotation {
+        Some(annotation) => write!(f, "{} ('{}')", column, annotation),
+        None => write!(f, "{}", column),
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UngatedConstraint { constraint } => write!(
+                f,
+                "{} queries advice cells but no selector or fixed column - missing selector?",
+                constraint
+            ),
+            Self::QueryOutsideRegion {
+                gate,
+                region,
+                gate_offset,
+                column,
+                rotation,
+            } => write!(
+                f,
+                "{} enables {} at offset {}, which queries {}@{} outside of any region",
+                region,
+                gate,
+                gate_offset,
+                DebugColumn::from((*column, region.column_annotations.as_ref())),
+                rotation
+            ),
+            Self::UnusedEqualityColumn { column, annotation } => {
+                fmt_column(f, column, annotation)?;
+                write!(f, " has equality enabled but is never copied")
+            }
+            Self::LookupDefaultNotInTable {
+                name,
+                lookup_index,
+                default,
+            } => write!(
+                f,
+                "Lookup {}(index: {}) input on unassigned rows ({}) is not in the table - missing selector?",
+                name,
+                lookup_index,
+                default.join(", ")
+            ),
+            Self::UnassignedFixedColumn { column, annotation } => {
+                fmt_column(f, column, annotation)?;
+                write!(f, " is never assigned")
+            }
+        }
+    }
+}
+
+/// Runs `circuit` through the [`MockProver`] and checks it for common mistakes.
+///
+/// This is equivalent to `MockProver::run(k, circuit, instance)?.lint()`.
+///
+/// # Examples
+///
+/// ```
+/// use ff::Field;
+/// use halo2_proofs::{
+///     circuit::{Layouter, SimpleFloorPlanner, Value},
+///     dev::{lint, Lint},
+///     plonk::{Advice, Any, Circuit, Column, ConstraintSystem, Error, Expression, Selector, TableColumn},
+///     poly::Rotation,
+/// };
+/// use halo2curves::pasta::Fp;
+///
+/// #[derive(Clone)]
+/// struct MyConfig {
+///     a: Column<Advice>,
+///     b: Column<Advice>,
+///     q: Selector,
+///     t: TableColumn,
+/// }
+///
+/// struct MyCircuit;
+///
+/// impl Circuit<Fp> for MyCircuit {
+///     type Config = MyConfig;
+///     type FloorPlanner = SimpleFloorPlanner;
+///
+///     fn without_witnesses(&self) -> Self {
+///         MyCircuit
+///     }
+///
+///     fn configure(meta: &mut ConstraintSystem<Fp>) -> MyConfig {
+///         let a = meta.advice_column();
+///         let b = meta.advice_column();
+///         let c = meta.advice_column();
+///         meta.enable_equality(c);
+///         let _f = meta.fixed_column();
+///         let t = meta.lookup_table_column();
+///         let q = meta.selector();
+///
+///         // BUG: no selector.
+///         meta.create_gate("ungated", |meta| {
+///             let a = meta.query_advice(a, Rotation::cur());
+///             let b = meta.query_advice(b, Rotation::cur());
+///             vec![a - b]
+///         });
+///
+///         // BUG: queries `a` on the row after the region.
+///         meta.create_gate("next", |meta| {
+///             let q = meta.query_selector(q);
+///             let a_next = meta.query_advice(a, Rotation::next());
+///             let b = meta.query_advice(b, Rotation::cur());
+///             vec![q * (a_next - b)]
+///         });
+///
+///         // BUG: 0 + 10 is not in the table.
+///         meta.lookup("offset", |meta| {
+///             let a = meta.query_advice(a, Rotation::cur());
+///             vec![(a + Expression::Constant(Fp::from(10)), t)]
+///         });
+///
+///         MyConfig { a, b, q, t }
+///     }
+///
+///     fn synthesize(&self, config: MyConfig, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+///         layouter.assign_table(
+///             || "table",
+///             |mut table| {
+///                 for i in 0..8 {
+///                     table.assign_cell(|| "t", config.t, i, || Value::known(Fp::from(i as u64)))?;
+///                 }
+///                 Ok(())
+///             },
+///         )?;
+///         layouter.assign_region(
+///             || "Pair",
+///             |mut region| {
+///                 config.q.enable(&mut region, 0)?;
+///                 region.assign_advice(|| "a", config.a, 0, || Value::known(Fp::one()))?;
+///                 region.assign_advice(|| "b", config.b, 0, || Value::known(Fp::one()))?;
+///                 Ok(())
+///             },
+///         )
+///     }
+/// }
+///
+/// assert_eq!(
+///     lint::<Fp, _>(4, &MyCircuit, vec![]).unwrap(),
+///     vec![
+///         Lint::UngatedConstraint {
+///             constraint: ((0, "ungated").into(), 0, "").into(),
+///         },
+///         Lint::QueryOutsideRegion {
+///             gate: (1, "next").into(),
+///             region: (1, "Pair").into(),
+///             gate_offset: 0,
+///             column: (Any::advice(), 0).into(),
+///             rotation: 1,
+///         },
+///         Lint::UnusedEqualityColumn {
+///             column: (Any::advice(), 2).into(),
+///             annotation: None,
+///         },
+///         Lint::LookupDefaultNotInTable {
+///             name: "offset",
+///             lookup_index: 0,
+///             default: vec!["0xa".to_string()],
+///         },
+///         Lint::UnassignedFixedColumn {
+///             column: (Any::Fixed, 0).into(),
+///             annotation: None,
+///         },
+///     ],
+/// );
+/// ```
+pub fn lint<F: FieldExt, ConcreteCircuit: Circuit<F>>(
+    k: u32,
+    circuit: &ConcreteCircuit,
+    instance: Vec<Vec<F>>,
+) -> Result<Vec<Lint>, Error> {
+    Ok(MockProver::run(k, circuit, instance)?.lint())
+}
+
+impl<'a, F: FieldExt> MockProver<'a, F> {
+    /// Checks the circuit for common mistakes, using both its constraint system and the
+    /// assignments made while it was synthesized.
+    ///
+    /// See [`Lint`] for the mistakes that are detected.
+    pub fn lint(&self) -> Vec<Lint> {
+        iter::empty()
+            .chain(self.lint_ungated_constraints())
+            .chain(self.lint_queries_outside_regions())
+            .chain(self.lint_unused_equality_columns())
+            .chain(self.lint_lookup_defaults())
+            .chain(self.lint_unassigned_fixed_columns())
+            .collect()
+    }
+
+    /// Returns the annotation of `column`, preferring circuit-wide annotations over those
+    /// given within a region.
+    fn column_annotation(&self, column: metadata::Column) -> Option<String> {
+        self.cs
+            .general_column_annotations
+            .get(&column)
+            .or_else(|| {
+                self.regions
+                    .iter()
+                    .find_map(|region| region.annotations.get(&column))
+            })
+            .cloned()
+    }
+
+    fn is_constants_column(&self, fixed_index: usize) -> bool {
+        self.cs
+            .constants
+            .iter()
+            .any(|constants| constants.index() == fixed_index)
+    }
+
+    fn lint_ungated_constraints(&self) -> Vec<Lint> {
+        self.cs
+            .gates
+            .iter()
+            .enumerate()
+            .flat_map(|(gate_index, gate)| {
+                gate.polynomials()
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(poly_index, poly)| {
+                        // Selectors have been compressed into fixed columns by now, so
+                        // querying any fixed column counts as being gated.
+                        let (queries_advice, queries_fixed) = poly.evaluate(
+                            &|_| (false, false),
+                            &|_| panic!("virtual selectors are removed during optimization"),
+                            &|_| (false, true),
+                            &|_| (true, false),
+                            &|_| (false, false),
+                            &|_| (false, false),
+                            &|a| a,
+                            &|a, b| (a.0 || b.0, a.1 || b.1),
+                            &|a, b| (a.0 || b.0, a.1 || b.1),
+                            &|a, _| a,
+                        );
+
+                        (queries_advice && !queries_fixed).then(|| Lint::UngatedConstraint {
+                            constraint: (
+                                (gate_index, gate.name()).into(),
+                                poly_index,
+                                gate.constraint_name(poly_index),
+                            )
+                                .into(),
+                        })
+                    })
+            })
+            .collect()
+    }
+
+    fn lint_queries_outside_regions(&self) -> Vec<Lint> {
+        let n = self.n as i32;
+        let covered = |column: Column<Any>, row: usize| {
+            self.regions.iter().any(|r| {
+                r.columns.contains(&column)
+                    && r.rows
+                        .map_or(false, |(start, end)| (start..=end).contains(&row))
+            })
+        };
+
+        // Each (gate, region, cell) is reported once, however often the gate is enabled.
+        let mut reported = HashSet::new();
+        let mut lints = vec![];
+        for (r_i, r) in self.regions.iter().enumerate() {
+            for (gate_index, gate) in self.cs.gates.iter().enumerate() {
+                for selector in gate.queried_selectors() {
+                    for selector_row in r.enabled_selectors.get(selector).into_iter().flatten() {
+                        for cell in gate.queried_cells() {
+                            if !matches!(cell.column.column_type(), Any::Advice(_)) {
+                                continue;
+                            }
+
+                            let cell_row =
+                                ((*selector_row as i32 + n + cell.rotation.0) % n) as usize;
+                            if !covered(cell.column, cell_row)
+                                && reported.insert((gate_index, r_i, cell.column, cell.rotation.0))
+                            {
+                                lints.push(Lint::QueryOutsideRegion {
+                                    gate: (gate_index, gate.name()).into(),
+                                    region: r.metadata(r_i),
+                                    gate_offset: selector_row - r.rows.unwrap().0,
+                                    column: cell.column.into(),
+                                    rotation: cell.rotation.0,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        lints
+    }
+
+    fn lint_unused_equality_columns(&self) -> Vec<Lint> {
+        // Forked provers record their copies in their regions until they are merged, so
+        // only the root prover has the full permutation.
+        let permutation = match self.permutation.as_ref() {
+            Some(permutation) => permutation,
+            None => return vec![],
+        };
+
+        // A column takes part in a copy constraint iff some cell in it is not in a
+        // 1-cycle of the permutation.
+        permutation
+            .columns
+            .iter()
+            .zip(permutation.mapping.iter())
+            .enumerate()
+            .filter(|(column_index, (column, mapping))| {
+                !(*column.column_type() == Any::Fixed && self.is_constants_column(column.index()))
+                    && mapping
+                        .iter()
+                        .enumerate()
+                        .all(|(row, cell)| *cell == (*column_index, row))
+            })
+            .map(|(_, (column, _))| Lint::UnusedEqualityColumn {
+                column: (*column).into(),
+                annotation: self.column_annotation((*column).into()),
+            })
+            .collect()
+    }
+
+    fn lint_lookup_defaults(&self) -> Vec<Lint> {
+        let n = self.n as i32;
+
+        self.cs
+            .lookups
+            .iter()
+            .enumerate()
+            .filter_map(|(lookup_index, lookup)| {
+                // On a row where nothing is assigned, every column (including the
+                // compressed selectors) evaluates to zero.
+                let default: Vec<_> = lookup
+                    .input_expressions
+                    .iter()
+                    .map(|expression| {
+                        expression.evaluate(
+                            &|scalar| Value::Real(scalar),
+                            &|_| panic!("virtual selectors are removed during optimization"),
+                            &|_| Value::Real(F::zero()),
+                            &|_| Value::Real(F::zero()),
+                            &|_| Value::Real(F::zero()),
+                            &|challenge| Value::Real(self.challenges[challenge.index()]),
+                            &|a| -a,
+                            &|a, b| a + b,
+                            &|a, b| a * b,
+                            &|a, scalar| a * scalar,
+                        )
+                    })
+                    .collect();
+
+                let table: BTreeSet<Vec<_>> = self
+                    .usable_rows
+                    .clone()
+                    .map(|row| {
+                        let row = row as i32 + n;
+                        lookup
+                            .table_expressions
+                            .iter()
+                            .map(|expression| {
+                                expression.evaluate(
+                                    &|scalar| Value::Real(scalar),
+                                    &|_| {
+                                        panic!("virtual selectors are removed during optimization")
+                                    },
+                                    &util::load_slice(n, row, &self.cs.fixed_queries, &self.fixed),
+                                    &util::load_slice(
+                                        n,
+                                        row,
+                                        &self.cs.advice_queries,
+                                        &self.advice,
+                                    ),
+                                    &util::load_instance(
+                                        n,
+                                        row,
+                                        &self.cs.instance_queries,
+                                        &self.instance,
+                                    ),
+                                    &|challenge| Value::Real(self.challenges[challenge.index()]),
+                                    &|a| -a,
+                                    &|a, b| a + b,
+                                    &|a, b| a * b,
+                                    &|a, scalar| a * scalar,
+                                )
+                            })
+                            .collect()
+                    })
+                    .collect();
+
+                (!table.contains(&default)).then(|| Lint::LookupDefaultNotInTable {
+                    name: lookup.name,
+                    lookup_index,
+                    default: default.into_iter().map(util::format_cell_value).collect(),
+                })
+            })
+            .collect()
+    }
+
+    fn lint_unassigned_fixed_columns(&self) -> Vec<Lint> {
+        // The columns that selectors were compressed into are assigned by construction.
+        let selector_columns: HashSet<_> = self
+            .cs
+            .selector_map
+            .iter()
+            .map(|column| column.index())
+            .collect();
+
+        self.fixed
+            .iter()
+            .enumerate()
+            .filter(|(index, cells)| {
+                !selector_columns.contains(index)
+                    && !self.is_constants_column(*index)
+                    && cells
+                        .iter()
+                        .all(|cell| matches!(cell, CellValue::Unassigned))
+            })
+            .map(|(index, _)| {
+                let column = (Any::Fixed, index).into();
+                Lint::UnassignedFixedColumn {
+                    column,
+                    annotation: self.column_annotation(column),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2curves::pasta::Fp;
+
+    use super::{lint, Lint};
+    use crate::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        plonk::{
+            Advice, Any, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Selector,
+            TableColumn,
+        },
+        poly::Rotation,
+    };
+
+    const K: u32 = 4;
+
+    fn lints<C: Circuit<Fp>>(circuit: &C) -> Vec<Lint> {
+        lint(K, circuit, vec![]).unwrap()
+    }
+
+    /// Checks that `a = b`, with a selector unless `BUGGY`.
+    struct UngatedCircuit<const BUGGY: bool>;
+
+    impl<const BUGGY: bool> Circuit<Fp> for UngatedCircuit<BUGGY> {
+        type Config = (Column<Advice>, Column<Advice>, Selector);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let a = meta.advice_column();
+            let b = meta.advice_column();
+            let q = meta.selector();
+
+            meta.create_gate("equal", |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                let b = meta.query_advice(b, Rotation::cur());
+                if BUGGY {
+                    vec![a - b]
+                } else {
+                    vec![meta.query_selector(q) * (a - b)]
+                }
+            });
+
+            (a, b, q)
+        }
+
+        fn synthesize(
+            &self,
+            (a, b, q): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "equal",
+                |mut region| {
+                    q.enable(&mut region, 0)?;
+                    region.assign_advice(|| "a", a, 0, || Value::known(Fp::one()))?;
+                    region.assign_advice(|| "b", b, 0, || Value::known(Fp::one()))?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn ungated_constraint() {
+        assert_eq!(
+            lints(&UngatedCircuit::<true>),
+            vec![Lint::UngatedConstraint {
+                constraint: ((0, "equal").into(), 0, "").into(),
+            }]
+        );
+        assert_eq!(lints(&UngatedCircuit::<false>), vec![]);
+    }
+
+    /// Checks that `a` on the next row equals `b`, assigning that next row unless
+    /// `BUGGY`.
+    struct QueryOutsideRegionCircuit<const BUGGY: bool>;
+
+    impl<const BUGGY: bool> Circuit<Fp> for QueryOutsideRegionCircuit<BUGGY> {
+        type Config = (Column<Advice>, Column<Advice>, Selector);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let a = meta.advice_column();
+            let b = meta.advice_column();
+            let q = meta.selector();
+
+            meta.create_gate("next", |meta| {
+                let q = meta.query_selector(q);
+                let a_next = meta.query_advice(a, Rotation::next());
+                let b = meta.query_advice(b, Rotation::cur());
+                vec![q * (a_next - b)]
+            });
+
+            (a, b, q)
+        }
+
+        fn synthesize(
+            &self,
+            (a, b, q): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "pair",
+                |mut region| {
+                    q.enable(&mut region, 0)?;
+                    region.assign_advice(|| "b", b, 0, || Value::known(Fp::one()))?;
+                    region.assign_advice(|| "a", a, 0, || Value::known(Fp::zero()))?;
+                    if !BUGGY {
+                        region.assign_advice(|| "a next", a, 1, || Value::known(Fp::one()))?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn query_outside_region() {
+        assert_eq!(
+            lints(&QueryOutsideRegionCircuit::<true>),
+            vec![Lint::QueryOutsideRegion {
+                gate: (0, "next").into(),
+                region: (0, "pair").into(),
+                gate_offset: 0,
+                column: (Any::advice(), 0).into(),
+                rotation: 1,
+            }]
+        );
+        assert_eq!(lints(&QueryOutsideRegionCircuit::<false>), vec![]);
+    }
+
+    /// Enables equality on `a` and `b`, copying `a` to `b` unless `BUGGY`. The unused
+    /// constants column must not be reported.
+    struct UnusedEqualityCircuit<const BUGGY: bool>;
+
+    impl<const BUGGY: bool> Circuit<Fp> for UnusedEqualityCircuit<BUGGY> {
+        type Config = (Column<Advice>, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let a = meta.advice_column();
+            let b = meta.advice_column();
+            meta.enable_equality(a);
+            meta.enable_equality(b);
+
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            (a, b)
+        }
+
+        fn synthesize(
+            &self,
+            (a, b): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "copy",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", a, 0, || Value::known(Fp::one()))?;
+                    let b = region.assign_advice(|| "b", b, 0, || Value::known(Fp::one()))?;
+                    if !BUGGY {
+                        region.constrain_equal(a.cell(), b.cell())?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn unused_equality_column() {
+        assert_eq!(
+            lints(&UnusedEqualityCircuit::<true>),
+            vec![
+                Lint::UnusedEqualityColumn {
+                    column: (Any::advice(), 0).into(),
+                    annotation: None,
+                },
+                Lint::UnusedEqualityColumn {
+                    column: (Any::advice(), 1).into(),
+                    annotation: None,
+                },
+            ]
+        );
+        assert_eq!(lints(&UnusedEqualityCircuit::<false>), vec![]);
+    }
+
+    /// Looks `a - 1` up in a table of `0..8`, with a selector unless `BUGGY`.
+    struct LookupDefaultCircuit<const BUGGY: bool>;
+
+    impl<const BUGGY: bool> Circuit<Fp> for LookupDefaultCircuit<BUGGY> {
+        type Config = (Column<Advice>, Selector, TableColumn);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let a = meta.advice_column();
+            let q = meta.complex_selector();
+            let t = meta.lookup_table_column();
+
+            meta.lookup("decrement", |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                let input = a - Expression::Constant(Fp::one());
+                if BUGGY {
+                    vec![(input, t)]
+                } else {
+                    vec![(meta.query_selector(q) * input, t)]
+                }
+            });
+
+            (a, q, t)
+        }
+
+        fn synthesize(
+            &self,
+            (a, q, t): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            layouter.assign_table(
+                || "table",
+                |mut table| {
+                    for value in 0..8u64 {
+                        table.assign_cell(
+                            || "t",
+                            t,
+                            value as usize,
+                            || Value::known(Fp::from(value)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+            layouter.assign_region(
+                || "decrement",
+                |mut region| {
+                    q.enable(&mut region, 0)?;
+                    region.assign_advice(|| "a", a, 0, || Value::known(Fp::from(3)))?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn lookup_default_not_in_table() {
+        assert_eq!(
+            lints(&LookupDefaultCircuit::<true>),
+            vec![Lint::LookupDefaultNotInTable {
+                name: "decrement",
+                lookup_index: 0,
+                default: vec!["-1".to_string()],
+            }]
+        );
+        assert_eq!(lints(&LookupDefaultCircuit::<false>), vec![]);
+    }
+
+    /// Assigns a fixed column unless `BUGGY`. The unused constants column must not be
+    /// reported.
+    struct UnassignedFixedCircuit<const BUGGY: bool>;
+
+    impl<const BUGGY: bool> Circuit<Fp> for UnassignedFixedCircuit<BUGGY> {
+        type Config = Column<Fixed>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let f = meta.fixed_column();
+
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            f
+        }
+
+        fn synthesize(
+            &self,
+            f: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            if BUGGY {
+                return Ok(());
+            }
+            layouter.assign_region(
+                || "fixed",
+                |mut region| {
+                    region.assign_fixed(|| "f", f, 0, || Value::known(Fp::one()))?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn unassigned_fixed_column() {
+        assert_eq!(
+            lints(&UnassignedFixedCircuit::<true>),
+            vec![Lint::UnassignedFixedColumn {
+                column: (Any::Fixed, 0).into(),
+                annotation: None,
+            }]
+        );
+        assert_eq!(lints(&UnassignedFixedCircuit::<false>), vec![]);
+    }
+}
@@ -75,7 +75,7 @@ impl fmt::Display for DebugColumn {
 /// within a custom gate.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct VirtualCell {
-    name: &'static str,
+    pub(super) name: &'static str,
     pub(super) column: Column,
     pub(super) rotation: i32,
 }
@@ -219,6 +219,9 @@ pub struct Region {
     pub(super) name: String,
     /// A reference to the annotations of the Columns that exist within this `Region`.
     pub(super) column_annotations: Option<HashMap<ColumnMetadata, String>>,
+    /// The path of namespaces (from `Layouter::namespace`) in which the region was
+    /// assigned, outermost first.
+    pub(super) namespace: Vec<String>,
 }
 
 impl Region {
@@ -232,6 +235,12 @@ impl Region {
             .as_ref()
             .and_then(|map| map.get(&metadata).cloned())
     }
+
+    /// Returns the path of namespaces in which this region was assigned, outermost
+    /// first.
+    pub fn namespace(&self) -> &[String] {
+        &self.namespace
+    }
 }
 
 impl PartialEq for Region {
@@ -244,13 +253,17 @@ impl Eq for Region {}
 
 impl Debug for Region {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Region {} ('{}')", self.index, self.name)
+        write!(f, "{}", self)
     }
 }
 
 impl fmt::Display for Region {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Region {} ('{}')", self.index, self.name)
+        write!(f, "Region {} ('{}')", self.index, self.name)?;
+        if !self.namespace.is_empty() {
+            write!(f, " in namespace '{}'", self.namespace.join("/"))?;
+        }
+        Ok(())
     }
 }
 
@@ -260,6 +273,7 @@ impl From<(usize, String)> for Region {
             index,
             name,
             column_annotations: None,
+            namespace: vec![],
         }
     }
 }
@@ -270,6 +284,7 @@ impl From<(usize, &str)> for Region {
             index,
             name: name.to_owned(),
             column_annotations: None,
+            namespace: vec![],
         }
     }
 }
@@ -280,6 +295,7 @@ impl From<(usize, String, HashMap<ColumnMetadata, String>)> for Region {
             index,
             name,
             column_annotations: Some(annotations),
+            namespace: vec![],
         }
     }
 }
@@ -290,6 +306,7 @@ impl From<(usize, &str, HashMap<ColumnMetadata, String>)> for Region {
             index,
             name: name.to_owned(),
             column_annotations: Some(annotations),
+            namespace: vec![],
         }
     }
 }
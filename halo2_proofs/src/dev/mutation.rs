@@ -0,0 +1,225 @@
+//! Under-constraint detection by perturbing the witness.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryInto;
+use std::fmt;
+
+use blake2b_simd::blake2b;
+use rand_core::RngCore;
+
+use super::{metadata, CellValue, MockProver};
+use crate::{
+    arithmetic::FieldExt,
+    plonk::{Advice, Any},
+};
+
+/// Assigned advice cells, in a single column of a single region, whose values can be
+/// changed without [`MockProver::verify`] noticing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnconstrainedCells {
+    /// The region in which the cells were assigned, or `None` if they were assigned
+    /// outside of any region.
+    pub region: Option<metadata::Region>,
+    /// The column containing the cells.
+    pub column: metadata::Column,
+    /// The annotation of the column, if any.
+    pub annotation: Option<String>,
+    /// The offsets of the cells relative to the start of the region, or their rows if
+    /// they are outside of any region.
+    pub offsets: Vec<usize>,
+}
+
+impl fmt::Display for UnconstrainedCells {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.region {
+            Some(region) => write!(f, "{}, ", region)?,
+            None => write!(f, "Outside any region, ")?,
+        }
+        write!(f, "{}", self.column)?;
+        if let Some(annotation) = &self.annotation {
+            write!(f, " ('{}')", annotation)?;
+        }
+        write!(
+            f,
+            " at {} {:?}",
+            if self.region.is_some() {
+                "offsets"
+            } else {
+                "rows"
+            },
+            self.offsets
+        )
+    }
+}
+
+/// The result of [`MockProver::mutation_test`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MutationReport {
+    /// The number of assigned advice cells that were perturbed.
+    pub cells_tested: usize,
+    /// The perturbed cells that went undetected, grouped by region and column.
+    pub unconstrained: Vec<UnconstrainedCells>,
+}
+
+impl MutationReport {
+    /// Returns `true` if every perturbed cell was detected.
+    pub fn is_fully_constrained(&self) -> bool {
+        self.unconstrained.is_empty()
+    }
+}
+
+impl fmt::Display for MutationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let undetected: usize = self
+            .unconstrained
+            .iter()
+            .map(|cells| cells.offsets.len())
+            .sum();
+        writeln!(
+            f,
+            "{} of {} mutated advice cells went undetected",
+            undetected, self.cells_tested
+        )?;
+        for cells in &self.unconstrained {
+            writeln!(f, "- {}", cells)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, F: FieldExt> MockProver<'a, F> {
+    /// Checks whether the constraints pin down the witness, by perturbing each assigned
+    /// advice cell in turn and checking that the circuit is no longer satisfied.
+    ///
+    /// Only the gates and lookups whose inputs can query a perturbed cell are re-checked,
+    /// along with the permutation. Cells that can be perturbed without any of them
+    /// failing are reported, and usually indicate an under-constrained gadget.
+    ///
+    /// This should be run on a `MockProver` that passes [`MockProver::verify`];
+    /// otherwise every perturbation will look as if it had been detected. The witness
+    /// is restored before this method returns.
+    pub fn mutation_test(&mut self) -> MutationReport {
+        let cells = self.assigned_advice_cells();
+        self.mutate(cells)
+    }
+
+    /// Like [`MockProver::mutation_test`], but only perturbs a random sample of at most
+    /// `samples` assigned advice cells. This is useful for large circuits, where
+    /// perturbing every cell is too slow.
+    pub fn mutation_test_sampled(
+        &mut self,
+        samples: usize,
+        mut rng: impl RngCore,
+    ) -> MutationReport {
+        let mut cells = self.assigned_advice_cells();
+
+        // Partial Fisher-Yates shuffle.
+        let samples = samples.min(cells.len());
+        for i in 0..samples {
+            let j = i + (rng.next_u64() % (cells.len() - i) as u64) as usize;
+            cells.swap(i, j);
+        }
+        cells.truncate(samples);
+        cells.sort_unstable();
+
+        self.mutate(cells)
+    }
+
+    /// Returns the `(column, row)` of every assigned advice cell in the usable rows.
+    fn assigned_advice_cells(&self) -> Vec<(usize, usize)> {
+        self.advice
+            .iter()
+            .enumerate()
+            .flat_map(|(column, cells)| {
+                self.usable_rows
+                    .clone()
+                    .filter(move |&row| matches!(cells[row], CellValue::Assigned(_)))
+                    .map(move |row| (column, row))
+            })
+            .collect()
+    }
+
+    fn mutate(&mut self, cells: Vec<(usize, usize)>) -> MutationReport {
+        let n = self.n as i32;
+
+        // A fixed pseudorandom offset, so that the report is reproducible. A polynomial
+        // constraint that depends on a cell only fails to notice it with negligible
+        // probability.
+        let hash: [u8; 64] = blake2b(b"Halo2-MockProver-Mutation")
+            .as_bytes()
+            .try_into()
+            .unwrap();
+        let delta = F::from_bytes_wide(&hash);
+
+        let mut undetected = vec![];
+        for &(column, row) in &cells {
+            let original = self.advice[column][row];
+            let value = match original {
+                CellValue::Assigned(value) => value,
+                _ => unreachable!("only assigned cells are mutated"),
+            };
+
+            // The rows at which some query of this column lands on the mutated cell.
+            let rows: BTreeSet<usize> = self
+                .cs
+                .advice_queries
+                .iter()
+                .filter(|(c, _)| c.index() == column)
+                .map(|(_, rotation)| ((row as i32 - rotation.0 + n) % n) as usize)
+                .filter(|row| self.usable_rows.contains(row))
+                .collect();
+
+            self.advice[column][row] = CellValue::Assigned(value + delta);
+            let detected = self
+                .verify_at_rows_par(rows.iter().copied(), rows.iter().copied())
+                .is_err();
+            self.advice[column][row] = original;
+
+            if !detected {
+                undetected.push((column, row));
+            }
+        }
+
+        // Group the undetected cells by region and column.
+        let mut groups = BTreeMap::<(Option<usize>, usize), Vec<usize>>::new();
+        for (column, row) in undetected {
+            let region = self.regions.iter().position(|r| {
+                r.columns
+                    .iter()
+                    .any(|c| c.index() == column && matches!(c.column_type(), Any::Advice(_)))
+                    && r.rows
+                        .map_or(false, |(start, end)| (start..=end).contains(&row))
+            });
+            let offset = region.map_or(row, |r_i| row - self.regions[r_i].rows.unwrap().0);
+            groups.entry((region, column)).or_default().push(offset);
+        }
+
+        let unconstrained = groups
+            .into_iter()
+            .map(|((region, column), offsets)| {
+                let column: metadata::Column = (
+                    Any::Advice(Advice {
+                        phase: self.cs.advice_column_phase[column],
+                    }),
+                    column,
+                )
+                    .into();
+                let annotation = region
+                    .and_then(|r_i| self.regions[r_i].annotations.get(&column))
+                    .or_else(|| self.cs.general_column_annotations.get(&column))
+                    .cloned();
+                UnconstrainedCells {
+                    region: region.map(|r_i| self.regions[r_i].metadata(r_i)),
+                    column,
+                    annotation,
+                    offsets,
+                }
+            })
+            .collect();
+
+        MutationReport {
+            cells_tested: cells.len(),
+            unconstrained,
+        }
+    }
+}
@@ -73,6 +73,14 @@ pub(super) fn format_value<F: Field>(v: F) -> String {
     }
 }
 
+/// Formats a cell value, rendering unassigned cells in unusable rows as `Poison`.
+pub(super) fn format_cell_value<F: Field>(v: Value<F>) -> String {
+    match v {
+        Value::Real(v) => format_value(v),
+        Value::Poison => "Poison".into(),
+    }
+}
+
 /*
 pub(super) fn load<'a, F: FieldExt, T: ColumnType, Q: Into<AnyQuery> + Copy>(
     n: i32,
@@ -175,3 +183,53 @@ pub(super) fn cell_values<'a, F: FieldExt>(
     );
     cell_values.into_iter().collect()
 }
+
+fn any_cell_value<'a, F: FieldExt, Q: Into<AnyQuery> + Copy>(
+    load: impl Fn(Q) -> Value<F> + 'a,
+) -> impl Fn(Q) -> BTreeMap<metadata::VirtualCell, String> + 'a {
+    move |query| {
+        let AnyQuery {
+            column_type,
+            column_index,
+            rotation,
+            ..
+        } = query.into();
+        Some((
+            ((column_type, column_index).into(), rotation.0).into(),
+            format_cell_value(load(query)),
+        ))
+        .into_iter()
+        .collect()
+    }
+}
+
+/// Collects the values of every cell queried by `expression`.
+///
+/// Unlike [`cell_values`], this does not filter by the cells a gate declares, because
+/// by the time lookups are checked their selectors have been compressed into fixed
+/// columns.
+pub(super) fn expression_cell_values<'a, F: FieldExt>(
+    expression: &Expression<F>,
+    load_fixed: impl Fn(FixedQuery) -> Value<F> + 'a,
+    load_advice: impl Fn(AdviceQuery) -> Value<F> + 'a,
+    load_instance: impl Fn(InstanceQuery) -> Value<F> + 'a,
+) -> BTreeMap<metadata::VirtualCell, String> {
+    expression.evaluate(
+        &|_| BTreeMap::default(),
+        &|_| panic!("virtual selectors are removed during optimization"),
+        &any_cell_value(load_fixed),
+        &any_cell_value(load_advice),
+        &any_cell_value(load_instance),
+        &|_| BTreeMap::default(),
+        &|a| a,
+        &|mut a, mut b| {
+            a.append(&mut b);
+            a
+        },
+        &|mut a, mut b| {
+            a.append(&mut b);
+            a
+        },
+        &|a, _| a,
+    )
+}
@@ -24,6 +24,7 @@ use crate::SerdeFormat;
 
 mod assigned;
 mod circuit;
+mod custom_argument;
 mod error;
 mod evaluation;
 mod keygen;
@@ -36,6 +37,7 @@ mod verifier;
 
 pub use assigned::*;
 pub use circuit::*;
+pub use custom_argument::{ArgumentExpression, ArgumentInputs, CustomArgument};
 pub use error::*;
 pub use keygen::*;
 pub use prover::*;
@@ -54,6 +56,8 @@ pub struct VerifyingKey<C: CurveAffine> {
     cs: ConstraintSystem<C::Scalar>,
     /// Cached maximum degree of `cs` (which doesn't change after construction).
     cs_degree: usize,
+    /// Cached simplification of the gates of `cs`.
+    expression_dag: ExpressionDag<C::Scalar>,
     /// The representative of this `VerifyingKey` in transcripts.
     transcript_repr: C::Scalar,
     //selectors: Vec<Vec<bool>>,
@@ -182,6 +186,7 @@ impl<C: CurveAffine> VerifyingKey<C> {
     ) -> Self {
         // Compute cached values.
         let cs_degree = cs.degree();
+        let expression_dag = cs.expression_dag();
 
         let mut vk = Self {
             domain,
@@ -189,6 +194,7 @@ impl<C: CurveAffine> VerifyingKey<C> {
             permutation,
             cs,
             cs_degree,
+            expression_dag,
             // Temporary, this is not pinned.
             transcript_repr: C::Scalar::zero(),
             //selectors,
@@ -247,6 +253,11 @@ impl<C: CurveAffine> VerifyingKey<C> {
     pub fn cs(&self) -> &ConstraintSystem<C::Scalar> {
         &self.cs
     }
+
+    /// Returns the simplified gates of the `ConstraintSystem`
+    pub fn expression_dag(&self) -> &ExpressionDag<C::Scalar> {
+        &self.expression_dag
+    }
 }
 
 /// Minimal representation of a verification key that can be used to identify
@@ -343,7 +354,7 @@ where
         let fixed_polys = read_polynomial_vec(reader, format)?;
         //let fixed_cosets = read_polynomial_vec(reader, format)?;
         let permutation = permutation::ProvingKey::read(reader, format)?;
-        let ev = Evaluator::new(vk.cs());
+        let ev = Evaluator::new(vk.cs(), vk.expression_dag());
         Ok(Self {
             vk,
             l0,
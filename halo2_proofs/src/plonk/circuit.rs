@@ -3,20 +3,31 @@ use core::ops::{Add, Mul};
 use ff::Field;
 use std::collections::HashMap;
 use std::ops::Range;
+use std::sync::Arc;
 use std::{
     convert::TryFrom,
     ops::{Neg, Sub},
 };
 
-use super::{lookup, permutation, Assigned, Error};
+use super::{custom_argument, lookup, permutation, Assigned, Error};
 use crate::dev::metadata;
 use crate::{
+    arithmetic::FieldExt,
     circuit::{Layouter, Region, Value},
     poly::Rotation,
 };
 use sealed::SealedPhase;
 
+mod composition;
 mod compress_selectors;
+mod constraint_builder;
+mod degree_reduction;
+mod expression_dag;
+
+pub use compress_selectors::{CompressedSelectors, SelectorCompression};
+pub use constraint_builder::{ConstraintBuilder, SubExpression};
+pub(crate) use expression_dag::DagNode;
+pub use expression_dag::{ExpressionDag, GateSavings};
 
 /// A column type
 pub trait ColumnType:
@@ -118,6 +129,28 @@ impl SealedPhase for super::ThirdPhase {
     }
 }
 
+/// The phase with index `N`, counting from zero, for circuits that need more
+/// interaction rounds than [`FirstPhase`], [`SecondPhase`] and [`ThirdPhase`] provide.
+/// `NthPhase<0>` is the same phase as `FirstPhase`, `NthPhase<3>` is the fourth phase,
+/// and so on.
+///
+/// As with the named phases, an advice column can only be allocated in a phase if the
+/// previous phase has at least one advice column.
+#[derive(Debug)]
+pub struct NthPhase<const N: u8>;
+
+impl<const N: u8> SealedPhase for NthPhase<N> {
+    fn to_sealed(self) -> sealed::Phase {
+        sealed::Phase(N)
+    }
+}
+
+impl SealedPhase for sealed::Phase {
+    fn to_sealed(self) -> sealed::Phase {
+        self
+    }
+}
+
 /// An advice column
 #[derive(Clone, Copy, Eq, PartialEq, Hash)]
 pub struct Advice {
@@ -505,6 +538,45 @@ impl TableColumn {
     }
 }
 
+/// A lookup table that shares its [`TableColumn`]s with other tables.
+///
+/// Each row of a tagged table is stored alongside its tag in a tag column, so that
+/// several small tables can be packed into the same columns. A tagged table is declared
+/// with [`ConstraintSystem::tagged_lookup_table`], loaded with
+/// [`Layouter::assign_tagged_table`], and looked up with
+/// [`ConstraintSystem::lookup_tagged`].
+///
+/// [`Layouter::assign_tagged_table`]: crate::circuit::Layouter::assign_tagged_table
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct TaggedTable {
+    tag_column: TableColumn,
+    tag: u64,
+}
+
+impl TaggedTable {
+    /// Returns the column that the tags of this table are stored in.
+    pub fn tag_column(&self) -> TableColumn {
+        self.tag_column
+    }
+
+    /// Returns the tag of this table, which is never zero.
+    pub fn tag(&self) -> u64 {
+        self.tag
+    }
+
+    /// Returns the tag of this table as a field element.
+    pub(crate) fn tag_value<F: Field>(&self) -> F {
+        (0..u64::BITS).rev().fold(F::zero(), |acc, bit| {
+            let acc = acc.double();
+            if (self.tag >> bit) & 1 == 1 {
+                acc + F::one()
+            } else {
+                acc
+            }
+        })
+    }
+}
+
 /// A challenge squeezed from transcript after advice columns at the phase have been committed.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub struct Challenge {
@@ -632,6 +704,12 @@ pub trait Assignment<F: Field>: Sized + Send {
     /// Returns `Value::unknown()` if the current synthesis phase is before the challenge can be queried.
     fn get_challenge(&self, challenge: Challenge) -> Value<F>;
 
+    /// Returns the number of rows that can be assigned, `n - (blinding_factors + 1)`, if
+    /// the backend synthesizes the circuit for a known `n`.
+    fn usable_rows(&self) -> Option<usize> {
+        None
+    }
+
     /// Creates a new (sub)namespace and enters into it.
     ///
     /// Not intended for downstream consumption; use [`Layouter::namespace`] instead.
@@ -1393,6 +1471,12 @@ pub struct ConstraintSystem<F: Field> {
     /// fixed column that they were compressed into. This is just used by dev
     /// tooling right now.
     pub(crate) selector_map: Vec<Column<Fixed>>,
+
+    // How simple selectors are combined into fixed columns.
+    pub(crate) selector_compression: SelectorCompression,
+
+    // The fixed columns that selectors were combined into, once they are compressed.
+    pub(crate) compressed_selectors: Vec<CompressedSelectors>,
     pub gates: Vec<Gate<F>>,
     pub advice_queries: Vec<(Column<Advice>, Rotation)>,
     // Contains an integer for each advice column
@@ -1409,14 +1493,33 @@ pub struct ConstraintSystem<F: Field> {
     // input expressions and a sequence of table expressions involved in the lookup.
     pub lookups: Vec<lookup::Argument<F>>,
 
+    // Vector of custom arguments, along with their inputs and constraints.
+    pub(crate) custom_arguments: Vec<custom_argument::Argument<F>>,
+
     // List of indexes of Fixed columns which are associated to a circuit-general Column tied to their annotation.
     pub(crate) general_column_annotations: HashMap<metadata::Column, String>,
 
+    // Annotations of selectors, by selector index.
+    pub(crate) selector_annotations: HashMap<usize, String>,
+
     // Vector of fixed columns, which can be used to store constant values
     // that are copied into advice columns.
     pub(crate) constants: Vec<Column<Fixed>>,
 
     pub(crate) minimum_degree: Option<usize>,
+
+    // The maximum degree of the constraints of gates, above which they are split.
+    pub(crate) max_gate_degree: Option<usize>,
+
+    // Advice columns allocated to reduce the degree of gates, along with the
+    // expressions that the prover fills them in with.
+    pub(crate) intermediate_columns: Vec<(Column<Advice>, Expression<F>)>,
+
+    // The last tag given to a table in each tag column.
+    pub(crate) table_tags: HashMap<TableColumn, u64>,
+
+    // The columns of the sub-circuit that is being configured, if any.
+    pub(crate) sub_circuit: Option<composition::SubCircuitColumns>,
 }
 
 /// Represents the minimal parameters that determine a `ConstraintSystem`.
@@ -1435,6 +1538,7 @@ pub struct PinnedConstraintSystem<'a, F: Field> {
     fixed_queries: &'a Vec<(Column<Fixed>, Rotation)>,
     permutation: &'a permutation::Argument,
     lookups: &'a Vec<lookup::Argument<F>>,
+    custom_arguments: &'a Vec<custom_argument::Argument<F>>,
     constants: &'a Vec<Column<Fixed>>,
     minimum_degree: &'a Option<usize>,
 }
@@ -1460,7 +1564,12 @@ impl<'a, F: Field> std::fmt::Debug for PinnedConstraintSystem<'a, F> {
             .field("instance_queries", self.instance_queries)
             .field("fixed_queries", self.fixed_queries)
             .field("permutation", self.permutation)
-            .field("lookups", self.lookups)
+            .field("lookups", self.lookups);
+        // Only show custom arguments if there are any.
+        if !self.custom_arguments.is_empty() {
+            debug_struct.field("custom_arguments", self.custom_arguments);
+        }
+        debug_struct
             .field("constants", self.constants)
             .field("minimum_degree", self.minimum_degree);
         debug_struct.finish()
@@ -1489,6 +1598,8 @@ impl<F: Field> Default for ConstraintSystem<F> {
             advice_column_phase: Vec::new(),
             challenge_phase: Vec::new(),
             selector_map: vec![],
+            selector_compression: SelectorCompression::default(),
+            compressed_selectors: vec![],
             gates: vec![],
             fixed_queries: Vec::new(),
             advice_queries: Vec::new(),
@@ -1496,9 +1607,15 @@ impl<F: Field> Default for ConstraintSystem<F> {
             instance_queries: Vec::new(),
             permutation: permutation::Argument::new(),
             lookups: Vec::new(),
+            custom_arguments: Vec::new(),
             general_column_annotations: HashMap::new(),
+            selector_annotations: HashMap::new(),
             constants: vec![],
             minimum_degree: None,
+            max_gate_degree: None,
+            intermediate_columns: vec![],
+            table_tags: HashMap::new(),
+            sub_circuit: None,
         }
     }
 }
@@ -1522,6 +1639,7 @@ impl<F: Field> ConstraintSystem<F> {
             instance_queries: &self.instance_queries,
             permutation: &self.permutation,
             lookups: &self.lookups,
+            custom_arguments: &self.custom_arguments,
             constants: &self.constants,
             minimum_degree: &self.minimum_degree,
         }
@@ -1595,6 +1713,150 @@ impl<F: Field> ConstraintSystem<F> {
         index
     }
 
+    /// Add a lookup argument for some input expressions into a [`TaggedTable`].
+    ///
+    /// `table_map` returns an expression that enables the lookup, which is usually a
+    /// selector query, and a map between input expressions and the table columns they
+    /// need to match. The input expressions, like the enabling expression, should be
+    /// zero in rows where the lookup is disabled: every tag column contains a row of
+    /// zeroes for them to match.
+    pub fn lookup_tagged(
+        &mut self,
+        name: &'static str,
+        table: TaggedTable,
+        table_map: impl FnOnce(
+            &mut VirtualCells<'_, F>,
+        ) -> (Expression<F>, Vec<(Expression<F>, TableColumn)>),
+    ) -> usize {
+        let mut cells = VirtualCells::new(self);
+        let (enable, table_map) = table_map(&mut cells);
+        let tag = enable * Expression::Constant(table.tag_value());
+
+        self.lookup(name, |_| {
+            std::iter::once((tag, table.tag_column))
+                .chain(table_map)
+                .collect()
+        })
+    }
+
+    /// Adds a [`CustomArgument`] to the circuit, and returns its index.
+    ///
+    /// `argument` queries the cells that the inputs and constraints of the argument use.
+    /// The columns of the argument are committed after the lookup and permutation
+    /// products, so that they can be computed from every challenge of the circuit as
+    /// well as `theta`, `beta` and `gamma`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an input or constraint of the argument contains a simple selector.
+    ///
+    /// [`CustomArgument`]: crate::plonk::CustomArgument
+    pub fn custom_argument<A: custom_argument::CustomArgument<F> + 'static>(
+        &mut self,
+        name: &'static str,
+        argument: impl FnOnce(&mut VirtualCells<'_, F>) -> A,
+    ) -> usize {
+        self.add_custom_argument(name, argument, None)
+    }
+
+    /// Adds a [`CustomArgument`] whose columns are committed along with the advice
+    /// columns of `phase`, and returns its index.
+    ///
+    /// The columns of the argument are computed before the challenges usable after
+    /// `phase` are squeezed, so those challenges can depend on them. In turn, the
+    /// inputs of the argument may only query advice columns of `phase` or an earlier
+    /// phase, and challenges usable after an earlier phase, and the argument cannot use
+    /// `theta`, `beta` or `gamma` to compute its columns. Its constraints may still
+    /// use every challenge.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no advice column is in `phase`, if an input of the argument queries a
+    /// column or challenge that is not available in `phase`, or if an input or
+    /// constraint of the argument contains a simple selector.
+    ///
+    /// [`CustomArgument`]: crate::plonk::CustomArgument
+    pub fn custom_argument_in<P: Phase, A: custom_argument::CustomArgument<F> + 'static>(
+        &mut self,
+        phase: P,
+        name: &'static str,
+        argument: impl FnOnce(&mut VirtualCells<'_, F>) -> A,
+    ) -> usize {
+        let phase = phase.to_sealed();
+        self.assert_phase_exists(
+            phase,
+            format!("custom argument in phase {:?}", phase).as_str(),
+        );
+
+        self.add_custom_argument(name, argument, Some(phase))
+    }
+
+    fn add_custom_argument<A: custom_argument::CustomArgument<F> + 'static>(
+        &mut self,
+        name: &'static str,
+        argument: impl FnOnce(&mut VirtualCells<'_, F>) -> A,
+        phase: Option<sealed::Phase>,
+    ) -> usize {
+        let mut cells = VirtualCells::new(self);
+        let argument = argument(&mut cells);
+        let argument = custom_argument::Argument::new(name, Arc::new(argument), phase);
+
+        let constraint_contains_simple_selector =
+            |constraint: &custom_argument::ArgumentExpression<F>| {
+                constraint.evaluate(
+                    &|leaf| match leaf {
+                        custom_argument::ArgumentExpression::Circuit(expression) => {
+                            expression.contains_simple_selector()
+                        }
+                        _ => false,
+                    },
+                    &|a| a,
+                    &|a, b| a || b,
+                    &|a, b| a || b,
+                    &|a, _| a,
+                )
+            };
+        if argument
+            .inputs
+            .iter()
+            .any(|input| input.contains_simple_selector())
+            || argument
+                .constraints
+                .iter()
+                .any(constraint_contains_simple_selector)
+        {
+            panic!("expression containing simple selector supplied to custom argument");
+        }
+
+        if let Some(phase) = phase {
+            for input in argument.inputs.iter() {
+                let available = input.evaluate(
+                    &|_| true,
+                    &|_| true,
+                    &|_| true,
+                    &|query| query.phase <= phase,
+                    &|_| true,
+                    &|challenge| challenge.phase() < phase.0,
+                    &|a| a,
+                    &|a, b| a && b,
+                    &|a, b| a && b,
+                    &|a, _| a,
+                );
+                assert!(
+                    available,
+                    "custom argument '{}' in phase {:?} has an input that queries a later column or challenge",
+                    name, phase
+                );
+            }
+        }
+
+        let index = self.custom_arguments.len();
+
+        self.custom_arguments.push(argument);
+
+        index
+    }
+
     fn query_fixed_index(&mut self, column: Column<Fixed>, at: Rotation) -> usize {
         // Return existing query, if it exists
         for (index, fixed_query) in self.fixed_queries.iter().enumerate() {
@@ -1704,12 +1966,49 @@ impl<F: Field> ConstraintSystem<F> {
         self.minimum_degree = Some(degree);
     }
 
+    /// Sets how simple selectors are combined into fixed columns when the keys of the
+    /// circuit are generated, trading the number of fixed columns against the degree
+    /// of the gates that use them.
+    ///
+    /// The setting is part of the constraint system, so it must be made in
+    /// [`Circuit::configure`] for key generation, [`MockProver`] and the verifier to
+    /// compress selectors the same way.
+    ///
+    /// [`MockProver`]: crate::dev::MockProver
+    pub fn set_selector_compression(&mut self, compression: SelectorCompression) {
+        self.selector_compression = compression;
+    }
+
+    /// Returns the fixed columns that selectors were combined into, with the degree
+    /// that it cost, once the selectors of this constraint system are compressed, as
+    /// they are in the constraint system of a [`VerifyingKey`].
+    ///
+    /// [`VerifyingKey`]: crate::plonk::VerifyingKey
+    pub fn compressed_selectors(&self) -> &[CompressedSelectors] {
+        &self.compressed_selectors
+    }
+
+    /// Sets the maximum degree of the constraints of gates created after this call.
+    /// Constraints of a higher degree are split into several constraints of at most
+    /// this degree, by moving subexpressions into new advice columns that the prover
+    /// fills in from the rest of the witness. This keeps a single high-degree gate from
+    /// raising the degree of the whole circuit.
+    ///
+    /// Only constraints that are multiplied by one or more selectors can be split, and
+    /// `degree` must leave room for those selectors and a product of two columns.
+    pub fn set_max_gate_degree(&mut self, degree: usize) {
+        self.max_gate_degree = Some(degree);
+    }
+
     /// Creates a new gate.
     ///
     /// # Panics
     ///
     /// A gate is required to contain polynomial constraints. This method will panic if
     /// `constraints` returns an empty iterator.
+    ///
+    /// If a maximum gate degree was set with [`ConstraintSystem::set_max_gate_degree`],
+    /// this method will panic if a constraint exceeds it and cannot be split.
     pub fn create_gate<C: Into<Constraint<F>>, Iter: IntoIterator<Item = C>>(
         &mut self,
         name: &'static str,
@@ -1731,6 +2030,24 @@ impl<F: Field> ConstraintSystem<F> {
             "Gates must contain at least one constraint."
         );
 
+        let (constraint_names, polys) = match self.max_gate_degree {
+            Some(max_degree) => {
+                let mut names = vec![];
+                let mut reduced = vec![];
+                for (name, poly) in constraint_names.into_iter().zip(polys) {
+                    let constraints = self.reduce_degree(poly, max_degree);
+                    names.push(name);
+                    names.extend(
+                        std::iter::repeat(degree_reduction::INTERMEDIATE_CONSTRAINT)
+                            .take(constraints.len() - 1),
+                    );
+                    reduced.extend(constraints);
+                }
+                (names, reduced)
+            }
+            None => (constraint_names, polys),
+        };
+
         self.gates.push(Gate {
             name,
             constraint_names,
@@ -1771,7 +2088,7 @@ impl<F: Field> ConstraintSystem<F> {
         let (polys, selector_assignment) = compress_selectors::process(
             selectors
                 .into_iter()
-                .zip(degrees.into_iter())
+                .zip(degrees.iter().copied())
                 .enumerate()
                 .map(
                     |(i, (activations, max_degree))| compress_selectors::SelectorDescription {
@@ -1782,6 +2099,7 @@ impl<F: Field> ConstraintSystem<F> {
                 )
                 .collect(),
             max_degree,
+            self.selector_compression.max_combination_len(),
             || {
                 let column = self.fixed_column();
                 new_columns.push(column);
@@ -1793,6 +2111,36 @@ impl<F: Field> ConstraintSystem<F> {
             },
         );
 
+        self.compressed_selectors = new_columns
+            .iter()
+            .map(|&column| CompressedSelectors {
+                column,
+                selectors: vec![],
+                names: vec![],
+                degree_increase: 0,
+                max_degree: 0,
+            })
+            .collect();
+        for assignment in selector_assignment.iter() {
+            let compressed = &mut self.compressed_selectors[assignment.combination_index];
+            compressed.selectors.push(assignment.selector);
+            compressed.names.push(
+                self.selector_annotations
+                    .get(&assignment.selector)
+                    .cloned()
+                    .unwrap_or_else(|| format!("selector {}", assignment.selector)),
+            );
+            // The selector is replaced by a product of one more factor than the
+            // selectors it was combined with.
+            if degrees[assignment.selector] > 0 {
+                compressed.max_degree = max(
+                    compressed.max_degree,
+                    degrees[assignment.selector] - 1 + assignment.expression.degree(),
+                );
+                compressed.degree_increase = assignment.expression.degree() - 1;
+            }
+        }
+
         let mut selector_map = vec![None; selector_assignment.len()];
         let mut selector_replacements = vec![None; selector_assignment.len()];
         for assignment in selector_assignment {
@@ -1853,6 +2201,25 @@ impl<F: Field> ConstraintSystem<F> {
             replace_selectors(expr, &selector_replacements, true);
         }
 
+        // Substitute non-simple selectors for the real fixed columns in the
+        // inputs and constraints of custom arguments
+        for argument in self.custom_arguments.iter_mut() {
+            for expr in argument.inputs.iter_mut() {
+                replace_selectors(expr, &selector_replacements, true);
+            }
+            for constraint in argument.constraints.iter_mut() {
+                constraint.for_each_circuit_expression_mut(&mut |expr| {
+                    replace_selectors(expr, &selector_replacements, true)
+                });
+            }
+        }
+
+        // Substitute selectors for the real fixed columns in the expressions of
+        // intermediate columns
+        for (_, expr) in self.intermediate_columns.iter_mut() {
+            replace_selectors(expr, &selector_replacements, false);
+        }
+
         (self, polys)
     }
 
@@ -1882,6 +2249,18 @@ impl<F: Field> ConstraintSystem<F> {
         }
     }
 
+    /// Declares a new lookup table whose rows will be tagged in `tag_column`, which
+    /// should be a column allocated with [`ConstraintSystem::lookup_table_column`]. The
+    /// tables tagged in the same tag column are packed into the same table columns.
+    pub fn tagged_lookup_table(&mut self, tag_column: TableColumn) -> TaggedTable {
+        let tag = self.table_tags.entry(tag_column).or_insert(0);
+        *tag += 1;
+        TaggedTable {
+            tag_column,
+            tag: *tag,
+        }
+    }
+
     /// Annotate a Lookup column.
     pub fn annotate_lookup_column<A, AR>(&mut self, column: TableColumn, annotation: A)
     where
@@ -1910,6 +2289,17 @@ impl<F: Field> ConstraintSystem<F> {
         );
     }
 
+    /// Annotate a selector.
+    pub fn annotate_selector<A, AR>(&mut self, selector: Selector, annotation: A)
+    where
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        // We don't care if the selector has already an annotation. If it's the case we keep the new one.
+        self.selector_annotations
+            .insert(selector.0, annotation().into());
+    }
+
     /// Allocate a new fixed column
     pub fn fixed_column(&mut self) -> Column<Fixed> {
         let tmp = Column {
@@ -1935,6 +2325,11 @@ impl<F: Field> ConstraintSystem<F> {
             );
         }
 
+        self.shared_advice_column(phase)
+            .unwrap_or_else(|| self.allocate_advice_column(phase))
+    }
+
+    fn allocate_advice_column(&mut self, phase: sealed::Phase) -> Column<Advice> {
         let tmp = Column {
             index: self.num_advice_columns,
             column_type: Advice { phase },
@@ -1947,11 +2342,17 @@ impl<F: Field> ConstraintSystem<F> {
 
     /// Allocate a new instance column
     pub fn instance_column(&mut self) -> Column<Instance> {
-        let tmp = Column {
-            index: self.num_instance_columns,
-            column_type: Instance,
-        };
-        self.num_instance_columns += 1;
+        let tmp = self.shared_instance_column().unwrap_or_else(|| {
+            let tmp = Column {
+                index: self.num_instance_columns,
+                column_type: Instance,
+            };
+            self.num_instance_columns += 1;
+            tmp
+        });
+        if let Some(sub_circuit) = self.sub_circuit.as_mut() {
+            sub_circuit.instance_columns.push(tmp);
+        }
         tmp
     }
 
@@ -1996,6 +2397,11 @@ impl<F: Field> ConstraintSystem<F> {
             .unwrap_or_default()
     }
 
+    /// Returns the number of phases in which the circuit's advice columns are committed.
+    pub fn num_phases(&self) -> usize {
+        usize::from(self.max_phase()) + 1
+    }
+
     pub fn phases(&self) -> impl Iterator<Item = sealed::Phase> {
         let max_phase = self
             .advice_column_phase
@@ -2024,6 +2430,16 @@ impl<F: Field> ConstraintSystem<F> {
                 .unwrap_or(1),
         );
 
+        // Custom arguments also serve alongside the gates.
+        degree = std::cmp::max(
+            degree,
+            self.custom_arguments
+                .iter()
+                .map(|argument| argument.required_degree())
+                .max()
+                .unwrap_or(1),
+        );
+
         // Account for each gate to ensure our quotient polynomial is the
         // correct degree and that our extended domain is the right size.
         degree = std::cmp::max(
@@ -2048,7 +2464,13 @@ impl<F: Field> ConstraintSystem<F> {
         // - The permutation argument witness polynomials are evaluated at most 3 times.
         // - Each lookup argument has independent witness polynomials, and they are
         //   evaluated at most 2 times.
-        let factors = std::cmp::max(3, factors);
+        // - The columns of each custom argument are evaluated at every rotation at
+        //   which its constraints query them.
+        let factors = self
+            .custom_arguments
+            .iter()
+            .map(|argument| argument.max_column_queries())
+            .fold(std::cmp::max(3, factors), std::cmp::max);
 
         // Each polynomial is evaluated at most an additional time during
         // multiopen (at x_3 to produce q_evals):
@@ -2146,6 +2568,20 @@ impl<F: Field> ConstraintSystem<F> {
     pub fn constants(&self) -> &Vec<Column<Fixed>> {
         &self.constants
     }
+
+    /// Returns the advice columns allocated to reduce the degree of gates, along with
+    /// the expressions they are equal to
+    pub fn intermediate_columns(&self) -> &[(Column<Advice>, Expression<F>)] {
+        &self.intermediate_columns
+    }
+}
+
+impl<F: FieldExt> ConstraintSystem<F> {
+    /// Simplifies the gates of this constraint system into an [`ExpressionDag`], which
+    /// can report how many expression nodes the simplification saves in each gate.
+    pub fn expression_dag(&self) -> ExpressionDag<F> {
+        ExpressionDag::new(&self.gates)
+    }
 }
 
 /// Exposes the "virtual cells" that can be queried while creating a custom gate or lookup
@@ -0,0 +1,151 @@
+use std::collections::BTreeSet;
+
+use ff::Field;
+
+use super::{sealed, Advice, Circuit, Column, ConstraintSystem, Instance};
+
+/// The columns of a sub-circuit that is being configured.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SubCircuitColumns {
+    /// Existing advice columns that the sub-circuit can allocate.
+    advice: Vec<Column<Advice>>,
+    /// Existing instance columns that the sub-circuit can allocate.
+    instance: Vec<Column<Instance>>,
+    /// The instance columns allocated by the sub-circuit, in order.
+    pub(super) instance_columns: Vec<Column<Instance>>,
+}
+
+impl<F: Field> ConstraintSystem<F> {
+    /// Configures `ConcreteCircuit` as a sub-circuit of this constraint system, and
+    /// returns its configuration along with the instance columns it allocated.
+    ///
+    /// If `share_columns` is set, the advice and instance columns that the sub-circuit
+    /// allocates are taken from the existing ones where possible. Fixed columns are
+    /// never shared, as they may hold lookup tables, and neither are advice columns
+    /// that an existing gate or lookup queries without a selector or fixed column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sub-circuit adds a gate or lookup that queries one of the shared
+    /// advice columns without a selector or fixed column, as it would constrain the
+    /// cells of the other circuits in that column.
+    pub(crate) fn configure_sub_circuit<ConcreteCircuit: Circuit<F>>(
+        &mut self,
+        share_columns: bool,
+    ) -> (ConcreteCircuit::Config, Vec<Column<Instance>>) {
+        let columns = if share_columns {
+            let intermediate: Vec<_> = self
+                .intermediate_columns
+                .iter()
+                .map(|(column, _)| column.index())
+                .collect();
+            let ungated = self.ungated_advice_columns(0, 0);
+            SubCircuitColumns {
+                advice: (0..self.num_advice_columns)
+                    .filter(|index| !intermediate.contains(index) && !ungated.contains(index))
+                    .map(|index| Column {
+                        index,
+                        column_type: Advice {
+                            phase: self.advice_column_phase[index],
+                        },
+                    })
+                    .collect(),
+                instance: (0..self.num_instance_columns)
+                    .map(|index| Column {
+                        index,
+                        column_type: Instance,
+                    })
+                    .collect(),
+                instance_columns: vec![],
+            }
+        } else {
+            SubCircuitColumns::default()
+        };
+
+        let shared = columns.advice.clone();
+        let (num_gates, num_lookups) = (self.gates.len(), self.lookups.len());
+
+        // Sub-circuits can themselves be composed of sub-circuits.
+        let outer = std::mem::replace(&mut self.sub_circuit, Some(columns));
+        let config = ConcreteCircuit::configure(self);
+        let columns = std::mem::replace(&mut self.sub_circuit, outer)
+            .expect("the sub-circuit's columns are restored by the time it is configured");
+
+        let ungated = self.ungated_advice_columns(num_gates, num_lookups);
+        for column in shared
+            .iter()
+            .filter(|column| !columns.advice.contains(column))
+        {
+            assert!(
+                !ungated.contains(&column.index()),
+                "the sub-circuit queries the shared advice column {:?} in a gate or lookup \
+                 without a selector or fixed column",
+                column
+            );
+        }
+
+        (config, columns.instance_columns)
+    }
+
+    /// Returns the indices of the advice columns that a gate from `gates` onwards, or a
+    /// lookup from `lookups` onwards, queries in a polynomial, input or table expression
+    /// that does not query a selector or fixed column. Such a gate or lookup applies to
+    /// every row, including the rows of any other sub-circuit.
+    fn ungated_advice_columns(&self, gates: usize, lookups: usize) -> BTreeSet<usize> {
+        let gate_expressions = self.gates[gates..]
+            .iter()
+            .flat_map(|gate| gate.polys.iter());
+        let lookup_expressions = self.lookups[lookups..].iter().flat_map(|lookup| {
+            lookup
+                .input_expressions
+                .iter()
+                .chain(lookup.table_expressions.iter())
+        });
+        gate_expressions
+            .chain(lookup_expressions)
+            .filter_map(|expression| {
+                let (columns, gated) = expression.evaluate(
+                    &|_| (vec![], false),
+                    &|_| (vec![], true),
+                    &|_| (vec![], true),
+                    &|query| (vec![query.column_index], false),
+                    &|_| (vec![], false),
+                    &|_| (vec![], false),
+                    &|a| a,
+                    &|(mut a, a_gated), (b, b_gated)| {
+                        a.extend(b);
+                        (a, a_gated && b_gated)
+                    },
+                    &|(mut a, a_gated), (b, b_gated)| {
+                        a.extend(b);
+                        (a, a_gated || b_gated)
+                    },
+                    &|a, _| a,
+                );
+                (!gated).then(|| columns)
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Takes an existing advice column in `phase` that the sub-circuit being configured
+    /// has not allocated yet, if there is one.
+    pub(super) fn shared_advice_column(&mut self, phase: sealed::Phase) -> Option<Column<Advice>> {
+        let advice = &mut self.sub_circuit.as_mut()?.advice;
+        let index = advice
+            .iter()
+            .position(|column| column.column_type().phase == phase)?;
+        Some(advice.remove(index))
+    }
+
+    /// Takes an existing instance column that the sub-circuit being configured has not
+    /// allocated yet, if there is one.
+    pub(super) fn shared_instance_column(&mut self) -> Option<Column<Instance>> {
+        let instance = &mut self.sub_circuit.as_mut()?.instance;
+        if instance.is_empty() {
+            None
+        } else {
+            Some(instance.remove(0))
+        }
+    }
+}
@@ -1,5 +1,101 @@
-use super::Expression;
+use super::{Column, Expression, Fixed, Selector};
 use ff::Field;
+use std::fmt;
+
+/// How the simple selectors of a constraint system are combined into fixed columns
+/// when its keys are generated.
+///
+/// Combining selectors that are never enabled on the same row reduces the number of
+/// fixed columns, and so the size of the keys and the cost of the proof, at the cost
+/// of raising the degree of the gates that use them. The degree of the constraint
+/// system itself is never raised.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectorCompression {
+    /// Combine as many selectors as the degree of the constraint system allows. This
+    /// is the default.
+    Full,
+    /// Combine selectors such that the degree of each gate is raised by at most the
+    /// given amount.
+    MaxDegreeIncrease(usize),
+    /// Give each selector its own fixed column.
+    Disabled,
+}
+
+impl Default for SelectorCompression {
+    fn default() -> Self {
+        SelectorCompression::Full
+    }
+}
+
+impl SelectorCompression {
+    /// Returns the maximum number of selectors combined into a single fixed column.
+    pub(crate) fn max_combination_len(&self) -> usize {
+        match self {
+            SelectorCompression::Full => usize::MAX,
+            SelectorCompression::MaxDegreeIncrease(increase) => increase.saturating_add(1),
+            SelectorCompression::Disabled => 1,
+        }
+    }
+}
+
+/// The selectors that were combined into a fixed column when the selectors of a
+/// constraint system were compressed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompressedSelectors {
+    pub(crate) column: Column<Fixed>,
+    pub(crate) selectors: Vec<usize>,
+    pub(crate) names: Vec<String>,
+    pub(crate) degree_increase: usize,
+    pub(crate) max_degree: usize,
+}
+
+impl CompressedSelectors {
+    /// Returns the fixed column that the selectors were combined into.
+    pub fn column(&self) -> Column<Fixed> {
+        self.column
+    }
+
+    /// Returns the indices of the selectors combined into the column.
+    pub fn selectors(&self) -> &[usize] {
+        &self.selectors
+    }
+
+    /// Returns whether `selector` was combined into the column.
+    pub fn contains(&self, selector: Selector) -> bool {
+        self.selectors.contains(&selector.0)
+    }
+
+    /// Returns how much the degree of the gates that use these selectors was raised.
+    pub fn degree_increase(&self) -> usize {
+        self.degree_increase
+    }
+
+    /// Returns the maximum degree of the gates that use these selectors, or zero if
+    /// the selectors are complex or not used in any gate.
+    pub fn max_degree(&self) -> usize {
+        self.max_degree
+    }
+}
+
+impl fmt::Display for CompressedSelectors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fixed column {}: {}",
+            self.column.index(),
+            self.names.join(", ")
+        )?;
+        if self.max_degree == 0 {
+            write!(f, " (not in any gate)")
+        } else {
+            write!(
+                f,
+                " (degree +{}, gates of degree up to {})",
+                self.degree_increase, self.max_degree
+            )
+        }
+    }
+}
 
 /// This describes a selector and where it is activated.
 #[derive(Debug, Clone)]
@@ -39,6 +135,8 @@ pub struct SelectorAssignment<F> {
 /// * `selectors`, a vector of `SelectorDescription`s that describe each
 ///   selector
 /// * `max_degree`, the maximum allowed degree of any gate
+/// * `max_combination_len`, the maximum number of selectors combined into a
+///   single fixed column
 /// * `allocate_fixed_columns`, a closure that constructs a new fixed column and
 ///   queries it at Rotation::cur(), returning the expression
 ///
@@ -51,6 +149,7 @@ pub struct SelectorAssignment<F> {
 pub fn process<F: Field, E>(
     mut selectors: Vec<SelectorDescription>,
     max_degree: usize,
+    max_combination_len: usize,
     mut allocate_fixed_column: E,
 ) -> (Vec<Vec<F>>, Vec<SelectorAssignment<F>>)
 where
@@ -142,7 +241,7 @@ where
 
         // Try to find other selectors that can join this one.
         'try_selectors: for (j, selector) in selectors.iter().enumerate().skip(i + 1) {
-            if d + combination.len() == max_degree {
+            if d + combination.len() == max_degree || combination.len() == max_combination_len {
                 // Short circuit; nothing can be added to this
                 // combination.
                 break 'try_selectors;
@@ -273,13 +372,68 @@ mod tests {
         }
     }
 
+    #[test]
+    fn selector_compression() {
+        use crate::plonk::ConstraintSystem;
+
+        fn compress(compression: SelectorCompression) -> ConstraintSystem<Fp> {
+            let mut meta = ConstraintSystem::<Fp>::default();
+            meta.set_selector_compression(compression);
+            let a = meta.advice_column();
+            let selectors: Vec<_> = (0..3).map(|_| meta.selector()).collect();
+            for &s in &selectors {
+                meta.create_gate("s * a", |meta| {
+                    vec![meta.query_selector(s) * meta.query_advice(a, Rotation::cur())]
+                });
+            }
+            // Raise the degree of the constraint system to 5.
+            meta.create_gate("a^5", |meta| {
+                let a = meta.query_advice(a, Rotation::cur());
+                vec![a.clone() * a.clone() * a.clone() * a.clone() * a]
+            });
+            meta.annotate_selector(selectors[0], || "first");
+
+            // The selectors are enabled on disjoint rows.
+            let activations = (0..3)
+                .map(|i| (0..4).map(|row| row == i).collect())
+                .collect();
+            meta.compress_selectors(activations).0
+        }
+
+        let full = compress(SelectorCompression::default());
+        assert_eq!(full.num_fixed_columns, 1);
+        assert_eq!(full.compressed_selectors()[0].selectors(), &[0, 1, 2]);
+        assert_eq!(full.compressed_selectors()[0].degree_increase(), 2);
+        assert_eq!(full.compressed_selectors()[0].max_degree(), 4);
+        assert_eq!(
+            full.compressed_selectors()[0].to_string(),
+            "fixed column 0: first, selector 1, selector 2 (degree +2, gates of degree up to 4)"
+        );
+
+        let capped = compress(SelectorCompression::MaxDegreeIncrease(1));
+        assert_eq!(capped.num_fixed_columns, 2);
+        assert_eq!(capped.compressed_selectors()[0].selectors(), &[0, 1]);
+        assert_eq!(capped.compressed_selectors()[0].degree_increase(), 1);
+        assert_eq!(capped.compressed_selectors()[1].selectors(), &[2]);
+        assert_eq!(capped.compressed_selectors()[1].degree_increase(), 0);
+
+        let disabled = compress(SelectorCompression::Disabled);
+        assert_eq!(disabled.num_fixed_columns, 3);
+        assert!(disabled
+            .compressed_selectors()
+            .iter()
+            .all(|compressed| compressed.selectors().len() == 1
+                && compressed.degree_increase() == 0
+                && compressed.max_degree() == 2));
+    }
+
     proptest! {
         #![proptest_config(ProptestConfig::with_cases(10000))]
         #[test]
         fn test_selector_combination((selectors, max_degree) in arb_instance(10, 10, 15)) {
             let mut query = 0;
             let (combination_assignments, selector_assignments) =
-                process::<Fp, _>(selectors.clone(), max_degree, || {
+                process::<Fp, _>(selectors.clone(), max_degree, usize::MAX, || {
                     let tmp = Expression::Fixed(FixedQuery {
                         index: query,
                         column_index: query,
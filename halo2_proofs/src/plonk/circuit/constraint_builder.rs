@@ -0,0 +1,348 @@
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+use ff::Field;
+
+use super::{Constraint, ConstraintSystem, Expression, VirtualCells};
+
+/// A named subexpression that can be queried in any number of gates.
+///
+/// The subexpression is defined by a closure that queries its cells, which is called
+/// again in each gate that uses it so that the gate records the cells it queries.
+#[derive(Clone)]
+pub struct SubExpression<F: Field> {
+    name: &'static str,
+    define: Rc<dyn Fn(&mut VirtualCells<'_, F>) -> Expression<F>>,
+}
+
+impl<F: Field> fmt::Debug for SubExpression<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubExpression")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl<F: Field> SubExpression<F> {
+    /// Defines a subexpression called `name`.
+    pub fn new(
+        name: &'static str,
+        define: impl Fn(&mut VirtualCells<'_, F>) -> Expression<F> + 'static,
+    ) -> Self {
+        SubExpression {
+            name,
+            define: Rc::new(define),
+        }
+    }
+
+    /// Returns the name of this subexpression.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// Builds the constraints of a gate declaratively. Created by
+/// [`ConstraintSystem::build_gate`].
+///
+/// The builder dereferences to the [`VirtualCells`] of the gate, so cells can be
+/// queried with it directly. Every constraint is multiplied by the conditions of the
+/// [`ConstraintBuilder::when`] and [`ConstraintBuilder::unless`] scopes it is required
+/// in.
+///
+/// ```
+/// use halo2_proofs::{
+///     plonk::{ConstraintSystem, SubExpression},
+///     poly::Rotation,
+/// };
+/// use halo2curves::pasta::Fp;
+///
+/// let mut meta = ConstraintSystem::<Fp>::default();
+/// let a = meta.advice_column();
+/// let b = meta.advice_column();
+/// let s = meta.selector();
+///
+/// let a = SubExpression::new("a", move |meta| meta.query_advice(a, Rotation::cur()));
+/// let b = SubExpression::new("b", move |meta| meta.query_advice(b, Rotation::cur()));
+///
+/// meta.build_gate("bits", |gate| {
+///     let s = gate.query_selector(s);
+///     gate.when(s, |gate| {
+///         gate.require_boolean("a is boolean", &a);
+///         gate.require_in_range("b in [0, 4)", &b, 4);
+///     });
+/// });
+/// ```
+pub struct ConstraintBuilder<'v, 'a, F: Field> {
+    cells: &'v mut VirtualCells<'a, F>,
+    conditions: Vec<Expression<F>>,
+    constraints: Vec<Constraint<F>>,
+}
+
+impl<'v, 'a, F: Field> fmt::Debug for ConstraintBuilder<'v, 'a, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConstraintBuilder")
+            .field("conditions", &self.conditions)
+            .field("constraints", &self.constraints)
+            .finish()
+    }
+}
+
+impl<'v, 'a, F: Field> Deref for ConstraintBuilder<'v, 'a, F> {
+    type Target = VirtualCells<'a, F>;
+
+    fn deref(&self) -> &Self::Target {
+        self.cells
+    }
+}
+
+impl<'v, 'a, F: Field> DerefMut for ConstraintBuilder<'v, 'a, F> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.cells
+    }
+}
+
+impl<'v, 'a, F: Field> ConstraintBuilder<'v, 'a, F> {
+    /// Queries the cells of `expression` in this gate.
+    pub fn query(&mut self, expression: &SubExpression<F>) -> Expression<F> {
+        (expression.define)(self.cells)
+    }
+
+    /// Requires the constraints added by `constraints` only where `condition` is
+    /// nonzero. This is usually a selector query.
+    pub fn when(&mut self, condition: Expression<F>, constraints: impl FnOnce(&mut Self)) {
+        self.conditions.push(condition);
+        constraints(self);
+        self.conditions.pop();
+    }
+
+    /// Requires the constraints added by `constraints` only where `condition` is zero.
+    /// `condition` must be boolean, and cannot contain a simple selector.
+    pub fn unless(&mut self, condition: Expression<F>, constraints: impl FnOnce(&mut Self)) {
+        self.when(Expression::Constant(F::one()) - condition, constraints)
+    }
+
+    /// Requires `poly` to be zero, in a constraint called `name`.
+    pub fn require_zero(&mut self, name: &'static str, poly: Expression<F>) {
+        let poly = self
+            .conditions
+            .iter()
+            .rev()
+            .fold(poly, |poly, condition| condition.clone() * poly);
+        self.constraints.push(Constraint { name, poly });
+    }
+
+    /// Requires `expression` to be zero or one, in a constraint called `name`.
+    pub fn require_boolean(&mut self, name: &'static str, expression: &SubExpression<F>) {
+        let value = self.query(expression);
+        self.require_zero(
+            name,
+            value.clone() * (Expression::Constant(F::one()) - value),
+        );
+    }
+
+    /// Requires `a` and `b` to be equal, in a constraint called `name`.
+    pub fn require_equal(
+        &mut self,
+        name: &'static str,
+        a: &SubExpression<F>,
+        b: &SubExpression<F>,
+    ) {
+        let poly = self.query(a) - self.query(b);
+        self.require_zero(name, poly);
+    }
+
+    /// Requires `expression` to be in the range `0..range`, in a constraint called
+    /// `name`. The constraint has degree `range`, so this is only suitable for small
+    /// ranges.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is zero.
+    pub fn require_in_range(
+        &mut self,
+        name: &'static str,
+        expression: &SubExpression<F>,
+        range: usize,
+    ) {
+        assert!(range > 0, "The range of {} is empty", expression.name);
+        let value = self.query(expression);
+        let mut bound = F::zero();
+        let poly = (1..range).fold(value.clone(), |poly, _| {
+            bound += F::one();
+            poly * (value.clone() - Expression::Constant(bound))
+        });
+        self.require_zero(name, poly);
+    }
+}
+
+impl<F: Field> ConstraintSystem<F> {
+    /// Creates a new gate whose constraints are added by `constraints` with a
+    /// [`ConstraintBuilder`].
+    ///
+    /// # Panics
+    ///
+    /// Like [`ConstraintSystem::create_gate`], this method will panic if no
+    /// constraints are added to the gate.
+    pub fn build_gate(
+        &mut self,
+        name: &'static str,
+        constraints: impl FnOnce(&mut ConstraintBuilder<'_, '_, F>),
+    ) {
+        self.create_gate(name, |cells| {
+            let mut builder = ConstraintBuilder {
+                cells,
+                conditions: vec![],
+                constraints: vec![],
+            };
+            constraints(&mut builder);
+            builder.constraints
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::{metadata, MockProver, VerifyFailure},
+        plonk::{Advice, Circuit, Column, Error, Selector},
+        poly::Rotation,
+    };
+    use halo2curves::pasta::Fp;
+
+    #[test]
+    fn build_gate() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let s = meta.selector();
+        let t = meta.fixed_column();
+
+        let a = SubExpression::new("a", move |meta| meta.query_advice(a, Rotation::cur()));
+        let b = SubExpression::new("b", move |meta| meta.query_advice(b, Rotation::next()));
+
+        meta.build_gate("first", |gate| {
+            let s = gate.query_selector(s);
+            gate.when(s, |gate| {
+                gate.require_boolean("a is boolean", &a);
+                let t = gate.query_fixed(t, Rotation::cur());
+                gate.unless(t, |gate| gate.require_equal("a == b", &a, &b));
+            });
+        });
+        // Subexpressions can be reused in other gates.
+        meta.build_gate("second", |gate| {
+            let s = gate.query_selector(s);
+            gate.when(s, |gate| gate.require_in_range("b in [0, 3)", &b, 3));
+        });
+
+        let names: Vec<_> = meta
+            .gates
+            .iter()
+            .flat_map(|gate| gate.constraint_names.iter().copied())
+            .collect();
+        assert_eq!(names, vec!["a is boolean", "a == b", "b in [0, 3)"]);
+
+        let degrees: Vec<_> = meta.gates[0]
+            .polynomials()
+            .iter()
+            .chain(meta.gates[1].polynomials())
+            .map(|poly| poly.degree())
+            .collect();
+        assert_eq!(degrees, vec![3, 3, 4]);
+        assert_eq!(meta.gates[1].queried_cells.len(), 1);
+    }
+
+    #[test]
+    fn named_failures() {
+        const K: u32 = 4;
+
+        #[derive(Clone)]
+        struct BitsConfig {
+            a: Column<Advice>,
+            b: Column<Advice>,
+            s: Selector,
+        }
+
+        struct BitsCircuit {
+            a: u64,
+            b: u64,
+        }
+
+        impl Circuit<Fp> for BitsCircuit {
+            type Config = BitsConfig;
+            type FloorPlanner = SimpleFloorPlanner;
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                let a = meta.advice_column();
+                let b = meta.advice_column();
+                let s = meta.selector();
+
+                let a_expr =
+                    SubExpression::new("a", move |meta| meta.query_advice(a, Rotation::cur()));
+                let b_expr =
+                    SubExpression::new("b", move |meta| meta.query_advice(b, Rotation::cur()));
+                meta.build_gate("bits", |gate| {
+                    let s = gate.query_selector(s);
+                    gate.when(s, |gate| {
+                        gate.require_boolean("a is boolean", &a_expr);
+                        gate.require_in_range("b is a 2-bit value", &b_expr, 4);
+                    });
+                });
+
+                BitsConfig { a, b, s }
+            }
+
+            fn without_witnesses(&self) -> Self {
+                Self { a: 0, b: 0 }
+            }
+
+            fn synthesize(
+                &self,
+                config: Self::Config,
+                mut layouter: impl Layouter<Fp>,
+            ) -> Result<(), Error> {
+                layouter.assign_region(
+                    || "bits",
+                    |mut region| {
+                        config.s.enable(&mut region, 0)?;
+                        region.assign_advice(
+                            || "a",
+                            config.a,
+                            0,
+                            || Value::known(Fp::from(self.a)),
+                        )?;
+                        region.assign_advice(
+                            || "b",
+                            config.b,
+                            0,
+                            || Value::known(Fp::from(self.b)),
+                        )?;
+                        Ok(())
+                    },
+                )
+            }
+        }
+
+        let prover = MockProver::run(K, &BitsCircuit { a: 1, b: 3 }, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // The failures are reported with the names given to the builder.
+        let prover = MockProver::run(K, &BitsCircuit { a: 2, b: 4 }, vec![]).unwrap();
+        let constraints: Vec<_> = prover
+            .verify()
+            .unwrap_err()
+            .into_iter()
+            .map(|failure| match failure {
+                VerifyFailure::ConstraintNotSatisfied { constraint, .. } => constraint,
+                _ => panic!("unexpected failure: {}", failure),
+            })
+            .collect();
+        let expected: Vec<metadata::Constraint> = vec![
+            ((0, "bits").into(), 0, "a is boolean").into(),
+            ((0, "bits").into(), 1, "b is a 2-bit value").into(),
+        ];
+        assert_eq!(constraints, expected);
+    }
+}
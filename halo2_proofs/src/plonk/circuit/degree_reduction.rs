@@ -0,0 +1,222 @@
+use std::cmp::max;
+
+use ff::Field;
+
+use super::{sealed, Advice, AdviceQuery, Column, ConstraintSystem, Expression};
+use crate::poly::Rotation;
+
+/// The name of the constraints that define intermediate columns.
+pub(super) const INTERMEDIATE_CONSTRAINT: &str = "intermediate column";
+
+impl<F: Field> ConstraintSystem<F> {
+    /// Splits a constraint whose degree exceeds `max_degree` into constraints of degree
+    /// at most `max_degree`. The first returned constraint replaces the original one,
+    /// and each of the others defines an intermediate advice column that it queries.
+    ///
+    /// The constraint must be the product of one or more selectors and a body. The
+    /// intermediate columns are only constrained where those selectors are enabled, so
+    /// that the prover does not need to fill them in outside of the usable rows.
+    pub(super) fn reduce_degree(
+        &mut self,
+        poly: Expression<F>,
+        max_degree: usize,
+    ) -> Vec<Expression<F>> {
+        if poly.degree() <= max_degree {
+            return vec![poly];
+        }
+
+        let mut selectors = vec![];
+        let body = split_selectors(&poly, &mut selectors);
+        let selector = selectors
+            .into_iter()
+            .reduce(|a, b| a * b)
+            .unwrap_or_else(|| {
+                panic!(
+                    "Constraint of degree {} must be multiplied by a selector to be reduced to degree {}",
+                    poly.degree(),
+                    max_degree
+                )
+            });
+        let body = body.expect("a constraint of degree at least two has a body");
+        let body_degree = max_degree.saturating_sub(selector.degree());
+        assert!(
+            body_degree >= 2,
+            "Cannot reduce a constraint with a selector of degree {} to degree {}",
+            selector.degree(),
+            max_degree
+        );
+
+        let mut used = vec![];
+        let body = self.reduce_body(&body, body_degree, &mut used);
+
+        std::iter::once(selector.clone() * body)
+            .chain(used.into_iter().map(|(query, expression)| {
+                selector.clone() * (Expression::Advice(query) - expression)
+            }))
+            .collect()
+    }
+
+    /// Rewrites `expression` to have degree at most `max_degree`, replacing
+    /// subexpressions with queries of intermediate columns, which are recorded in
+    /// `used` along with the subexpressions they are equal to.
+    fn reduce_body(
+        &mut self,
+        expression: &Expression<F>,
+        max_degree: usize,
+        used: &mut Vec<(AdviceQuery, Expression<F>)>,
+    ) -> Expression<F> {
+        match expression {
+            Expression::Negated(a) => -self.reduce_body(a, max_degree, used),
+            Expression::Scaled(a, f) => self.reduce_body(a, max_degree, used) * *f,
+            Expression::Sum(a, b) => {
+                self.reduce_body(a, max_degree, used) + self.reduce_body(b, max_degree, used)
+            }
+            Expression::Product(a, b) => {
+                let mut a = self.reduce_body(a, max_degree, used);
+                let mut b = self.reduce_body(b, max_degree, used);
+                // Each factor has degree at most `max_degree`, so this replaces at most
+                // two of them.
+                while a.degree() + b.degree() > max_degree {
+                    if a.degree() >= b.degree() {
+                        a = self.intermediate(a, used);
+                    } else {
+                        b = self.intermediate(b, used);
+                    }
+                }
+                a * b
+            }
+            _ => expression.clone(),
+        }
+    }
+
+    /// Returns a query of an intermediate column equal to `expression`, allocating the
+    /// column if no existing intermediate column is equal to it.
+    fn intermediate(
+        &mut self,
+        expression: Expression<F>,
+        used: &mut Vec<(AdviceQuery, Expression<F>)>,
+    ) -> Expression<F> {
+        let identifier = expression.identifier();
+        let column = match self
+            .intermediate_columns
+            .iter()
+            .find(|(_, e)| e.identifier() == identifier)
+        {
+            Some((column, _)) => *column,
+            None => {
+                // The column can be filled in as soon as every advice column and
+                // challenge that the expression uses is available.
+                let phase = expression.evaluate(
+                    &|_| 0,
+                    &|_| 0,
+                    &|_| 0,
+                    &|query| query.phase.0,
+                    &|_| 0,
+                    &|challenge| challenge.phase() + 1,
+                    &|a| a,
+                    &max,
+                    &max,
+                    &|a, _| a,
+                );
+                // Intermediate columns are never shared with other sub-circuits.
+                let column = self.allocate_advice_column(sealed::Phase(phase));
+                self.intermediate_columns.push((column, expression.clone()));
+                column
+            }
+        };
+
+        let query = self.intermediate_query(column);
+        if !used
+            .iter()
+            .any(|(q, _)| q.column_index == query.column_index)
+        {
+            used.push((query, expression));
+        }
+        Expression::Advice(query)
+    }
+
+    fn intermediate_query(&mut self, column: Column<Advice>) -> AdviceQuery {
+        AdviceQuery {
+            index: self.query_advice_index(column, Rotation::cur()),
+            column_index: column.index,
+            rotation: Rotation::cur(),
+            phase: column.column_type().phase,
+        }
+    }
+}
+
+/// Collects the selectors that `expression` is a product of into `selectors`, and
+/// returns the product of its other factors, if any.
+fn split_selectors<F: Field>(
+    expression: &Expression<F>,
+    selectors: &mut Vec<Expression<F>>,
+) -> Option<Expression<F>> {
+    match expression {
+        Expression::Selector(_) => {
+            selectors.push(expression.clone());
+            None
+        }
+        Expression::Product(a, b) => {
+            match (split_selectors(a, selectors), split_selectors(b, selectors)) {
+                (Some(a), Some(b)) => Some(a * b),
+                (a, b) => a.or(b),
+            }
+        }
+        _ => Some(expression.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plonk::{FirstPhase, SecondPhase};
+    use halo2curves::pasta::Fp;
+
+    #[test]
+    fn reduce_gate_degree() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let a = meta.advice_column();
+        let b = meta.advice_column_in(SecondPhase);
+        let s = meta.selector();
+        let theta = meta.challenge_usable_after(FirstPhase);
+        meta.set_max_gate_degree(3);
+
+        meta.create_gate("degree 6", |meta| {
+            let s = meta.query_selector(s);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::next());
+            let theta = meta.query_challenge(theta);
+            vec![s * a.clone() * a.clone() * a.clone() * (b + theta) * a]
+        });
+
+        let gate = &meta.gates[0];
+        assert!(gate.polynomials().iter().all(|poly| poly.degree() <= 3));
+        assert_eq!(
+            gate.polynomials().len(),
+            1 + meta.intermediate_columns.len()
+        );
+        assert_eq!(meta.degree(), 3);
+
+        // a * a, and then (a * a) * a, are moved into first-phase columns, while their
+        // product with `b + theta` is moved into a second-phase column.
+        let phases: Vec<_> = meta
+            .intermediate_columns
+            .iter()
+            .map(|(column, _)| column.column_type().phase.0)
+            .collect();
+        assert_eq!(phases, vec![0, 0, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be multiplied by a selector")]
+    fn reduce_gate_degree_without_selector() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let a = meta.advice_column();
+        meta.set_max_gate_degree(3);
+
+        meta.create_gate("ungated", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            vec![a.clone() * a.clone() * a.clone() * a]
+        });
+    }
+}
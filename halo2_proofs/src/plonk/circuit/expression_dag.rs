@@ -0,0 +1,661 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use ff::{Field, PrimeField};
+
+use super::{AdviceQuery, Challenge, Expression, FixedQuery, Gate, InstanceQuery, Selector};
+use crate::arithmetic::FieldExt;
+
+/// A node of an [`ExpressionDag`]. Nodes refer to other nodes by their index, which is
+/// always smaller than their own.
+#[derive(Clone, Debug)]
+pub(crate) enum DagNode<F> {
+    /// A query of a fixed column.
+    Fixed(FixedQuery),
+    /// A query of an advice column.
+    Advice(AdviceQuery),
+    /// A query of an instance column.
+    Instance(InstanceQuery),
+    /// A challenge.
+    Challenge(Challenge),
+    /// A virtual selector. These only exist before selectors are compressed.
+    Selector(Selector),
+    /// `constant + Σ coefficient * node`, where the first coefficient is one and the
+    /// terms are sorted by node.
+    Sum { constant: F, terms: Vec<(F, usize)> },
+    /// The product of at least two nodes, which are sorted and may repeat.
+    Product(Vec<usize>),
+}
+
+/// A constraint of a gate in an [`ExpressionDag`]: `coefficient * node`, or just
+/// `coefficient` if there is no node.
+#[derive(Clone, Debug)]
+pub(crate) struct DagConstraint<F> {
+    pub(crate) coefficient: F,
+    pub(crate) node: Option<usize>,
+}
+
+/// A gate in an [`ExpressionDag`].
+#[derive(Clone, Debug)]
+pub(crate) struct DagGate<F> {
+    pub(crate) name: &'static str,
+    /// A factor shared by every constraint of this gate, usually its selector, which
+    /// has been divided out of the constraints.
+    pub(crate) factor: Option<usize>,
+    pub(crate) constraints: Vec<DagConstraint<F>>,
+    /// The number of nodes in the expression trees of the original constraints.
+    original_nodes: usize,
+}
+
+/// The gates of a constraint system, simplified into a directed acyclic graph of
+/// expressions that is shared between all of the gates.
+///
+/// When building the graph:
+/// - constants are folded, and `Scaled` and `Negated` expressions are folded into the
+///   coefficients of sums;
+/// - identical terms of a sum are combined, which cancels those that sum to zero;
+/// - identical subexpressions, up to a constant factor, are shared;
+/// - a factor shared by every constraint of a gate, such as its selector, is factored
+///   out so that it is only multiplied in once.
+///
+/// Each constraint of the graph evaluates to the same value as the original
+/// constraint, so proofs do not depend on whether the graph is used. The prover uses it
+/// to evaluate the quotient polynomial and the verifier to evaluate the gates at `x`.
+#[derive(Clone, Debug)]
+pub struct ExpressionDag<F> {
+    pub(crate) nodes: Vec<DagNode<F>>,
+    pub(crate) gates: Vec<DagGate<F>>,
+}
+
+/// The savings from simplifying a single gate into an [`ExpressionDag`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GateSavings {
+    /// The name of the gate.
+    pub name: &'static str,
+    /// The number of nodes in the expression trees of the gate's constraints.
+    pub original_nodes: usize,
+    /// The number of distinct nodes of the graph that the gate's constraints use,
+    /// including those shared with other gates.
+    pub dag_nodes: usize,
+    /// Whether a factor shared by every constraint of the gate was factored out.
+    pub factored: bool,
+}
+
+impl fmt::Display for GateSavings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} -> {} nodes",
+            self.name, self.original_nodes, self.dag_nodes
+        )?;
+        if self.factored {
+            write!(f, " (shared factor)")?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> ExpressionDag<F> {
+    /// Builds the graph of the given gates.
+    pub(crate) fn new(gates: &[Gate<F>]) -> Self {
+        let mut builder = Builder {
+            nodes: vec![],
+            keys: HashMap::new(),
+        };
+
+        let gates = gates
+            .iter()
+            .map(|gate| {
+                let mut constraints: Vec<_> = gate
+                    .polynomials()
+                    .iter()
+                    .map(|poly| {
+                        let linear = builder.linear(poly);
+                        match linear.as_constant() {
+                            Some(coefficient) => DagConstraint {
+                                coefficient,
+                                node: None,
+                            },
+                            None => {
+                                let (coefficient, node) = builder.materialize(linear);
+                                DagConstraint {
+                                    coefficient,
+                                    node: Some(node),
+                                }
+                            }
+                        }
+                    })
+                    .collect();
+                let factor = builder.factor_out(&mut constraints);
+
+                DagGate {
+                    name: gate.name(),
+                    factor,
+                    constraints,
+                    original_nodes: gate.polynomials().iter().map(tree_size).sum(),
+                }
+            })
+            .collect();
+
+        ExpressionDag {
+            nodes: builder.nodes,
+            gates,
+        }
+        .pruned()
+    }
+}
+
+impl<F: Field> ExpressionDag<F> {
+    /// Returns the number of nodes in this graph.
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the savings from simplifying each gate, in the order in which the gates
+    /// were created.
+    pub fn savings(&self) -> Vec<GateSavings> {
+        self.gates
+            .iter()
+            .map(|gate| {
+                let roots = gate
+                    .constraints
+                    .iter()
+                    .filter_map(|constraint| constraint.node)
+                    .chain(gate.factor);
+                GateSavings {
+                    name: gate.name,
+                    original_nodes: gate.original_nodes,
+                    dag_nodes: self.reachable(roots).iter().filter(|&&r| r).count(),
+                    factored: gate.factor.is_some(),
+                }
+            })
+            .collect()
+    }
+
+    /// Evaluates every constraint of every gate, in order, given the values of the
+    /// queries and challenges.
+    pub(crate) fn evaluate(
+        &self,
+        fixed: &impl Fn(&FixedQuery) -> F,
+        advice: &impl Fn(&AdviceQuery) -> F,
+        instance: &impl Fn(&InstanceQuery) -> F,
+        challenge: &impl Fn(&Challenge) -> F,
+    ) -> Vec<F> {
+        let mut values: Vec<F> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let value = match node {
+                DagNode::Fixed(query) => fixed(query),
+                DagNode::Advice(query) => advice(query),
+                DagNode::Instance(query) => instance(query),
+                DagNode::Challenge(c) => challenge(c),
+                DagNode::Selector(_) => {
+                    panic!("virtual selectors are removed during optimization")
+                }
+                DagNode::Sum { constant, terms } => {
+                    terms.iter().fold(*constant, |acc, (coefficient, node)| {
+                        acc + *coefficient * values[*node]
+                    })
+                }
+                DagNode::Product(factors) => factors
+                    .iter()
+                    .fold(F::one(), |acc, factor| acc * values[*factor]),
+            };
+            values.push(value);
+        }
+
+        self.gates
+            .iter()
+            .flat_map(|gate| {
+                let values = &values;
+                let factor = gate.factor.map_or(F::one(), |node| values[node]);
+                gate.constraints.iter().map(move |constraint| {
+                    let value = constraint.node.map_or(F::one(), |node| values[node]);
+                    factor * constraint.coefficient * value
+                })
+            })
+            .collect()
+    }
+
+    /// Marks the nodes that are reachable from the given roots.
+    fn reachable(&self, roots: impl Iterator<Item = usize>) -> Vec<bool> {
+        let mut reachable = vec![false; self.nodes.len()];
+        for root in roots {
+            reachable[root] = true;
+        }
+        // Children always precede their parents.
+        for index in (0..self.nodes.len()).rev() {
+            if !reachable[index] {
+                continue;
+            }
+            match &self.nodes[index] {
+                DagNode::Sum { terms, .. } => {
+                    for (_, node) in terms {
+                        reachable[*node] = true;
+                    }
+                }
+                DagNode::Product(factors) => {
+                    for factor in factors {
+                        reachable[*factor] = true;
+                    }
+                }
+                _ => (),
+            }
+        }
+        reachable
+    }
+
+    /// Removes the nodes that no gate uses, such as the products that shared factors
+    /// were divided out of.
+    fn pruned(self) -> Self {
+        let roots = self.gates.iter().flat_map(|gate| {
+            gate.constraints
+                .iter()
+                .filter_map(|constraint| constraint.node)
+                .chain(gate.factor)
+        });
+        let reachable = self.reachable(roots);
+
+        // The renumbering preserves the order of nodes, so sorted terms stay sorted.
+        let mut renumbered = vec![usize::MAX; self.nodes.len()];
+        let mut nodes = vec![];
+        for (index, node) in self.nodes.into_iter().enumerate() {
+            if !reachable[index] {
+                continue;
+            }
+            renumbered[index] = nodes.len();
+            nodes.push(match node {
+                DagNode::Sum { constant, terms } => DagNode::Sum {
+                    constant,
+                    terms: terms
+                        .into_iter()
+                        .map(|(coefficient, node)| (coefficient, renumbered[node]))
+                        .collect(),
+                },
+                DagNode::Product(factors) => {
+                    DagNode::Product(factors.into_iter().map(|f| renumbered[f]).collect())
+                }
+                node => node,
+            });
+        }
+
+        let gates = self
+            .gates
+            .into_iter()
+            .map(|gate| DagGate {
+                factor: gate.factor.map(|node| renumbered[node]),
+                constraints: gate
+                    .constraints
+                    .into_iter()
+                    .map(|constraint| DagConstraint {
+                        node: constraint.node.map(|node| renumbered[node]),
+                        ..constraint
+                    })
+                    .collect(),
+                ..gate
+            })
+            .collect();
+
+        ExpressionDag { nodes, gates }
+    }
+}
+
+/// Returns the number of nodes in an expression tree.
+fn tree_size<F: Field>(expression: &Expression<F>) -> usize {
+    expression.evaluate(
+        &|_| 1,
+        &|_| 1,
+        &|_| 1,
+        &|_| 1,
+        &|_| 1,
+        &|_| 1,
+        &|a| a + 1,
+        &|a, b| a + b + 1,
+        &|a, b| a + b + 1,
+        &|a, _| a + 1,
+    )
+}
+
+/// A linear combination of nodes, `constant + Σ coefficient * node`, whose terms are
+/// sorted by node and have non-zero coefficients.
+#[derive(Clone, Debug)]
+struct Linear<F> {
+    constant: F,
+    terms: Vec<(F, usize)>,
+}
+
+impl<F: Field> Linear<F> {
+    fn constant(constant: F) -> Self {
+        Linear {
+            constant,
+            terms: vec![],
+        }
+    }
+
+    fn node(node: usize) -> Self {
+        Linear {
+            constant: F::zero(),
+            terms: vec![(F::one(), node)],
+        }
+    }
+
+    /// Returns the value of this combination, if it has no terms.
+    fn as_constant(&self) -> Option<F> {
+        if self.terms.is_empty() {
+            Some(self.constant)
+        } else {
+            None
+        }
+    }
+
+    fn scale(mut self, factor: F) -> Self {
+        if factor == F::zero() {
+            return Linear::constant(F::zero());
+        }
+        self.constant *= factor;
+        for (coefficient, _) in self.terms.iter_mut() {
+            *coefficient *= factor;
+        }
+        self
+    }
+
+    fn add(self, other: Self) -> Self {
+        let mut terms = Vec::with_capacity(self.terms.len() + other.terms.len());
+        let mut a = self.terms.into_iter().peekable();
+        let mut b = other.terms.into_iter().peekable();
+        loop {
+            let term = match (a.peek(), b.peek()) {
+                (Some(&(x, i)), Some(&(y, j))) if i == j => {
+                    a.next();
+                    b.next();
+                    (x + y, i)
+                }
+                (Some(&(_, i)), Some(&(_, j))) if j < i => b.next().unwrap(),
+                (Some(_), _) => a.next().unwrap(),
+                (None, Some(_)) => b.next().unwrap(),
+                (None, None) => break,
+            };
+            // Identical terms may cancel.
+            if term.0 != F::zero() {
+                terms.push(term);
+            }
+        }
+        Linear {
+            constant: self.constant + other.constant,
+            terms,
+        }
+    }
+}
+
+/// A key identifying a node, so that identical nodes are only created once.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum NodeKey {
+    Fixed(usize, i32),
+    Advice(usize, i32),
+    Instance(usize, i32),
+    Challenge(usize),
+    Selector(usize),
+    Sum(Vec<u8>, Vec<(Vec<u8>, usize)>),
+    Product(Vec<usize>),
+}
+
+fn repr<F: PrimeField>(value: &F) -> Vec<u8> {
+    value.to_repr().as_ref().to_vec()
+}
+
+struct Builder<F> {
+    nodes: Vec<DagNode<F>>,
+    keys: HashMap<NodeKey, usize>,
+}
+
+impl<F: FieldExt> Builder<F> {
+    /// Returns the index of the node with the given key, creating it if necessary.
+    fn node(&mut self, key: NodeKey, node: impl FnOnce() -> DagNode<F>) -> usize {
+        let nodes = &mut self.nodes;
+        *self.keys.entry(key).or_insert_with(|| {
+            nodes.push(node());
+            nodes.len() - 1
+        })
+    }
+
+    fn linear(&mut self, expression: &Expression<F>) -> Linear<F> {
+        match expression {
+            Expression::Constant(constant) => Linear::constant(*constant),
+            Expression::Selector(selector) => {
+                Linear::node(self.node(NodeKey::Selector(selector.0), || {
+                    DagNode::Selector(*selector)
+                }))
+            }
+            Expression::Fixed(query) => Linear::node(
+                self.node(NodeKey::Fixed(query.column_index, query.rotation.0), || {
+                    DagNode::Fixed(*query)
+                }),
+            ),
+            Expression::Advice(query) => Linear::node(self.node(
+                NodeKey::Advice(query.column_index, query.rotation.0),
+                || DagNode::Advice(*query),
+            )),
+            Expression::Instance(query) => Linear::node(self.node(
+                NodeKey::Instance(query.column_index, query.rotation.0),
+                || DagNode::Instance(*query),
+            )),
+            Expression::Challenge(challenge) => {
+                Linear::node(self.node(NodeKey::Challenge(challenge.index()), || {
+                    DagNode::Challenge(*challenge)
+                }))
+            }
+            Expression::Negated(a) => self.linear(a).scale(-F::one()),
+            Expression::Scaled(a, factor) => self.linear(a).scale(*factor),
+            Expression::Sum(a, b) => {
+                let a = self.linear(a);
+                let b = self.linear(b);
+                a.add(b)
+            }
+            Expression::Product(a, b) => {
+                let a = self.linear(a);
+                let b = self.linear(b);
+                self.product(a, b)
+            }
+        }
+    }
+
+    fn product(&mut self, a: Linear<F>, b: Linear<F>) -> Linear<F> {
+        if let Some(constant) = a.as_constant() {
+            return b.scale(constant);
+        }
+        if let Some(constant) = b.as_constant() {
+            return a.scale(constant);
+        }
+
+        let (a_coefficient, a) = self.materialize(a);
+        let (b_coefficient, b) = self.materialize(b);
+        let mut factors = self.factors(a);
+        factors.extend(self.factors(b));
+        factors.sort_unstable();
+
+        Linear {
+            constant: F::zero(),
+            terms: vec![(a_coefficient * b_coefficient, self.product_node(factors))],
+        }
+    }
+
+    /// Returns a node equal to the given combination, which must have at least one term,
+    /// divided by the returned coefficient.
+    fn materialize(&mut self, linear: Linear<F>) -> (F, usize) {
+        if linear.constant == F::zero() && linear.terms.len() == 1 {
+            return linear.terms[0];
+        }
+
+        // Divide out the first coefficient, so that multiples of a sum share its node.
+        let leading = linear.terms[0].0;
+        let sum = linear.scale(leading.invert().unwrap());
+        let key = NodeKey::Sum(
+            repr(&sum.constant),
+            sum.terms
+                .iter()
+                .map(|(coefficient, node)| (repr(coefficient), *node))
+                .collect(),
+        );
+        let node = self.node(key, || DagNode::Sum {
+            constant: sum.constant,
+            terms: sum.terms,
+        });
+        (leading, node)
+    }
+
+    /// Returns the factors of a node: those of a product, or else the node itself.
+    fn factors(&self, node: usize) -> Vec<usize> {
+        match &self.nodes[node] {
+            DagNode::Product(factors) => factors.clone(),
+            _ => vec![node],
+        }
+    }
+
+    /// Returns the product of the given sorted factors, of which there must be at least
+    /// one.
+    fn product_node(&mut self, factors: Vec<usize>) -> usize {
+        if factors.len() == 1 {
+            return factors[0];
+        }
+        self.node(NodeKey::Product(factors.clone()), || {
+            DagNode::Product(factors)
+        })
+    }
+
+    /// Divides the factors shared by every one of a gate's constraints out of them,
+    /// returning their product.
+    fn factor_out(&mut self, constraints: &mut [DagConstraint<F>]) -> Option<usize> {
+        if constraints.len() < 2 {
+            return None;
+        }
+        let factors = constraints
+            .iter()
+            .map(|constraint| constraint.node.map(|node| self.factors(node)))
+            .collect::<Option<Vec<_>>>()?;
+
+        // The intersection of the sorted multisets of factors.
+        let mut shared = factors[0].clone();
+        for factors in &factors[1..] {
+            let mut remaining = factors.iter().peekable();
+            shared.retain(|factor| {
+                while remaining.next_if(|&&f| f < *factor).is_some() {}
+                remaining.next_if(|&&f| f == *factor).is_some()
+            });
+        }
+        if shared.is_empty() {
+            return None;
+        }
+
+        for (constraint, mut factors) in constraints.iter_mut().zip(factors) {
+            for factor in &shared {
+                let position = factors.iter().position(|f| f == factor).unwrap();
+                factors.remove(position);
+            }
+            constraint.node = if factors.is_empty() {
+                None
+            } else {
+                Some(self.product_node(factors))
+            };
+        }
+        Some(self.product_node(shared))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plonk::{ConstraintSystem, FirstPhase};
+    use crate::poly::Rotation;
+    use halo2curves::pasta::Fp;
+    use rand_core::OsRng;
+
+    #[test]
+    fn simplify_gates() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let q = meta.fixed_column();
+        let f = meta.fixed_column();
+        let theta = meta.challenge_usable_after(FirstPhase);
+
+        meta.create_gate("shared factor", |meta| {
+            let a_cur = meta.query_advice(a, Rotation::cur());
+            let a_next = meta.query_advice(a, Rotation::next());
+            let b = meta.query_advice(b, Rotation::cur());
+            let q = meta.query_fixed(q, Rotation::cur());
+            let f = meta.query_fixed(f, Rotation::cur());
+            let theta = meta.query_challenge(theta);
+
+            vec![
+                // The `b` terms cancel.
+                q.clone() * (a_cur.clone() + b.clone() - a_next.clone() - b.clone()),
+                // A multiple of the previous constraint.
+                q.clone() * (a_cur.clone() - a_next) * Expression::Constant(Fp::from(3)),
+                q.clone() * b.clone() * (Expression::Constant(Fp::one()) - b),
+                q * (-(-f) * theta + a_cur * Fp::from(5)),
+            ]
+        });
+        meta.create_gate("constant", |_| vec![Expression::Constant(Fp::one())]);
+
+        let dag = ExpressionDag::new(&meta.gates);
+        assert_eq!(
+            dag.savings(),
+            vec![
+                GateSavings {
+                    name: "shared factor",
+                    original_nodes: 37,
+                    // q, a, a', b, a - a', b - 1, f, theta, b * (b - 1), f * theta and
+                    // a + f * theta / 5.
+                    dag_nodes: 11,
+                    factored: true,
+                },
+                GateSavings {
+                    name: "constant",
+                    original_nodes: 1,
+                    dag_nodes: 0,
+                    factored: false,
+                },
+            ]
+        );
+        assert_eq!(dag.num_nodes(), 11);
+        assert_eq!(
+            dag.savings()[0].to_string(),
+            "shared factor: 37 -> 11 nodes (shared factor)"
+        );
+
+        // The graph evaluates to the same values as the original constraints.
+        for _ in 0..10 {
+            // q, f, a, a', b and theta.
+            let values: Vec<Fp> = (0..6).map(|_| Fp::random(OsRng)).collect();
+            let fixed = |query: &FixedQuery| values[query.column_index];
+            let advice = |query: &AdviceQuery| {
+                values[2 + query.rotation.0 as usize + 2 * query.column_index]
+            };
+            let challenge = |_: &Challenge| values[5];
+
+            let expected: Vec<Fp> = meta
+                .gates
+                .iter()
+                .flat_map(|gate| gate.polynomials().iter())
+                .map(|poly| {
+                    poly.evaluate(
+                        &|scalar| scalar,
+                        &|_| unreachable!(),
+                        &|query| fixed(&query),
+                        &|query| advice(&query),
+                        &|_| unreachable!(),
+                        &|c| challenge(&c),
+                        &|a| -a,
+                        &|a, b| a + b,
+                        &|a, b| a * b,
+                        &|a, scalar| a * scalar,
+                    )
+                })
+                .collect();
+
+            assert_eq!(
+                dag.evaluate(&fixed, &advice, &|_| unreachable!(), &challenge),
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,494 @@
+use std::fmt::{self, Debug};
+use std::ops::{Add, Mul, Neg, Sub};
+use std::sync::Arc;
+
+use ff::Field;
+
+use super::circuit::{sealed, Challenge, Expression};
+use super::Error;
+use crate::poly::Rotation;
+
+pub(crate) mod prover;
+pub(crate) mod verifier;
+
+/// An argument that is proven alongside the gates, lookups and permutation of a
+/// circuit, such as a multiset check or a sum-check.
+///
+/// An argument has its own columns, which the prover computes from the values of the
+/// argument's input expressions on every row. The constraints of the argument are added
+/// to the quotient polynomial, must vanish on every row, and are checked by the verifier
+/// from the evaluations of the columns they query. Each column of the argument is opened
+/// at every rotation at which the constraints query it.
+///
+/// Arguments are added to a circuit with [`ConstraintSystem::custom_argument`], which
+/// commits their columns after the lookup and permutation products, so that they can
+/// depend on every challenge of the circuit as well as on `theta`, `beta` and `gamma`.
+/// [`ConstraintSystem::custom_argument_in`] instead commits them along with the advice
+/// columns of a phase, so that the challenges usable after that phase can depend on
+/// them. Both read the inputs and constraints of the argument once.
+///
+/// [`MockProver`](crate::dev::MockProver) checks the constraints of custom arguments on
+/// every row up to [`ArgumentExpression::Last`], with the columns that
+/// [`CustomArgument::compute_columns`] returns.
+///
+/// [`ConstraintSystem::custom_argument`]: crate::plonk::ConstraintSystem::custom_argument
+/// [`ConstraintSystem::custom_argument_in`]: crate::plonk::ConstraintSystem::custom_argument_in
+pub trait CustomArgument<F: Field>: Debug + Send + Sync {
+    /// Returns the number of columns that the prover commits to for this argument.
+    fn num_columns(&self) -> usize;
+
+    /// Returns the expressions, in the columns and challenges of the circuit, whose
+    /// values are passed to [`CustomArgument::compute_columns`].
+    fn inputs(&self) -> Vec<Expression<F>>;
+
+    /// Returns the constraints that the columns of this argument satisfy on every row.
+    fn constraints(&self) -> Vec<ArgumentExpression<F>>;
+
+    /// Computes the values of the columns of this argument, from the first row up to
+    /// [`ArgumentInputs::rows`]. Rows that are not given a value are zero.
+    fn compute_columns(&self, inputs: &ArgumentInputs<'_, F>) -> Result<Vec<Vec<F>>, Error>;
+}
+
+/// The values that the prover computes the columns of a [`CustomArgument`] from.
+#[derive(Debug)]
+pub struct ArgumentInputs<'a, F: Field> {
+    pub(crate) inputs: &'a [Vec<F>],
+    pub(crate) challenges: &'a [F],
+    /// The phase that the argument's columns are committed in, if any.
+    pub(crate) phase: Option<sealed::Phase>,
+    pub(crate) theta: F,
+    pub(crate) beta: F,
+    pub(crate) gamma: F,
+    pub(crate) rows: usize,
+}
+
+impl<'a, F: Field> ArgumentInputs<'a, F> {
+    /// Returns the values of the `index`th input expression of the argument.
+    pub fn input(&self, index: usize) -> &[F] {
+        &self.inputs[index]
+    }
+
+    /// Returns the value of `challenge`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the columns of the argument are committed in a phase that `challenge`
+    /// is not usable after.
+    pub fn challenge(&self, challenge: Challenge) -> F {
+        if let Some(phase) = self.phase {
+            assert!(
+                challenge.phase() < phase.0,
+                "{:?} is not usable by an argument committed in {:?}",
+                challenge,
+                phase
+            );
+        }
+        self.challenges[challenge.index()]
+    }
+
+    /// Returns the `theta` challenge.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the columns of the argument are committed in an advice phase, before
+    /// `theta` is squeezed.
+    pub fn theta(&self) -> F {
+        self.assert_after_products("theta");
+        self.theta
+    }
+
+    /// Returns the `beta` challenge.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the columns of the argument are committed in an advice phase, before
+    /// `beta` is squeezed.
+    pub fn beta(&self) -> F {
+        self.assert_after_products("beta");
+        self.beta
+    }
+
+    /// Returns the `gamma` challenge.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the columns of the argument are committed in an advice phase, before
+    /// `gamma` is squeezed.
+    pub fn gamma(&self) -> F {
+        self.assert_after_products("gamma");
+        self.gamma
+    }
+
+    fn assert_after_products(&self, challenge: &str) {
+        if let Some(phase) = self.phase {
+            panic!(
+                "{} is not usable by an argument committed in {:?}",
+                challenge, phase
+            );
+        }
+    }
+
+    /// Returns the number of rows that the columns of the argument can set. The last of
+    /// them is the row where [`ArgumentExpression::Last`] is one, and the rows after
+    /// it are filled with random blinding factors.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+}
+
+/// A constraint of a [`CustomArgument`].
+#[derive(Clone, Debug)]
+pub enum ArgumentExpression<F: Field> {
+    /// This is a constant
+    Constant(F),
+    /// This is an expression in the columns and challenges of the circuit
+    Circuit(Expression<F>),
+    /// This is the column of the argument with the given index, at a rotation
+    Column(usize, Rotation),
+    /// This is one on the first row, and zero elsewhere
+    First,
+    /// This is one on the last row that the argument's columns can set, and zero
+    /// elsewhere
+    Last,
+    /// This is one on the rows before [`ArgumentExpression::Last`], and zero elsewhere
+    Active,
+    /// This is the `theta` challenge
+    Theta,
+    /// This is the `beta` challenge
+    Beta,
+    /// This is the `gamma` challenge
+    Gamma,
+    /// This is a negated expression
+    Negated(Box<ArgumentExpression<F>>),
+    /// This is the sum of two expressions
+    Sum(Box<ArgumentExpression<F>>, Box<ArgumentExpression<F>>),
+    /// This is the product of two expressions
+    Product(Box<ArgumentExpression<F>>, Box<ArgumentExpression<F>>),
+    /// This is a scaled expression
+    Scaled(Box<ArgumentExpression<F>>, F),
+}
+
+impl<F: Field> ArgumentExpression<F> {
+    /// Evaluates this expression, handling every variant that has no subexpressions
+    /// with `leaf`.
+    pub(crate) fn evaluate<T>(
+        &self,
+        leaf: &impl Fn(&Self) -> T,
+        negated: &impl Fn(T) -> T,
+        sum: &impl Fn(T, T) -> T,
+        product: &impl Fn(T, T) -> T,
+        scaled: &impl Fn(T, F) -> T,
+    ) -> T {
+        match self {
+            ArgumentExpression::Negated(a) => {
+                let a = a.evaluate(leaf, negated, sum, product, scaled);
+                negated(a)
+            }
+            ArgumentExpression::Sum(a, b) => {
+                let a = a.evaluate(leaf, negated, sum, product, scaled);
+                let b = b.evaluate(leaf, negated, sum, product, scaled);
+                sum(a, b)
+            }
+            ArgumentExpression::Product(a, b) => {
+                let a = a.evaluate(leaf, negated, sum, product, scaled);
+                let b = b.evaluate(leaf, negated, sum, product, scaled);
+                product(a, b)
+            }
+            ArgumentExpression::Scaled(a, f) => {
+                let a = a.evaluate(leaf, negated, sum, product, scaled);
+                scaled(a, *f)
+            }
+            _ => leaf(self),
+        }
+    }
+
+    /// Compute the degree of this polynomial
+    pub fn degree(&self) -> usize {
+        self.evaluate(
+            &|leaf| match leaf {
+                ArgumentExpression::Circuit(expression) => expression.degree(),
+                ArgumentExpression::Column(..)
+                | ArgumentExpression::First
+                | ArgumentExpression::Last
+                | ArgumentExpression::Active => 1,
+                _ => 0,
+            },
+            &|a| a,
+            &|a, b| std::cmp::max(a, b),
+            &|a, b| a + b,
+            &|a, _| a,
+        )
+    }
+
+    /// Calls `f` on every expression in the columns of the circuit that this
+    /// expression contains.
+    pub(crate) fn for_each_circuit_expression_mut(
+        &mut self,
+        f: &mut impl FnMut(&mut Expression<F>),
+    ) {
+        match self {
+            ArgumentExpression::Circuit(expression) => f(expression),
+            ArgumentExpression::Negated(a) | ArgumentExpression::Scaled(a, _) => {
+                a.for_each_circuit_expression_mut(f)
+            }
+            ArgumentExpression::Sum(a, b) | ArgumentExpression::Product(a, b) => {
+                a.for_each_circuit_expression_mut(f);
+                b.for_each_circuit_expression_mut(f);
+            }
+            _ => {}
+        }
+    }
+
+    /// Adds the columns of the argument that this expression queries, and their
+    /// rotations, to `queries`.
+    fn collect_queries(&self, queries: &mut Vec<(usize, Rotation)>) {
+        match self {
+            ArgumentExpression::Column(column, rotation) => {
+                if !queries.contains(&(*column, *rotation)) {
+                    queries.push((*column, *rotation));
+                }
+            }
+            ArgumentExpression::Negated(a) | ArgumentExpression::Scaled(a, _) => {
+                a.collect_queries(queries)
+            }
+            ArgumentExpression::Sum(a, b) | ArgumentExpression::Product(a, b) => {
+                a.collect_queries(queries);
+                b.collect_queries(queries);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<F: Field> From<Expression<F>> for ArgumentExpression<F> {
+    fn from(expression: Expression<F>) -> Self {
+        ArgumentExpression::Circuit(expression)
+    }
+}
+
+impl<F: Field> Neg for ArgumentExpression<F> {
+    type Output = ArgumentExpression<F>;
+    fn neg(self) -> Self::Output {
+        ArgumentExpression::Negated(Box::new(self))
+    }
+}
+
+impl<F: Field> Add for ArgumentExpression<F> {
+    type Output = ArgumentExpression<F>;
+    fn add(self, rhs: ArgumentExpression<F>) -> ArgumentExpression<F> {
+        ArgumentExpression::Sum(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl<F: Field> Sub for ArgumentExpression<F> {
+    type Output = ArgumentExpression<F>;
+    fn sub(self, rhs: ArgumentExpression<F>) -> ArgumentExpression<F> {
+        ArgumentExpression::Sum(Box::new(self), Box::new(-rhs))
+    }
+}
+
+impl<F: Field> Mul for ArgumentExpression<F> {
+    type Output = ArgumentExpression<F>;
+    fn mul(self, rhs: ArgumentExpression<F>) -> ArgumentExpression<F> {
+        ArgumentExpression::Product(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl<F: Field> Mul<F> for ArgumentExpression<F> {
+    type Output = ArgumentExpression<F>;
+    fn mul(self, rhs: F) -> ArgumentExpression<F> {
+        ArgumentExpression::Scaled(Box::new(self), rhs)
+    }
+}
+
+/// A custom argument of a constraint system, along with its inputs and constraints.
+#[derive(Clone)]
+pub(crate) struct Argument<F: Field> {
+    pub(crate) name: &'static str,
+    pub(crate) argument: Arc<dyn CustomArgument<F>>,
+    pub(crate) num_columns: usize,
+    pub(crate) inputs: Vec<Expression<F>>,
+    pub(crate) constraints: Vec<ArgumentExpression<F>>,
+    /// The columns of the argument that are opened, and their rotations.
+    pub(crate) queries: Vec<(usize, Rotation)>,
+    /// The phase whose advice columns the columns of the argument are committed with,
+    /// or `None` if they are committed after the lookup and permutation products.
+    pub(crate) phase: Option<sealed::Phase>,
+}
+
+impl<F: Field> Debug for Argument<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Argument")
+            .field("name", &self.name)
+            .field("num_columns", &self.num_columns)
+            .field("inputs", &self.inputs)
+            .field("constraints", &self.constraints)
+            .field("phase", &self.phase)
+            .finish()
+    }
+}
+
+impl<F: Field> Argument<F> {
+    /// Reads the inputs and constraints of a custom argument.
+    pub(crate) fn new(
+        name: &'static str,
+        argument: Arc<dyn CustomArgument<F>>,
+        phase: Option<sealed::Phase>,
+    ) -> Self {
+        let num_columns = argument.num_columns();
+        let constraints = argument.constraints();
+        let mut queries = vec![];
+        for constraint in constraints.iter() {
+            constraint.collect_queries(&mut queries);
+        }
+        assert!(
+            queries.iter().all(|(column, _)| *column < num_columns),
+            "custom argument '{}' queries a column it does not have",
+            name
+        );
+
+        Argument {
+            name,
+            num_columns,
+            inputs: argument.inputs(),
+            constraints,
+            queries,
+            phase,
+            argument,
+        }
+    }
+
+    /// Computes the columns of the argument, up to and including the row of
+    /// [`ArgumentExpression::Last`], which is `rows - 1`.
+    ///
+    /// `circuit` evaluates an expression in the columns of the circuit on every row.
+    pub(crate) fn compute_columns(
+        &self,
+        rows: usize,
+        circuit: &impl Fn(&Expression<F>) -> Vec<F>,
+        challenges: &[F],
+        theta: F,
+        beta: F,
+        gamma: F,
+    ) -> Result<Vec<Vec<F>>, Error> {
+        let inputs: Vec<_> = self
+            .inputs
+            .iter()
+            .map(|expression| {
+                let mut values = circuit(expression);
+                values.truncate(rows);
+                values
+            })
+            .collect();
+
+        let columns = self.argument.compute_columns(&ArgumentInputs {
+            inputs: &inputs,
+            challenges,
+            phase: self.phase,
+            theta,
+            beta,
+            gamma,
+            rows,
+        })?;
+        if columns.len() != self.num_columns || columns.iter().any(|values| values.len() > rows) {
+            return Err(Error::Synthesis);
+        }
+        Ok(columns)
+    }
+
+    /// Computes the columns of the argument on a circuit of `n` rows, and returns the
+    /// index of each constraint that they do not satisfy, along with the first row at
+    /// which it is not satisfied.
+    ///
+    /// The constraints are checked up to the row of [`ArgumentExpression::Last`]. The
+    /// columns of the argument are zero on the rows after it, where the prover blinds
+    /// them.
+    pub(crate) fn check(
+        &self,
+        n: usize,
+        blinding_factors: usize,
+        circuit: &impl Fn(&Expression<F>) -> Vec<F>,
+        challenges: &[F],
+        theta: F,
+        beta: F,
+        gamma: F,
+    ) -> Result<Vec<(usize, usize)>, Error> {
+        let rows = n - blinding_factors;
+        let columns = self.compute_columns(rows, circuit, challenges, theta, beta, gamma)?;
+
+        let indicator = |is_set: &dyn Fn(usize) -> bool| -> Vec<F> {
+            (0..n)
+                .map(|row| if is_set(row) { F::one() } else { F::zero() })
+                .collect()
+        };
+        let failures = self
+            .constraints
+            .iter()
+            .enumerate()
+            .filter_map(|(constraint_index, constraint)| {
+                let values = constraint.evaluate(
+                    &|leaf| match leaf {
+                        ArgumentExpression::Constant(scalar) => vec![*scalar; n],
+                        ArgumentExpression::Circuit(expression) => circuit(expression),
+                        ArgumentExpression::Column(column, rotation) => (0..n)
+                            .map(|row| {
+                                let row = (row as i32 + rotation.0).rem_euclid(n as i32);
+                                columns[*column]
+                                    .get(row as usize)
+                                    .copied()
+                                    .unwrap_or_else(F::zero)
+                            })
+                            .collect(),
+                        ArgumentExpression::First => indicator(&|row| row == 0),
+                        ArgumentExpression::Last => indicator(&|row| row == rows - 1),
+                        ArgumentExpression::Active => indicator(&|row| row < rows - 1),
+                        ArgumentExpression::Theta => vec![theta; n],
+                        ArgumentExpression::Beta => vec![beta; n],
+                        ArgumentExpression::Gamma => vec![gamma; n],
+                        _ => unreachable!("only leaves are evaluated"),
+                    },
+                    &|a| a.into_iter().map(|a| -a).collect(),
+                    &|a, b| a.into_iter().zip(b).map(|(a, b)| a + b).collect(),
+                    &|a, b| a.into_iter().zip(b).map(|(a, b)| a * b).collect(),
+                    &|a, scalar| a.into_iter().map(|a| a * scalar).collect(),
+                );
+                values[..rows]
+                    .iter()
+                    .position(|value| *value != F::zero())
+                    .map(|row| (constraint_index, row))
+            })
+            .collect();
+        Ok(failures)
+    }
+
+    pub(crate) fn required_degree(&self) -> usize {
+        self.constraints
+            .iter()
+            .map(|constraint| constraint.degree())
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Returns the largest number of rotations at which a column of the argument is
+    /// opened.
+    pub(crate) fn max_column_queries(&self) -> usize {
+        (0..self.num_columns)
+            .map(|column| {
+                self.queries
+                    .iter()
+                    .filter(|(queried, _)| *queried == column)
+                    .count()
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the index of the evaluation of `column` at `rotation` among the
+    /// evaluations of the argument's columns.
+    pub(crate) fn query_index(&self, column: usize, rotation: Rotation) -> usize {
+        self.queries
+            .iter()
+            .position(|query| *query == (column, rotation))
+            .expect("the constraints only query opened columns")
+    }
+}
@@ -0,0 +1,145 @@
+use super::super::{ChallengeBeta, ChallengeGamma, ChallengeTheta, ChallengeX, Error, ProvingKey};
+use super::Argument;
+use crate::plonk::evaluation::evaluate;
+use crate::{
+    arithmetic::{eval_polynomial, CurveAffine, FieldExt},
+    poly::{
+        commitment::{Blind, Params},
+        Coeff, LagrangeCoeff, Polynomial, ProverQuery,
+    },
+    transcript::{EncodedChallenge, TranscriptWrite},
+};
+use group::{ff::Field, Curve};
+use rand_core::RngCore;
+use std::ops::{Mul, MulAssign};
+
+#[derive(Debug)]
+pub(in crate::plonk) struct Committed<C: CurveAffine> {
+    pub(in crate::plonk) polys: Vec<Polynomial<C::Scalar, Coeff>>,
+    blinds: Vec<Blind<C::Scalar>>,
+}
+
+pub(in crate::plonk) struct Evaluated<C: CurveAffine> {
+    constructed: Committed<C>,
+}
+
+impl<F: FieldExt> Argument<F> {
+    /// Evaluates the input expressions of the argument, computes its columns from them
+    /// and commits to the columns.
+    ///
+    /// `products` holds `theta`, `beta` and `gamma`, unless the argument is committed in
+    /// an advice phase, before they are squeezed. Challenges that are not squeezed yet
+    /// are zero in `challenges`.
+    pub(in crate::plonk) fn commit<
+        'a,
+        'params: 'a,
+        C,
+        P: Params<'params, C>,
+        E: EncodedChallenge<C>,
+        R: RngCore,
+        T: TranscriptWrite<C, E>,
+    >(
+        &self,
+        pk: &ProvingKey<C>,
+        params: &P,
+        products: Option<(ChallengeTheta<C>, ChallengeBeta<C>, ChallengeGamma<C>)>,
+        advice_values: &'a [Polynomial<C::Scalar, LagrangeCoeff>],
+        fixed_values: &'a [Polynomial<C::Scalar, LagrangeCoeff>],
+        instance_values: &'a [Polynomial<C::Scalar, LagrangeCoeff>],
+        challenges: &'a [C::Scalar],
+        mut rng: R,
+        transcript: &mut T,
+    ) -> Result<Committed<C>, Error>
+    where
+        C: CurveAffine<ScalarExt = F>,
+        C::Curve: Mul<F, Output = C::Curve> + MulAssign<F>,
+    {
+        debug_assert_eq!(products.is_some(), self.phase.is_none());
+        let (theta, beta, gamma) = products
+            .map_or((F::zero(), F::zero(), F::zero()), |(theta, beta, gamma)| {
+                (*theta, *beta, *gamma)
+            });
+
+        // The columns are set up to and including the last usable row, and blinded
+        // after it.
+        let rows = params.n() as usize - pk.vk.cs.blinding_factors();
+        let columns = self.compute_columns(
+            rows,
+            &|expression| {
+                evaluate(
+                    expression,
+                    params.n() as usize,
+                    1,
+                    fixed_values,
+                    advice_values,
+                    instance_values,
+                    challenges,
+                )
+            },
+            challenges,
+            theta,
+            beta,
+            gamma,
+        )?;
+
+        let mut polys = Vec::with_capacity(columns.len());
+        let mut blinds = Vec::with_capacity(columns.len());
+        for values in columns {
+            let mut column = pk.vk.domain.empty_lagrange();
+            for (cell, value) in column.iter_mut().zip(values) {
+                *cell = value;
+            }
+            for cell in column[rows..].iter_mut() {
+                *cell = C::Scalar::random(&mut rng);
+            }
+
+            let blind = Blind(C::Scalar::random(&mut rng));
+            let commitment = params.commit_lagrange(&column, blind).to_affine();
+
+            // Hash column commitment
+            transcript.write_point(commitment)?;
+
+            polys.push(pk.vk.domain.lagrange_to_coeff(column));
+            blinds.push(blind);
+        }
+
+        Ok(Committed { polys, blinds })
+    }
+}
+
+impl<C: CurveAffine> Committed<C> {
+    pub(in crate::plonk) fn evaluate<E: EncodedChallenge<C>, T: TranscriptWrite<C, E>>(
+        self,
+        argument: &Argument<C::Scalar>,
+        pk: &ProvingKey<C>,
+        x: ChallengeX<C>,
+        transcript: &mut T,
+    ) -> Result<Evaluated<C>, Error> {
+        // Hash each column evaluation
+        for &(column, at) in argument.queries.iter() {
+            let eval = eval_polynomial(&self.polys[column], pk.vk.domain.rotate_omega(*x, at));
+            transcript.write_scalar(eval)?;
+        }
+
+        Ok(Evaluated { constructed: self })
+    }
+}
+
+impl<C: CurveAffine> Evaluated<C> {
+    pub(in crate::plonk) fn open<'a>(
+        &'a self,
+        argument: &'a Argument<C::Scalar>,
+        pk: &'a ProvingKey<C>,
+        x: ChallengeX<C>,
+    ) -> impl Iterator<Item = ProverQuery<'a, C>> + Clone {
+        // Open each column at the rotations the constraints query it at
+        argument
+            .queries
+            .iter()
+            .map(move |&(column, at)| ProverQuery {
+                point: pk.vk.domain.rotate_omega(*x, at),
+                poly: &self.constructed.polys[column],
+                blind: self.constructed.blinds[column],
+            })
+    }
+}
@@ -0,0 +1,123 @@
+use super::super::{ChallengeBeta, ChallengeGamma, ChallengeTheta, ChallengeX};
+use super::{Argument, ArgumentExpression};
+use crate::{
+    arithmetic::{CurveAffine, FieldExt},
+    plonk::{Error, VerifyingKey},
+    poly::{commitment::MSM, VerifierQuery},
+    transcript::{read_n_points, read_n_scalars, EncodedChallenge, TranscriptRead},
+};
+use ff::Field;
+
+#[derive(Debug)]
+pub struct Committed<C: CurveAffine> {
+    column_commitments: Vec<C>,
+}
+
+#[derive(Debug)]
+pub struct Evaluated<C: CurveAffine> {
+    committed: Committed<C>,
+    column_evals: Vec<C::Scalar>,
+}
+
+impl<F: FieldExt> Argument<F> {
+    pub fn read_commitments<C: CurveAffine, E: EncodedChallenge<C>, T: TranscriptRead<C, E>>(
+        &self,
+        transcript: &mut T,
+    ) -> Result<Committed<C>, Error> {
+        let column_commitments = read_n_points(transcript, self.num_columns)?;
+
+        Ok(Committed { column_commitments })
+    }
+}
+
+impl<C: CurveAffine> Committed<C> {
+    pub fn evaluate<E: EncodedChallenge<C>, T: TranscriptRead<C, E>>(
+        self,
+        argument: &Argument<C::Scalar>,
+        transcript: &mut T,
+    ) -> Result<Evaluated<C>, Error> {
+        let column_evals = read_n_scalars(transcript, argument.queries.len())?;
+
+        Ok(Evaluated {
+            committed: self,
+            column_evals,
+        })
+    }
+}
+
+impl<C: CurveAffine> Evaluated<C> {
+    pub(in crate::plonk) fn expressions(
+        &self,
+        l_0: C::Scalar,
+        l_last: C::Scalar,
+        l_blind: C::Scalar,
+        argument: &Argument<C::Scalar>,
+        theta: ChallengeTheta<C>,
+        beta: ChallengeBeta<C>,
+        gamma: ChallengeGamma<C>,
+        advice_evals: &[C::Scalar],
+        fixed_evals: &[C::Scalar],
+        instance_evals: &[C::Scalar],
+        challenges: &[C::Scalar],
+    ) -> Vec<C::Scalar> {
+        let active_rows = C::Scalar::one() - (l_last + l_blind);
+
+        argument
+            .constraints
+            .iter()
+            .map(|constraint| {
+                constraint.evaluate(
+                    &|leaf| match leaf {
+                        ArgumentExpression::Constant(scalar) => *scalar,
+                        ArgumentExpression::Circuit(expression) => expression.evaluate(
+                            &|scalar| scalar,
+                            &|_| panic!("virtual selectors are removed during optimization"),
+                            &|query| fixed_evals[query.index],
+                            &|query| advice_evals[query.index],
+                            &|query| instance_evals[query.index],
+                            &|challenge| challenges[challenge.index()],
+                            &|a| -a,
+                            &|a, b| a + &b,
+                            &|a, b| a * &b,
+                            &|a, scalar| a * &scalar,
+                        ),
+                        ArgumentExpression::Column(column, at) => {
+                            self.column_evals[argument.query_index(*column, *at)]
+                        }
+                        ArgumentExpression::First => l_0,
+                        ArgumentExpression::Last => l_last,
+                        ArgumentExpression::Active => active_rows,
+                        ArgumentExpression::Theta => *theta,
+                        ArgumentExpression::Beta => *beta,
+                        ArgumentExpression::Gamma => *gamma,
+                        _ => unreachable!("only leaves are evaluated"),
+                    },
+                    &|a| -a,
+                    &|a, b| a + &b,
+                    &|a, b| a * &b,
+                    &|a, scalar| a * &scalar,
+                )
+            })
+            .collect()
+    }
+
+    pub(in crate::plonk) fn queries<'r, M: MSM<C> + 'r>(
+        &'r self,
+        argument: &'r Argument<C::Scalar>,
+        vk: &'r VerifyingKey<C>,
+        x: ChallengeX<C>,
+    ) -> impl Iterator<Item = VerifierQuery<'r, C, M>> + Clone {
+        // Open each column commitment at the rotations the constraints query it at
+        argument
+            .queries
+            .iter()
+            .zip(self.column_evals.iter())
+            .map(move |(&(column, at), &eval)| {
+                VerifierQuery::new_commitment(
+                    &self.committed.column_commitments[column],
+                    vk.domain.rotate_omega(*x, at),
+                    eval,
+                )
+            })
+    }
+}
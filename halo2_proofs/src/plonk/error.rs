@@ -4,7 +4,7 @@ use std::error;
 use std::fmt;
 use std::io;
 
-use super::{Any, Column};
+use super::{Any, Column, Instance, TableColumn};
 
 /// This is an error that could occur during proving or circuit synthesis.
 // TODO: these errors need to be cleaned up
@@ -40,6 +40,204 @@ pub enum Error {
     /// The instance sets up a copy constraint involving a column that has not been
     /// included in the permutation.
     ColumnNotInPermutation(Column<Any>),
+    /// The prover-side sanity check found a witness that does not satisfy the circuit.
+    ///
+    /// This is only returned by [`create_proof`] when the `sanity-checks` feature is
+    /// enabled.
+    ///
+    /// [`create_proof`]: crate::plonk::create_proof
+    ConstraintNotSatisfied {
+        /// The index of the circuit (within the `circuits` passed to the prover) whose
+        /// witness is invalid.
+        circuit: usize,
+        /// The constraint that is not satisfied.
+        constraint: UnsatisfiedConstraint,
+    },
+    /// Two different cells are constrained to equal the same row of an instance column.
+    ///
+    /// This is returned by [`compute_instances`], which cannot tell which of the two
+    /// cells the row should take its value from.
+    ///
+    /// [`compute_instances`]: crate::circuit::compute_instances
+    InstanceRowConflict {
+        /// The instance column.
+        column: Column<Instance>,
+        /// The row of the instance column.
+        row: usize,
+    },
+    /// A [`TaggedTable`] could not be assigned.
+    ///
+    /// [`TaggedTable`]: crate::plonk::TaggedTable
+    InvalidTaggedTable(TaggedTableError),
+}
+
+/// The reason a [`TaggedTable`] could not be assigned, as reported by
+/// [`Error::InvalidTaggedTable`].
+///
+/// [`TaggedTable`]: crate::plonk::TaggedTable
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaggedTableError {
+    /// The table column is already used by an untagged table.
+    ColumnInUntaggedTable(TableColumn),
+    /// The table column is already shared by the tables of another tag column.
+    ColumnInOtherTagColumn(TableColumn),
+    /// The table's tag column was assigned to, although the layouter assigns the tags.
+    TagColumnAssigned(TableColumn),
+    /// A cell of the table was assigned more than once.
+    CellAssignedTwice {
+        /// The table column of the cell.
+        column: TableColumn,
+        /// The offset of the cell within the table.
+        offset: usize,
+    },
+}
+
+impl fmt::Display for TaggedTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaggedTableError::ColumnInUntaggedTable(column) => {
+                write!(f, "{:?} is already used by an untagged table", column)
+            }
+            TaggedTableError::ColumnInOtherTagColumn(column) => write!(
+                f,
+                "{:?} is already used by the tables of another tag column",
+                column
+            ),
+            TaggedTableError::TagColumnAssigned(column) => write!(
+                f,
+                "The tag column {:?} is assigned by the layouter, not the table",
+                column
+            ),
+            TaggedTableError::CellAssignedTwice { column, offset } => write!(
+                f,
+                "The cell at offset {} of {:?} is assigned more than once",
+                offset, column
+            ),
+        }
+    }
+}
+
+/// A constraint that is not satisfied by a witness, as reported by the prover-side
+/// sanity check.
+///
+/// Rows are absolute offsets in the circuit, as regions have already been laid out by
+/// the time the prover sees the witness.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UnsatisfiedConstraint {
+    /// A polynomial constraint of a custom gate evaluates to a non-zero value.
+    Gate {
+        /// The index of the gate.
+        gate_index: usize,
+        /// The name of the gate.
+        gate_name: &'static str,
+        /// The index of the constraint within the gate.
+        constraint_index: usize,
+        /// The name of the constraint.
+        constraint_name: &'static str,
+        /// The row at which the constraint is not satisfied.
+        row: usize,
+    },
+    /// A lookup input tuple is not present in the lookup table.
+    Lookup {
+        /// The index of the lookup argument.
+        lookup_index: usize,
+        /// The name of the lookup argument.
+        name: &'static str,
+        /// The row at which the input tuple is not present in the table.
+        row: usize,
+    },
+    /// A constraint of a custom argument evaluates to a non-zero value.
+    CustomArgument {
+        /// The index of the custom argument.
+        argument_index: usize,
+        /// The name of the custom argument.
+        name: &'static str,
+        /// The index of the constraint within the argument.
+        constraint_index: usize,
+        /// The row at which the constraint is not satisfied.
+        row: usize,
+    },
+    /// Two cells that are constrained to be equal hold different values.
+    Permutation {
+        /// The column of the first cell.
+        column: Column<Any>,
+        /// The row of the first cell.
+        row: usize,
+        /// The column of the cell it is copied to.
+        target_column: Column<Any>,
+        /// The row of the cell it is copied to.
+        target_row: usize,
+    },
+    /// The permutation maps a cell to a label that does not belong to any usable cell,
+    /// so the proving key does not match the circuit's copy constraints.
+    UnknownPermutationTarget {
+        /// The column of the cell.
+        column: Column<Any>,
+        /// The row of the cell.
+        row: usize,
+    },
+}
+
+impl fmt::Display for UnsatisfiedConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnsatisfiedConstraint::Gate {
+                gate_index,
+                gate_name,
+                constraint_index,
+                constraint_name,
+                row,
+            } => {
+                write!(
+                    f,
+                    "Constraint {}{} in gate {} ('{}') is not satisfied at row {}",
+                    constraint_index,
+                    if constraint_name.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" ('{}')", constraint_name)
+                    },
+                    gate_index,
+                    gate_name,
+                    row,
+                )
+            }
+            UnsatisfiedConstraint::Lookup {
+                lookup_index,
+                name,
+                row,
+            } => write!(
+                f,
+                "Lookup {} ('{}') input at row {} is not present in the table",
+                lookup_index, name, row,
+            ),
+            UnsatisfiedConstraint::CustomArgument {
+                argument_index,
+                name,
+                constraint_index,
+                row,
+            } => write!(
+                f,
+                "Constraint {} of custom argument {} ('{}') is not satisfied at row {}",
+                constraint_index, argument_index, name, row,
+            ),
+            UnsatisfiedConstraint::Permutation {
+                column,
+                row,
+                target_column,
+                target_row,
+            } => write!(
+                f,
+                "Equality constraint not satisfied between cell ({:?}, row {}) and cell ({:?}, row {})",
+                column, row, target_column, target_row,
+            ),
+            UnsatisfiedConstraint::UnknownPermutationTarget { column, row } => write!(
+                f,
+                "Cell ({:?}, row {}) is mapped by the permutation to a cell outside the usable rows",
+                column, row,
+            ),
+        }
+    }
 }
 
 impl From<io::Error> for Error {
@@ -88,6 +286,16 @@ impl fmt::Display for Error {
                 "Column {:?} must be included in the permutation. Help: try applying `meta.enable_equalty` on the column",
                 column
             ),
+            Error::ConstraintNotSatisfied {
+                circuit,
+                constraint,
+            } => write!(f, "Circuit {}: {}", circuit, constraint),
+            Error::InstanceRowConflict { column, row } => write!(
+                f,
+                "Row {} of instance column {:?} is constrained to equal two different cells",
+                row, column
+            ),
+            Error::InvalidTaggedTable(error) => write!(f, "Invalid tagged table: {}", error),
         }
     }
 }
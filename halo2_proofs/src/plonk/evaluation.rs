@@ -1,7 +1,10 @@
 use crate::multicore;
 use crate::plonk::lookup::prover::Committed;
 use crate::plonk::permutation::Argument;
-use crate::plonk::{lookup, permutation, AdviceQuery, Any, FixedQuery, InstanceQuery, ProvingKey};
+use crate::plonk::{
+    custom_argument, lookup, permutation, AdviceQuery, Any, ArgumentExpression, FixedQuery,
+    InstanceQuery, ProvingKey,
+};
 use crate::poly::Basis;
 use crate::{
     arithmetic::{eval_polynomial, parallelize, CurveAffine, FieldExt},
@@ -26,7 +29,8 @@ use std::{
     ops::{Index, Mul, MulAssign},
 };
 
-use super::{ConstraintSystem, Expression};
+use super::circuit::DagNode;
+use super::{ConstraintSystem, Expression, ExpressionDag};
 
 /// Return the index in the polynomial of size `isize` after rotation `rot`.
 fn get_rotation_idx(idx: usize, rot: i32, rot_scale: i32, isize: i32) -> usize {
@@ -46,6 +50,9 @@ pub enum ValueSource {
     Advice(usize, usize),
     /// This is an instance (external) column
     Instance(usize, usize),
+    /// This is a column of a custom argument, after the Lagrange polynomials that
+    /// custom arguments can use
+    Argument(usize, usize),
     /// This is a challenge
     Challenge(usize),
     /// beta
@@ -76,6 +83,7 @@ impl ValueSource {
         fixed_values: &[Polynomial<F, B>],
         advice_values: &[Polynomial<F, B>],
         instance_values: &[Polynomial<F, B>],
+        argument_values: &[Polynomial<F, B>],
         challenges: &[F],
         beta: &F,
         gamma: &F,
@@ -95,6 +103,9 @@ impl ValueSource {
             ValueSource::Instance(column_index, rotation) => {
                 instance_values[*column_index][rotations[*rotation]]
             }
+            ValueSource::Argument(column_index, rotation) => {
+                argument_values[*column_index][rotations[*rotation]]
+            }
             ValueSource::Challenge(index) => challenges[*index],
             ValueSource::Beta() => *beta,
             ValueSource::Gamma() => *gamma,
@@ -136,6 +147,7 @@ impl Calculation {
         fixed_values: &[Polynomial<F, B>],
         advice_values: &[Polynomial<F, B>],
         instance_values: &[Polynomial<F, B>],
+        argument_values: &[Polynomial<F, B>],
         challenges: &[F],
         beta: &F,
         gamma: &F,
@@ -151,6 +163,7 @@ impl Calculation {
                 fixed_values,
                 advice_values,
                 instance_values,
+                argument_values,
                 challenges,
                 beta,
                 gamma,
@@ -186,6 +199,8 @@ pub struct Evaluator<C: CurveAffine> {
     pub custom_gates: GraphEvaluator<C>,
     ///  Lookups evalution
     pub lookups: Vec<GraphEvaluator<C>>,
+    ///  Custom arguments evalution
+    pub custom_arguments: Vec<GraphEvaluator<C>>,
 }
 
 /// GraphEvaluator
@@ -221,23 +236,57 @@ pub struct CalculationInfo {
 
 impl<C: CurveAffine> Evaluator<C> {
     /// Creates a new evaluation structure
-    pub fn new(cs: &ConstraintSystem<C::ScalarExt>) -> Self {
+    pub fn new(cs: &ConstraintSystem<C::ScalarExt>, dag: &ExpressionDag<C::ScalarExt>) -> Self {
         let mut ev = Evaluator::default();
 
         // Custom gates
+        let nodes = ev.custom_gates.add_dag_nodes(dag);
+        let mut value = ValueSource::PreviousValue();
         let mut parts = Vec::new();
-        for gate in cs.gates.iter() {
-            parts.extend(
-                gate.polynomials()
-                    .iter()
-                    .map(|poly| ev.custom_gates.add_expression(poly)),
-            );
+        for gate in dag.gates.iter() {
+            let constraints: Vec<_> = gate
+                .constraints
+                .iter()
+                .map(|constraint| {
+                    let node = constraint
+                        .node
+                        .map_or(ValueSource::Constant(1), |n| nodes[n]);
+                    ev.custom_gates.add_scaled(node, &constraint.coefficient)
+                })
+                .collect();
+            match gate.factor {
+                None => parts.extend(constraints),
+                Some(factor) => {
+                    // Multiply the shared factor in once, rather than into every constraint:
+                    // `value * y^m + factor * (c_0 * y^(m - 1) + ... + c_(m - 1))`.
+                    if !parts.is_empty() {
+                        value = ev.custom_gates.add_calculation(Calculation::Horner(
+                            value,
+                            std::mem::take(&mut parts),
+                            ValueSource::Y(),
+                        ));
+                    }
+                    let y_power = ev
+                        .custom_gates
+                        .add_power(ValueSource::Y(), constraints.len());
+                    let constraints = ev.custom_gates.add_calculation(Calculation::Horner(
+                        ValueSource::Constant(0),
+                        constraints,
+                        ValueSource::Y(),
+                    ));
+                    let block = ev.custom_gates.add_product(nodes[factor], constraints);
+                    value = ev.custom_gates.add_calculation(Calculation::Horner(
+                        value,
+                        vec![block],
+                        y_power,
+                    ));
+                }
+            }
+        }
+        if !parts.is_empty() || value == ValueSource::PreviousValue() {
+            ev.custom_gates
+                .add_calculation(Calculation::Horner(value, parts, ValueSource::Y()));
         }
-        ev.custom_gates.add_calculation(Calculation::Horner(
-            ValueSource::PreviousValue(),
-            parts,
-            ValueSource::Y(),
-        ));
 
         // Lookups
         for lookup in cs.lookups.iter() {
@@ -273,6 +322,24 @@ impl<C: CurveAffine> Evaluator<C> {
             ev.lookups.push(graph);
         }
 
+        // Custom arguments
+        for argument in cs.custom_arguments.iter() {
+            let mut graph = GraphEvaluator::default();
+
+            let parts = argument
+                .constraints
+                .iter()
+                .map(|constraint| graph.add_argument_expression(constraint))
+                .collect();
+            graph.add_calculation(Calculation::Horner(
+                ValueSource::PreviousValue(),
+                parts,
+                ValueSource::Y(),
+            ));
+
+            ev.custom_arguments.push(graph);
+        }
+
         ev
     }
 
@@ -289,6 +356,7 @@ impl<C: CurveAffine> Evaluator<C> {
         theta: C::ScalarExt,
         lookups: &[Vec<lookup::prover::Committed<C>>],
         permutations: &[permutation::prover::Committed<C>],
+        custom_arguments: &[Vec<custom_argument::prover::Committed<C>>],
     ) -> Polynomial<C::ScalarExt, ExtendedLagrangeCoeff> {
         let domain = &pk.vk.domain;
         let size = 1 << domain.k() as usize;
@@ -344,11 +412,12 @@ impl<C: CurveAffine> Evaluator<C> {
 
                 // Core expression evaluations
                 let num_threads = multicore::current_num_threads();
-                for (((advice, instance), lookups), permutation) in advice
+                for ((((advice, instance), lookups), permutation), custom_arguments) in advice
                     .iter()
                     .zip(instance.iter())
                     .zip(lookups.iter())
                     .zip(permutations.iter())
+                    .zip(custom_arguments.iter())
                 {
                     // Custom gates
                     multicore::scope(|scope| {
@@ -364,6 +433,7 @@ impl<C: CurveAffine> Evaluator<C> {
                                         fixed,
                                         advice,
                                         instance,
+                                        &[],
                                         challenges,
                                         &beta,
                                         &gamma,
@@ -522,6 +592,7 @@ impl<C: CurveAffine> Evaluator<C> {
                                     fixed,
                                     advice,
                                     instance,
+                                    &[],
                                     challenges,
                                     &beta,
                                     &gamma,
@@ -572,6 +643,49 @@ impl<C: CurveAffine> Evaluator<C> {
                             }
                         });
                     }
+
+                    // Custom arguments
+                    for (n, argument) in custom_arguments.iter().enumerate() {
+                        // The Lagrange polynomials that the constraints can use, followed
+                        // by the columns of the argument.
+                        let argument_cosets: Vec<Polynomial<C::ScalarExt, LagrangeCoeff>> =
+                            [&l0, &l_last, &l_active_row]
+                                .into_iter()
+                                .cloned()
+                                .chain(argument.polys.iter().map(|poly| {
+                                    domain.coeff_to_extended_part(
+                                        poly.clone(),
+                                        current_extended_omega,
+                                    )
+                                }))
+                                .collect();
+                        let argument_cosets = &argument_cosets[..];
+
+                        // Custom argument constraints
+                        parallelize(&mut values, |values, start| {
+                            let argument_evaluator = &self.custom_arguments[n];
+                            let mut eval_data = argument_evaluator.instance();
+                            for (i, value) in values.iter_mut().enumerate() {
+                                let idx = start + i;
+                                *value = argument_evaluator.evaluate(
+                                    &mut eval_data,
+                                    fixed,
+                                    advice,
+                                    instance,
+                                    argument_cosets,
+                                    challenges,
+                                    &beta,
+                                    &gamma,
+                                    &theta,
+                                    &y,
+                                    value,
+                                    idx,
+                                    rot_scale,
+                                    isize,
+                                );
+                            }
+                        });
+                    }
                 }
                 current_extended_omega *= extended_omega;
                 values
@@ -646,6 +760,150 @@ impl<C: CurveAffine> GraphEvaluator<C> {
         }
     }
 
+    /// Adds the sum of two values
+    fn add_sum(&mut self, a: ValueSource, b: ValueSource) -> ValueSource {
+        if a == ValueSource::Constant(0) {
+            b
+        } else if b == ValueSource::Constant(0) {
+            a
+        } else if a <= b {
+            self.add_calculation(Calculation::Add(a, b))
+        } else {
+            self.add_calculation(Calculation::Add(b, a))
+        }
+    }
+
+    /// Adds the difference of two values
+    fn add_sub(&mut self, a: ValueSource, b: ValueSource) -> ValueSource {
+        if a == ValueSource::Constant(0) {
+            self.add_calculation(Calculation::Negate(b))
+        } else if b == ValueSource::Constant(0) {
+            a
+        } else {
+            self.add_calculation(Calculation::Sub(a, b))
+        }
+    }
+
+    /// Adds the product of two values
+    fn add_product(&mut self, a: ValueSource, b: ValueSource) -> ValueSource {
+        if a == ValueSource::Constant(0) || b == ValueSource::Constant(0) {
+            ValueSource::Constant(0)
+        } else if a == ValueSource::Constant(1) {
+            b
+        } else if b == ValueSource::Constant(1) {
+            a
+        } else if a == ValueSource::Constant(2) {
+            self.add_calculation(Calculation::Double(b))
+        } else if b == ValueSource::Constant(2) {
+            self.add_calculation(Calculation::Double(a))
+        } else if a == b {
+            self.add_calculation(Calculation::Square(a))
+        } else if a <= b {
+            self.add_calculation(Calculation::Mul(a, b))
+        } else {
+            self.add_calculation(Calculation::Mul(b, a))
+        }
+    }
+
+    /// Adds a value multiplied by a constant
+    fn add_scaled(&mut self, a: ValueSource, scalar: &C::ScalarExt) -> ValueSource {
+        if *scalar == -C::ScalarExt::one() {
+            self.add_sub(ValueSource::Constant(0), a)
+        } else {
+            let scalar = self.add_constant(scalar);
+            self.add_product(a, scalar)
+        }
+    }
+
+    /// Adds `base^exp`, using square-and-multiply
+    fn add_power(&mut self, base: ValueSource, exp: usize) -> ValueSource {
+        let mut result = ValueSource::Constant(1);
+        for bit in (0..usize::BITS - exp.leading_zeros()).rev() {
+            result = self.add_product(result, result);
+            if (exp >> bit) & 1 == 1 {
+                result = self.add_product(result, base);
+            }
+        }
+        result
+    }
+
+    /// Generates an optimized evaluation for every node of the DAG, in order
+    fn add_dag_nodes(&mut self, dag: &ExpressionDag<C::ScalarExt>) -> Vec<ValueSource> {
+        let mut values: Vec<ValueSource> = Vec::with_capacity(dag.nodes.len());
+        for node in dag.nodes.iter() {
+            let value = match node {
+                DagNode::Fixed(query) => self.add_expression(&Expression::Fixed(*query)),
+                DagNode::Advice(query) => self.add_expression(&Expression::Advice(*query)),
+                DagNode::Instance(query) => self.add_expression(&Expression::Instance(*query)),
+                DagNode::Challenge(challenge) => {
+                    self.add_expression(&Expression::Challenge(*challenge))
+                }
+                DagNode::Selector(_) => unreachable!(),
+                DagNode::Sum { constant, terms } => {
+                    let mut sum = self.add_constant(constant);
+                    for (coefficient, node) in terms.iter() {
+                        sum = if *coefficient == -C::ScalarExt::one() {
+                            self.add_sub(sum, values[*node])
+                        } else {
+                            let term = self.add_scaled(values[*node], coefficient);
+                            self.add_sum(sum, term)
+                        };
+                    }
+                    sum
+                }
+                DagNode::Product(factors) => factors
+                    .iter()
+                    .fold(ValueSource::Constant(1), |product, factor| {
+                        self.add_product(product, values[*factor])
+                    }),
+            };
+            values.push(value);
+        }
+        values
+    }
+
+    /// Adds a value of a custom argument. The Lagrange polynomials `l_0`, `l_last` and
+    /// `l_active_row` come before the columns of the argument.
+    fn add_argument_value(&mut self, index: usize, rotation: &Rotation) -> ValueSource {
+        let rot_idx = self.add_rotation(rotation);
+        self.add_calculation(Calculation::Store(ValueSource::Argument(index, rot_idx)))
+    }
+
+    /// Generates an optimized evaluation for a constraint of a custom argument
+    fn add_argument_expression(&mut self, expr: &ArgumentExpression<C::ScalarExt>) -> ValueSource {
+        match expr {
+            ArgumentExpression::Constant(scalar) => self.add_constant(scalar),
+            ArgumentExpression::Circuit(expr) => self.add_expression(expr),
+            ArgumentExpression::Column(column, rotation) => {
+                self.add_argument_value(3 + column, rotation)
+            }
+            ArgumentExpression::First => self.add_argument_value(0, &Rotation::cur()),
+            ArgumentExpression::Last => self.add_argument_value(1, &Rotation::cur()),
+            ArgumentExpression::Active => self.add_argument_value(2, &Rotation::cur()),
+            ArgumentExpression::Theta => ValueSource::Theta(),
+            ArgumentExpression::Beta => ValueSource::Beta(),
+            ArgumentExpression::Gamma => ValueSource::Gamma(),
+            ArgumentExpression::Negated(a) => {
+                let result_a = self.add_argument_expression(a);
+                self.add_sub(ValueSource::Constant(0), result_a)
+            }
+            ArgumentExpression::Sum(a, b) => {
+                let result_a = self.add_argument_expression(a);
+                let result_b = self.add_argument_expression(b);
+                self.add_sum(result_a, result_b)
+            }
+            ArgumentExpression::Product(a, b) => {
+                let result_a = self.add_argument_expression(a);
+                let result_b = self.add_argument_expression(b);
+                self.add_product(result_a, result_b)
+            }
+            ArgumentExpression::Scaled(a, f) => {
+                let result_a = self.add_argument_expression(a);
+                self.add_scaled(result_a, f)
+            }
+        }
+    }
+
     /// Generates an optimized evaluation for the expression
     fn add_expression(&mut self, expr: &Expression<C::ScalarExt>) -> ValueSource {
         match expr {
@@ -691,49 +949,19 @@ impl<C: CurveAffine> GraphEvaluator<C> {
                     Expression::Negated(b_int) => {
                         let result_a = self.add_expression(a);
                         let result_b = self.add_expression(b_int);
-                        if result_a == ValueSource::Constant(0) {
-                            self.add_calculation(Calculation::Negate(result_b))
-                        } else if result_b == ValueSource::Constant(0) {
-                            result_a
-                        } else {
-                            self.add_calculation(Calculation::Sub(result_a, result_b))
-                        }
+                        self.add_sub(result_a, result_b)
                     }
                     _ => {
                         let result_a = self.add_expression(a);
                         let result_b = self.add_expression(b);
-                        if result_a == ValueSource::Constant(0) {
-                            result_b
-                        } else if result_b == ValueSource::Constant(0) {
-                            result_a
-                        } else if result_a <= result_b {
-                            self.add_calculation(Calculation::Add(result_a, result_b))
-                        } else {
-                            self.add_calculation(Calculation::Add(result_b, result_a))
-                        }
+                        self.add_sum(result_a, result_b)
                     }
                 }
             }
             Expression::Product(a, b) => {
                 let result_a = self.add_expression(a);
                 let result_b = self.add_expression(b);
-                if result_a == ValueSource::Constant(0) || result_b == ValueSource::Constant(0) {
-                    ValueSource::Constant(0)
-                } else if result_a == ValueSource::Constant(1) {
-                    result_b
-                } else if result_b == ValueSource::Constant(1) {
-                    result_a
-                } else if result_a == ValueSource::Constant(2) {
-                    self.add_calculation(Calculation::Double(result_b))
-                } else if result_b == ValueSource::Constant(2) {
-                    self.add_calculation(Calculation::Double(result_a))
-                } else if result_a == result_b {
-                    self.add_calculation(Calculation::Square(result_a))
-                } else if result_a <= result_b {
-                    self.add_calculation(Calculation::Mul(result_a, result_b))
-                } else {
-                    self.add_calculation(Calculation::Mul(result_b, result_a))
-                }
+                self.add_product(result_a, result_b)
             }
             Expression::Scaled(a, f) => {
                 if *f == C::ScalarExt::zero() {
@@ -763,6 +991,7 @@ impl<C: CurveAffine> GraphEvaluator<C> {
         fixed: &[Polynomial<C::ScalarExt, B>],
         advice: &[Polynomial<C::ScalarExt, B>],
         instance: &[Polynomial<C::ScalarExt, B>],
+        arguments: &[Polynomial<C::ScalarExt, B>],
         challenges: &[C::ScalarExt],
         beta: &C::ScalarExt,
         gamma: &C::ScalarExt,
@@ -787,6 +1016,7 @@ impl<C: CurveAffine> GraphEvaluator<C> {
                 fixed,
                 advice,
                 instance,
+                arguments,
                 challenges,
                 beta,
                 gamma,
@@ -285,6 +285,10 @@ impl<'a, F: Field> Assignment<F> for Assembly<'a, F> {
         Value::unknown()
     }
 
+    fn usable_rows(&self) -> Option<usize> {
+        Some(self.usable_rows.end)
+    }
+
     fn annotate_column<A, AR>(&mut self, _annotation: A, _column: Column<Any>)
     where
         A: FnOnce() -> AR,
@@ -307,6 +311,11 @@ impl<'a, F: Field> Assignment<F> for Assembly<'a, F> {
 }
 
 /// Generate a `VerifyingKey` from an instance of `Circuit`.
+///
+/// The simple selectors of the circuit are combined into fixed columns as set with
+/// [`ConstraintSystem::set_selector_compression`], and the resulting columns are
+/// reported by [`ConstraintSystem::compressed_selectors`] on the key's constraint
+/// system.
 pub fn keygen_vk<'params, C, P, ConcreteCircuit>(
     params: &P,
     circuit: &ConcreteCircuit,
@@ -555,7 +564,7 @@ where
     let l_active_row = vk.domain.lagrange_to_coeff(l_active_row);
 
     // Compute the optimized evaluation data structure
-    let ev = Evaluator::new(&vk.cs);
+    let ev = Evaluator::new(&vk.cs, &vk.expression_dag);
 
     Ok(ProvingKey {
         vk,
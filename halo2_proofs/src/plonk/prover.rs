@@ -16,9 +16,10 @@ use super::{
         Advice, Any, Assignment, Challenge, Circuit, Column, ConstraintSystem, FirstPhase, Fixed,
         FloorPlanner, Instance, Selector,
     },
-    lookup, permutation, vanishing, ChallengeBeta, ChallengeGamma, ChallengeTheta, ChallengeX,
-    ChallengeY, Error, Expression, ProvingKey,
+    custom_argument, lookup, permutation, vanishing, ChallengeBeta, ChallengeGamma, ChallengeTheta,
+    ChallengeX, ChallengeY, Error, Expression, ProvingKey,
 };
+use crate::plonk::evaluation::evaluate;
 use crate::{
     arithmetic::{eval_polynomial, CurveAffine, FieldExt},
     circuit::Value,
@@ -36,10 +37,18 @@ use crate::{
 };
 use group::prime::PrimeCurveAffine;
 
+#[cfg(feature = "sanity-checks")]
+mod sanity;
+
 /// This creates a proof for the provided `circuit` when given the public
 /// parameters `params` and the proving key [`ProvingKey`] that was
 /// generated previously for the same circuit. The provided `instances`
 /// are zero-padded internally.
+///
+/// With the `sanity-checks` feature enabled, the witness is checked against the
+/// constraint system before the quotient polynomial is committed to, and
+/// [`Error::ConstraintNotSatisfied`] names the first unsatisfied gate, lookup or
+/// equality constraint.
 pub fn create_proof<
     'params,
     Scheme: CommitmentScheme,
@@ -332,6 +341,10 @@ pub fn create_proof<
                 .unwrap_or_else(Value::unknown)
         }
 
+        fn usable_rows(&self) -> Option<usize> {
+            Some(self.usable_rows.end)
+        }
+
         fn push_namespace<NR, N>(&mut self, _: N)
         where
             NR: Into<String>,
@@ -345,7 +358,7 @@ pub fn create_proof<
         }
     }
 
-    let (advice, challenges) = {
+    let (advice, challenges, custom_arguments) = {
         let mut advice = vec![
             AdviceSingle::<Scheme::Curve, LagrangeCoeff> {
                 advice_polys: vec![domain.empty_lagrange(); meta.num_advice_columns],
@@ -357,9 +370,25 @@ pub fn create_proof<
         let mut advice_assignments =
             vec![vec![domain.empty_lagrange_assigned(); meta.num_advice_columns]; instances.len()];
         let mut challenges = HashMap::<usize, Scheme::Scalar>::with_capacity(meta.num_challenges);
+        // The columns of the custom arguments that are committed in an advice phase.
+        let mut custom_arguments: Vec<
+            Vec<Option<custom_argument::prover::Committed<Scheme::Curve>>>,
+        > = instances
+            .iter()
+            .map(|_| meta.custom_arguments.iter().map(|_| None).collect())
+            .collect();
 
         let unusable_rows_start = params.n() as usize - (meta.blinding_factors() + 1);
         for current_phase in pk.vk.cs.phases() {
+            // The challenges squeezed before this phase, and zero for the others.
+            let available_challenges: Vec<_> = (0..meta.num_challenges)
+                .map(|index| {
+                    challenges
+                        .get(&index)
+                        .copied()
+                        .unwrap_or_else(Scheme::Scalar::zero)
+                })
+                .collect();
             let column_indices = meta
                 .advice_column_phase
                 .iter()
@@ -373,10 +402,11 @@ pub fn create_proof<
                 })
                 .collect::<BTreeSet<_>>();
 
-            for (circuit_idx, ((circuit, advice), instances)) in circuits
+            for (circuit_idx, (((circuit, advice), instances), instance)) in circuits
                 .iter()
                 .zip(advice.iter_mut())
                 .zip(instances)
+                .zip(instance.iter())
                 .enumerate()
             {
                 let advice_vec = Arc::new(vec![
@@ -443,6 +473,48 @@ pub fn create_proof<
                         .collect(),
                 );
 
+                // Fill in the intermediate columns of this phase, which were allocated to
+                // reduce the degree of gates, from the rest of the witness.
+                if meta
+                    .intermediate_columns
+                    .iter()
+                    .any(|(column, _)| column.column_type().phase == current_phase)
+                {
+                    let mut columns = advice.advice_polys.clone();
+                    for (column_index, values) in
+                        column_indices.iter().zip(advice_values.iter_mut())
+                    {
+                        mem::swap(&mut columns[*column_index], values);
+                    }
+                    // Intermediate columns are allocated after those their expressions use.
+                    for (column, expression) in meta.intermediate_columns.iter() {
+                        if column.column_type().phase != current_phase {
+                            continue;
+                        }
+                        let values = evaluate(
+                            expression,
+                            params.n() as usize,
+                            1,
+                            &pk.fixed_values,
+                            &columns,
+                            &instance.instance_values,
+                            &available_challenges,
+                        );
+                        for (cell, value) in columns[column.index()]
+                            .iter_mut()
+                            .zip(values)
+                            .take(unusable_rows_start)
+                        {
+                            *cell = value;
+                        }
+                    }
+                    for (column_index, values) in
+                        column_indices.iter().zip(advice_values.iter_mut())
+                    {
+                        mem::swap(&mut columns[*column_index], values);
+                    }
+                }
+
                 // Add blinding factors to advice columns
                 for advice_values in &mut advice_values {
                     //for cell in &mut advice_values[unusable_rows_start..] {
@@ -481,6 +553,27 @@ pub fn create_proof<
                     advice.advice_polys[*column_index] = advice_values;
                     advice.advice_blinds[*column_index] = blind;
                 }
+
+                // Compute and commit to the columns of each custom argument of this phase
+                for (argument, committed) in meta
+                    .custom_arguments
+                    .iter()
+                    .zip(custom_arguments[circuit_idx].iter_mut())
+                {
+                    if argument.phase == Some(current_phase) {
+                        *committed = Some(argument.commit(
+                            pk,
+                            params,
+                            None,
+                            &advice.advice_polys,
+                            &pk.fixed_values,
+                            &instance.instance_values,
+                            &available_challenges,
+                            &mut rng,
+                            transcript,
+                        )?);
+                    }
+                }
             }
 
             for (index, phase) in meta.challenge_phase.iter().enumerate() {
@@ -497,9 +590,22 @@ pub fn create_proof<
             .map(|index| challenges.remove(&index).unwrap())
             .collect::<Vec<_>>();
 
-        (advice, challenges)
+        (advice, challenges, custom_arguments)
     };
 
+    // Check the witness against every gate, lookup and equality constraint, so that a
+    // bad witness is reported here instead of producing a proof that fails to verify.
+    #[cfg(feature = "sanity-checks")]
+    for (circuit, (instance, advice)) in instance.iter().zip(advice.iter()).enumerate() {
+        sanity::check_witness(
+            pk,
+            circuit,
+            &advice.advice_polys,
+            &instance.instance_values,
+            &challenges,
+        )?;
+    }
+
     // Sample theta challenge for keeping lookup columns linearly independent
     let theta: ChallengeTheta<_> = transcript.squeeze_challenge_scalar();
 
@@ -567,6 +673,50 @@ pub fn create_proof<
         })
         .collect::<Result<Vec<_>, _>>()?;
 
+    // Check the witness against the custom arguments, now that every challenge their
+    // columns can depend on is squeezed.
+    #[cfg(feature = "sanity-checks")]
+    for (circuit, (instance, advice)) in instance.iter().zip(advice.iter()).enumerate() {
+        sanity::check_custom_arguments(
+            pk,
+            circuit,
+            &advice.advice_polys,
+            &instance.instance_values,
+            &challenges,
+            (*theta, *beta, *gamma),
+        )?;
+    }
+
+    let custom_arguments: Vec<Vec<custom_argument::prover::Committed<Scheme::Curve>>> = instance
+        .iter()
+        .zip(advice.iter())
+        .zip(custom_arguments)
+        .map(|((instance, advice), committed)| -> Result<Vec<_>, Error> {
+            // Compute and commit to the columns of each custom argument that is not
+            // committed in an advice phase
+            pk.vk
+                .cs
+                .custom_arguments
+                .iter()
+                .zip(committed)
+                .map(|(argument, committed)| match committed {
+                    Some(committed) => Ok(committed),
+                    None => argument.commit(
+                        pk,
+                        params,
+                        Some((theta, beta, gamma)),
+                        &advice.advice_polys,
+                        &pk.fixed_values,
+                        &instance.instance_values,
+                        &challenges,
+                        &mut rng,
+                        transcript,
+                    ),
+                })
+                .collect()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
     // Commit to the vanishing argument's random polynomial for blinding h(x_3)
     let vanishing = vanishing::Argument::commit(params, domain, &mut rng, transcript)?;
 
@@ -610,6 +760,7 @@ pub fn create_proof<
         *theta,
         &lookups,
         &permutations,
+        &custom_arguments,
     );
 
     // Construct the vanishing argument's h(X) commitments
@@ -696,53 +847,72 @@ pub fn create_proof<
         })
         .collect::<Result<Vec<_>, _>>()?;
 
-    let instances = instance
-        .iter()
-        .zip(advice.iter())
-        .zip(permutations.iter())
-        .zip(lookups.iter())
-        .flat_map(|(((instance, advice), permutation), lookups)| {
-            iter::empty()
-                .chain(
-                    P::QUERY_INSTANCE
-                        .then_some(pk.vk.cs.instance_queries.iter().map(move |&(column, at)| {
+    // Evaluate the columns of the custom arguments, if any, at omega^i x.
+    let custom_arguments: Vec<Vec<custom_argument::prover::Evaluated<Scheme::Curve>>> =
+        custom_arguments
+            .into_iter()
+            .map(|custom_arguments| -> Result<Vec<_>, _> {
+                custom_arguments
+                    .into_iter()
+                    .zip(pk.vk.cs.custom_arguments.iter())
+                    .map(|(p, argument)| p.evaluate(argument, pk, x, transcript))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+    let instances =
+        instance
+            .iter()
+            .zip(advice.iter())
+            .zip(permutations.iter())
+            .zip(lookups.iter())
+            .zip(custom_arguments.iter())
+            .flat_map(
+                |((((instance, advice), permutation), lookups), custom_arguments)| {
+                    iter::empty()
+                        .chain(
+                            P::QUERY_INSTANCE
+                                .then_some(pk.vk.cs.instance_queries.iter().map(
+                                    move |&(column, at)| ProverQuery {
+                                        point: domain.rotate_omega(*x, at),
+                                        poly: &instance.instance_polys[column.index()],
+                                        blind: Blind::default(),
+                                    },
+                                ))
+                                .into_iter()
+                                .flatten(),
+                        )
+                        .chain(pk.vk.cs.advice_queries.iter().map(move |&(column, at)| {
                             ProverQuery {
                                 point: domain.rotate_omega(*x, at),
-                                poly: &instance.instance_polys[column.index()],
-                                blind: Blind::default(),
+                                poly: &advice.advice_polys[column.index()],
+                                blind: advice.advice_blinds[column.index()],
                             }
                         }))
-                        .into_iter()
-                        .flatten(),
-                )
-                .chain(
-                    pk.vk
-                        .cs
-                        .advice_queries
-                        .iter()
-                        .map(move |&(column, at)| ProverQuery {
-                            point: domain.rotate_omega(*x, at),
-                            poly: &advice.advice_polys[column.index()],
-                            blind: advice.advice_blinds[column.index()],
-                        }),
-                )
-                .chain(permutation.open(pk, x))
-                .chain(lookups.iter().flat_map(move |p| p.open(pk, x)).into_iter())
-        })
-        .chain(
-            pk.vk
-                .cs
-                .fixed_queries
-                .iter()
-                .map(|&(column, at)| ProverQuery {
-                    point: domain.rotate_omega(*x, at),
-                    poly: &pk.fixed_polys[column.index()],
-                    blind: Blind::default(),
-                }),
-        )
-        .chain(pk.permutation.open(x))
-        // We query the h(X) polynomial at x
-        .chain(vanishing.open(x));
+                        .chain(permutation.open(pk, x))
+                        .chain(lookups.iter().flat_map(move |p| p.open(pk, x)).into_iter())
+                        .chain(
+                            custom_arguments
+                                .iter()
+                                .zip(pk.vk.cs.custom_arguments.iter())
+                                .flat_map(move |(p, argument)| p.open(argument, pk, x)),
+                        )
+                },
+            )
+            .chain(
+                pk.vk
+                    .cs
+                    .fixed_queries
+                    .iter()
+                    .map(|&(column, at)| ProverQuery {
+                        point: domain.rotate_omega(*x, at),
+                        poly: &pk.fixed_polys[column.index()],
+                        blind: Blind::default(),
+                    }),
+            )
+            .chain(pk.permutation.open(x))
+            // We query the h(X) polynomial at x
+            .chain(vanishing.open(x));
 
     let prover = P::new(params);
     prover
@@ -0,0 +1,186 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use ff::Field;
+
+use crate::{
+    arithmetic::{CurveAffine, FieldExt},
+    plonk::{evaluation::evaluate, Any, Error, Expression, ProvingKey, UnsatisfiedConstraint},
+    poly::{LagrangeCoeff, Polynomial},
+};
+
+/// Checks that the Lagrange-basis witness of a single circuit satisfies every gate,
+/// lookup and equality constraint of `pk`, returning the first violation found.
+///
+/// Only the usable rows are checked; the blinding rows at the end of every column
+/// hold random values and are not constrained.
+pub(super) fn check_witness<C: CurveAffine>(
+    pk: &ProvingKey<C>,
+    circuit: usize,
+    advice: &[Polynomial<C::Scalar, LagrangeCoeff>],
+    instance: &[Polynomial<C::Scalar, LagrangeCoeff>],
+    challenges: &[C::Scalar],
+) -> Result<(), Error> {
+    let cs = &pk.vk.cs;
+    let n = 1 << pk.vk.domain.k();
+    let usable_rows = n - (cs.blinding_factors() + 1);
+    let fixed = &pk.fixed_values;
+
+    let fail = |constraint| Error::ConstraintNotSatisfied {
+        circuit,
+        constraint,
+    };
+
+    let eval = |expression: &Expression<C::Scalar>| {
+        evaluate(expression, n, 1, fixed, advice, instance, challenges)
+    };
+
+    for (gate_index, gate) in cs.gates.iter().enumerate() {
+        for (constraint_index, poly) in gate.polynomials().iter().enumerate() {
+            let values = eval(poly);
+            if let Some(row) = values[..usable_rows]
+                .iter()
+                .position(|value| *value != C::Scalar::zero())
+            {
+                return Err(fail(UnsatisfiedConstraint::Gate {
+                    gate_index,
+                    gate_name: gate.name(),
+                    constraint_index,
+                    constraint_name: gate.constraint_name(constraint_index),
+                    row,
+                }));
+            }
+        }
+    }
+
+    for (lookup_index, lookup) in cs.lookups.iter().enumerate() {
+        // Evaluates the tuple of expressions at every usable row.
+        let tuples = |expressions: &[Expression<C::Scalar>]| -> Vec<Vec<C::Scalar>> {
+            let columns: Vec<_> = expressions.iter().map(&eval).collect();
+            (0..usable_rows)
+                .map(|row| columns.iter().map(|column| column[row]).collect())
+                .collect()
+        };
+
+        let table: BTreeSet<_> = tuples(&lookup.table_expressions).into_iter().collect();
+        if let Some(row) = tuples(&lookup.input_expressions)
+            .iter()
+            .position(|input| !table.contains(input))
+        {
+            return Err(fail(UnsatisfiedConstraint::Lookup {
+                lookup_index,
+                name: lookup.name,
+                row,
+            }));
+        }
+    }
+
+    check_permutation(pk, advice, instance, usable_rows).map_err(fail)
+}
+
+/// Checks that the columns the custom arguments of `pk` compute from the witness of a
+/// single circuit satisfy the constraints of the arguments, returning the first
+/// violation found.
+pub(super) fn check_custom_arguments<C: CurveAffine>(
+    pk: &ProvingKey<C>,
+    circuit: usize,
+    advice: &[Polynomial<C::Scalar, LagrangeCoeff>],
+    instance: &[Polynomial<C::Scalar, LagrangeCoeff>],
+    challenges: &[C::Scalar],
+    (theta, beta, gamma): (C::Scalar, C::Scalar, C::Scalar),
+) -> Result<(), Error> {
+    let cs = &pk.vk.cs;
+    let n = 1 << pk.vk.domain.k();
+    let fixed = &pk.fixed_values;
+
+    let eval = |expression: &Expression<C::Scalar>| {
+        evaluate(expression, n, 1, fixed, advice, instance, challenges)
+    };
+
+    for (argument_index, argument) in cs.custom_arguments.iter().enumerate() {
+        // Arguments committed in an advice phase do not see `theta`, `beta` and `gamma`.
+        let (theta, beta, gamma) = match argument.phase {
+            Some(_) => (C::Scalar::zero(), C::Scalar::zero(), C::Scalar::zero()),
+            None => (theta, beta, gamma),
+        };
+        let failures = argument.check(
+            n,
+            cs.blinding_factors(),
+            &eval,
+            challenges,
+            theta,
+            beta,
+            gamma,
+        )?;
+        if let Some(&(constraint_index, row)) = failures.first() {
+            return Err(Error::ConstraintNotSatisfied {
+                circuit,
+                constraint: UnsatisfiedConstraint::CustomArgument {
+                    argument_index,
+                    name: argument.name,
+                    constraint_index,
+                    row,
+                },
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Follows the permutation encoded in the proving key and checks that every cell holds
+/// the same value as the cell it is mapped to.
+fn check_permutation<C: CurveAffine>(
+    pk: &ProvingKey<C>,
+    advice: &[Polynomial<C::Scalar, LagrangeCoeff>],
+    instance: &[Polynomial<C::Scalar, LagrangeCoeff>],
+    usable_rows: usize,
+) -> Result<(), UnsatisfiedConstraint> {
+    let columns = &pk.vk.cs.permutation.columns;
+    let permutations = &pk.permutation.permutations;
+    let fixed: &[_] = &pk.fixed_values;
+    let omega = pk.vk.domain.get_omega();
+
+    // Cell (i, j) is labelled \delta^i \omega^j, and the permutation polynomial of
+    // column i holds at row j the label of the cell that (i, j) is mapped to.
+    let mut cells = BTreeMap::new();
+    let mut delta = C::Scalar::one();
+    for column_index in 0..columns.len() {
+        let mut label = delta;
+        for row in 0..usable_rows {
+            cells.insert(label, (column_index, row));
+            label *= omega;
+        }
+        delta *= C::Scalar::DELTA;
+    }
+
+    let value = |column_index: usize, row: usize| {
+        let column = columns[column_index];
+        let values = match column.column_type() {
+            Any::Advice(_) => advice,
+            Any::Fixed => fixed,
+            Any::Instance => instance,
+        };
+        values[column.index()][row]
+    };
+
+    for (column_index, permutation) in permutations.iter().enumerate() {
+        for (row, label) in permutation.iter().take(usable_rows).enumerate() {
+            let (target_column, target_row) =
+                *cells
+                    .get(label)
+                    .ok_or(UnsatisfiedConstraint::UnknownPermutationTarget {
+                        column: columns[column_index],
+                        row,
+                    })?;
+            if value(column_index, row) != value(target_column, target_row) {
+                return Err(UnsatisfiedConstraint::Permutation {
+                    column: columns[column_index],
+                    row,
+                    target_column: columns[target_column],
+                    target_row,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
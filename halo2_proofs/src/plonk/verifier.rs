@@ -21,6 +21,12 @@ mod batch;
 #[cfg(feature = "batch")]
 pub use batch::BatchVerifier;
 
+mod diagnostic;
+pub use diagnostic::{
+    verify_proof_diagnostic, Identity, QuotientTerm, VerificationFailure, VerificationReport,
+    VerificationStage,
+};
+
 use crate::poly::commitment::ParamsVerifier;
 
 /// Returns a boolean indicating whether or not the proof is valid
@@ -37,6 +43,25 @@ pub fn verify_proof<
     strategy: Strategy,
     instances: &[&[&[Scheme::Scalar]]],
     transcript: &mut T,
+) -> Result<Strategy::Output, Error> {
+    verify_proof_inner(params, vk, strategy, instances, transcript, None)
+}
+
+/// Verifies a proof, recording the progress of the verifier into `trace` if provided.
+fn verify_proof_inner<
+    'params,
+    Scheme: CommitmentScheme,
+    V: Verifier<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    T: TranscriptRead<Scheme::Curve, E>,
+    Strategy: VerificationStrategy<'params, Scheme, V>,
+>(
+    params: &'params Scheme::ParamsVerifier,
+    vk: &VerifyingKey<Scheme::Curve>,
+    strategy: Strategy,
+    instances: &[&[&[Scheme::Scalar]]],
+    transcript: &mut T,
+    mut trace: Option<&mut diagnostic::Trace<Scheme::Scalar>>,
 ) -> Result<Strategy::Output, Error> {
     // Check that instances matches the expected number of instance columns
     for instances in instances.iter() {
@@ -90,14 +115,24 @@ pub fn verify_proof<
         }
     }
 
+    diagnostic::enter(&mut trace, VerificationStage::AdviceCommitments);
+
     // Hash the prover's advice commitments into the transcript and squeeze challenges
-    let (advice_commitments, challenges) = {
+    let (advice_commitments, challenges, custom_arguments_committed) = {
         let mut advice_commitments =
             vec![vec![Scheme::Curve::default(); vk.cs.num_advice_columns]; num_proofs];
         let mut challenges = vec![Scheme::Scalar::zero(); vk.cs.num_challenges];
+        // The column commitments of the custom arguments that are committed in an
+        // advice phase.
+        let mut custom_arguments_committed: Vec<Vec<Option<_>>> = (0..num_proofs)
+            .map(|_| vk.cs.custom_arguments.iter().map(|_| None).collect())
+            .collect();
 
         for current_phase in vk.cs.phases() {
-            for advice_commitments in advice_commitments.iter_mut() {
+            for (advice_commitments, custom_arguments_committed) in advice_commitments
+                .iter_mut()
+                .zip(custom_arguments_committed.iter_mut())
+            {
                 for (phase, commitment) in vk
                     .cs
                     .advice_column_phase
@@ -108,6 +143,16 @@ pub fn verify_proof<
                         *commitment = transcript.read_point()?;
                     }
                 }
+                for (argument, committed) in vk
+                    .cs
+                    .custom_arguments
+                    .iter()
+                    .zip(custom_arguments_committed.iter_mut())
+                {
+                    if argument.phase == Some(current_phase) {
+                        *committed = Some(argument.read_commitments(transcript)?);
+                    }
+                }
             }
             for (phase, challenge) in vk.cs.challenge_phase.iter().zip(challenges.iter_mut()) {
                 if current_phase == *phase {
@@ -116,12 +161,14 @@ pub fn verify_proof<
             }
         }
 
-        (advice_commitments, challenges)
+        (advice_commitments, challenges, custom_arguments_committed)
     };
 
     // Sample theta challenge for keeping lookup columns linearly independent
     let theta: ChallengeTheta<_> = transcript.squeeze_challenge_scalar();
 
+    diagnostic::enter(&mut trace, VerificationStage::LookupPermutedCommitments);
+
     let lookups_permuted = (0..num_proofs)
         .map(|_| -> Result<Vec<_>, _> {
             // Hash each lookup permuted commitment
@@ -139,6 +186,8 @@ pub fn verify_proof<
     // Sample gamma challenge
     let gamma: ChallengeGamma<_> = transcript.squeeze_challenge_scalar();
 
+    diagnostic::enter(&mut trace, VerificationStage::PermutationCommitments);
+
     let permutations_committed = (0..num_proofs)
         .map(|_| {
             // Hash each permutation product commitment
@@ -146,6 +195,8 @@ pub fn verify_proof<
         })
         .collect::<Result<Vec<_>, _>>()?;
 
+    diagnostic::enter(&mut trace, VerificationStage::LookupProductCommitments);
+
     let lookups_committed = lookups_permuted
         .into_iter()
         .map(|lookups| {
@@ -157,6 +208,27 @@ pub fn verify_proof<
         })
         .collect::<Result<Vec<_>, _>>()?;
 
+    diagnostic::enter(&mut trace, VerificationStage::CustomArgumentCommitments);
+
+    let custom_arguments_committed = custom_arguments_committed
+        .into_iter()
+        .map(|committed| -> Result<Vec<_>, _> {
+            // Hash the column commitments of each custom argument that is not committed
+            // in an advice phase
+            vk.cs
+                .custom_arguments
+                .iter()
+                .zip(committed)
+                .map(|(argument, committed)| match committed {
+                    Some(committed) => Ok(committed),
+                    None => argument.read_commitments(transcript),
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    diagnostic::enter(&mut trace, VerificationStage::VanishingCommitments);
+
     let vanishing = vanishing::Argument::read_commitments_before_y(transcript)?;
 
     // Sample y challenge, which keeps the gates linearly independent.
@@ -167,6 +239,13 @@ pub fn verify_proof<
     // Sample x challenge, which is used to ensure the circuit is
     // satisfied with high probability.
     let x: ChallengeX<_> = transcript.squeeze_challenge_scalar();
+
+    diagnostic::enter(&mut trace, VerificationStage::Evaluations);
+    if let Some(trace) = trace.as_mut() {
+        trace.x = Some(*x);
+        trace.y = Some(*y);
+    }
+
     let instance_evals = if V::QUERY_INSTANCE {
         (0..num_proofs)
             .map(|_| -> Result<Vec<_>, _> {
@@ -239,6 +318,17 @@ pub fn verify_proof<
         })
         .collect::<Result<Vec<_>, _>>()?;
 
+    let custom_arguments_evaluated = custom_arguments_committed
+        .into_iter()
+        .map(|custom_arguments| -> Result<Vec<_>, _> {
+            custom_arguments
+                .into_iter()
+                .zip(vk.cs.custom_arguments.iter())
+                .map(|(custom_argument, argument)| custom_argument.evaluate(argument, transcript))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
     // This check ensures the circuit is satisfied so long as the polynomial
     // commitments open to the correct values.
     let vanishing = {
@@ -262,65 +352,85 @@ pub fn verify_proof<
             .zip(instance_evals.iter())
             .zip(permutations_evaluated.iter())
             .zip(lookups_evaluated.iter())
-            .flat_map(|(((advice_evals, instance_evals), permutation), lookups)| {
-                let challenges = &challenges;
-                let fixed_evals = &fixed_evals;
-                std::iter::empty()
-                    // Evaluate the circuit using the custom gates provided
-                    .chain(vk.cs.gates.iter().flat_map(move |gate| {
-                        gate.polynomials().iter().map(move |poly| {
-                            poly.evaluate(
-                                &|scalar| scalar,
-                                &|_| panic!("virtual selectors are removed during optimization"),
-                                &|query| fixed_evals[query.index],
-                                &|query| advice_evals[query.index],
-                                &|query| instance_evals[query.index],
-                                &|challenge| challenges[challenge.index()],
-                                &|a| -a,
-                                &|a, b| a + &b,
-                                &|a, b| a * &b,
-                                &|a, scalar| a * &scalar,
-                            )
-                        })
-                    }))
-                    .chain(permutation.expressions(
-                        vk,
-                        &vk.cs.permutation,
-                        &permutations_common,
-                        advice_evals,
-                        fixed_evals,
-                        instance_evals,
-                        l_0,
-                        l_last,
-                        l_blind,
-                        beta,
-                        gamma,
-                        x,
-                    ))
-                    .chain(
-                        lookups
-                            .iter()
-                            .zip(vk.cs.lookups.iter())
-                            .flat_map(move |(p, argument)| {
-                                p.expressions(
-                                    l_0,
-                                    l_last,
-                                    l_blind,
-                                    argument,
-                                    theta,
-                                    beta,
-                                    gamma,
-                                    advice_evals,
-                                    fixed_evals,
-                                    instance_evals,
-                                    challenges,
-                                )
-                            })
-                            .into_iter(),
-                    )
-            });
-
-        vanishing.verify(params, expressions, y, xn)
+            .zip(custom_arguments_evaluated.iter())
+            .flat_map(
+                |((((advice_evals, instance_evals), permutation), lookups), custom_arguments)| {
+                    let challenges = &challenges;
+                    let fixed_evals = &fixed_evals;
+                    std::iter::empty()
+                        // Evaluate the circuit using the custom gates provided
+                        .chain(vk.expression_dag().evaluate(
+                            &|query| fixed_evals[query.index],
+                            &|query| advice_evals[query.index],
+                            &|query| instance_evals[query.index],
+                            &|challenge| challenges[challenge.index()],
+                        ))
+                        .chain(permutation.expressions(
+                            vk,
+                            &vk.cs.permutation,
+                            &permutations_common,
+                            advice_evals,
+                            fixed_evals,
+                            instance_evals,
+                            l_0,
+                            l_last,
+                            l_blind,
+                            beta,
+                            gamma,
+                            x,
+                        ))
+                        .chain(
+                            lookups
+                                .iter()
+                                .zip(vk.cs.lookups.iter())
+                                .flat_map(move |(p, argument)| {
+                                    p.expressions(
+                                        l_0,
+                                        l_last,
+                                        l_blind,
+                                        argument,
+                                        theta,
+                                        beta,
+                                        gamma,
+                                        advice_evals,
+                                        fixed_evals,
+                                        instance_evals,
+                                        challenges,
+                                    )
+                                })
+                                .into_iter(),
+                        )
+                        .chain(
+                            custom_arguments
+                                .iter()
+                                .zip(vk.cs.custom_arguments.iter())
+                                .flat_map(move |(p, argument)| {
+                                    p.expressions(
+                                        l_0,
+                                        l_last,
+                                        l_blind,
+                                        argument,
+                                        theta,
+                                        beta,
+                                        gamma,
+                                        advice_evals,
+                                        fixed_evals,
+                                        instance_evals,
+                                        challenges,
+                                    )
+                                }),
+                        )
+                },
+            );
+
+        match trace.as_mut() {
+            Some(trace) => {
+                trace.xn = Some(xn);
+                trace.expressions = expressions.collect();
+                vanishing.verify(params, trace.expressions.iter().cloned(), y, xn)
+            }
+            None => vanishing.verify(params, expressions, y, xn),
+        }
     };
 
     let queries = instance_commitments
@@ -330,13 +440,20 @@ pub fn verify_proof<
         .zip(advice_evals.iter())
         .zip(permutations_evaluated.iter())
         .zip(lookups_evaluated.iter())
+        .zip(custom_arguments_evaluated.iter())
         .flat_map(
             |(
                 (
-                    (((instance_commitments, instance_evals), advice_commitments), advice_evals),
-                    permutation,
+                    (
+                        (
+                            ((instance_commitments, instance_evals), advice_commitments),
+                            advice_evals,
+                        ),
+                        permutation,
+                    ),
+                    lookups,
                 ),
-                lookups,
+                custom_arguments,
             )| {
                 iter::empty()
                     .chain(
@@ -369,6 +486,12 @@ pub fn verify_proof<
                             .flat_map(move |p| p.queries(vk, x))
                             .into_iter(),
                     )
+                    .chain(
+                        custom_arguments
+                            .iter()
+                            .zip(vk.cs.custom_arguments.iter())
+                            .flat_map(move |(p, argument)| p.queries(argument, vk, x)),
+                    )
             },
         )
         .chain(
@@ -390,6 +513,8 @@ pub fn verify_proof<
     // We are now convinced the circuit is satisfied so long as the
     // polynomial commitments open to the correct values.
 
+    diagnostic::enter(&mut trace, VerificationStage::Opening);
+
     let verifier = V::new(params);
     strategy.process(|msm| {
         verifier
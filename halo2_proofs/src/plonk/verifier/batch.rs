@@ -18,7 +18,7 @@ use crate::{
             strategy::GuardIPA,
         },
     },
-    transcript::{Blake2bRead, TranscriptReadBuffer},
+    transcript::Blake2bRead,
 };
 
 /// A proof verification strategy that returns the proof's MSM.
@@ -0,0 +1,480 @@
+use std::fmt;
+
+use ff::Field;
+
+use super::verify_proof_inner;
+use crate::{
+    arithmetic::FieldExt,
+    plonk::{ConstraintSystem, Error, VerifyingKey},
+    poly::{
+        commitment::{CommitmentScheme, Verifier},
+        VerificationStrategy,
+    },
+    transcript::{EncodedChallenge, TranscriptRead},
+};
+
+/// The step of [`verify_proof`] that was being performed when a proof was rejected.
+///
+/// [`verify_proof`]: crate::plonk::verify_proof
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationStage {
+    /// Checking the instance columns against the verifying key.
+    Instances,
+    /// Reading the advice commitments of every phase, along with the column commitments
+    /// of the custom arguments committed in a phase.
+    AdviceCommitments,
+    /// Reading the permuted input and table commitments of the lookup arguments.
+    LookupPermutedCommitments,
+    /// Reading the permutation product commitments.
+    PermutationCommitments,
+    /// Reading the product commitments of the lookup arguments.
+    LookupProductCommitments,
+    /// Reading the column commitments of the custom arguments that are committed after
+    /// the products.
+    CustomArgumentCommitments,
+    /// Reading the random polynomial and quotient commitments of the vanishing argument.
+    VanishingCommitments,
+    /// Reading the evaluations of the committed polynomials at `x`.
+    Evaluations,
+    /// Checking the multi-opening argument.
+    Opening,
+}
+
+/// An identity whose evaluation at `x` contributes to the expected quotient `h(x)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Identity {
+    /// A constraint of a custom gate.
+    Gate {
+        /// The index of the gate.
+        gate_index: usize,
+        /// The name of the gate.
+        gate_name: &'static str,
+        /// The index of the constraint within the gate.
+        constraint_index: usize,
+        /// The name of the constraint.
+        constraint_name: &'static str,
+    },
+    /// `l_0(X) * (1 - z_0(X)) = 0` for the first permutation set.
+    PermutationFirstSet,
+    /// `l_last(X) * (z_l(X)^2 - z_l(X)) = 0` for the last permutation set.
+    PermutationLastSet,
+    /// `l_0(X) * (z_i(X) - z_{i-1}(\omega^(last) X)) = 0` linking a set to the previous one.
+    PermutationSetLink {
+        /// The index of the permutation set.
+        set: usize,
+    },
+    /// The grand product identity of a permutation set.
+    PermutationProduct {
+        /// The index of the permutation set.
+        set: usize,
+    },
+    /// `l_0(X) * (1 - z(X)) = 0` for a lookup argument.
+    LookupFirstProduct {
+        /// The index of the lookup argument.
+        lookup_index: usize,
+        /// The name of the lookup argument.
+        name: &'static str,
+    },
+    /// `l_last(X) * (z(X)^2 - z(X)) = 0` for a lookup argument.
+    LookupLastProduct {
+        /// The index of the lookup argument.
+        lookup_index: usize,
+        /// The name of the lookup argument.
+        name: &'static str,
+    },
+    /// The grand product identity of a lookup argument.
+    LookupProduct {
+        /// The index of the lookup argument.
+        lookup_index: usize,
+        /// The name of the lookup argument.
+        name: &'static str,
+    },
+    /// `l_0(X) * (a'(X) - s'(X)) = 0` for a lookup argument.
+    LookupFirstPermuted {
+        /// The index of the lookup argument.
+        lookup_index: usize,
+        /// The name of the lookup argument.
+        name: &'static str,
+    },
+    /// `(a'(X) - s'(X)) * (a'(X) - a'(\omega^{-1} X)) = 0` on the active rows of a lookup
+    /// argument.
+    LookupPermutedOrder {
+        /// The index of the lookup argument.
+        lookup_index: usize,
+        /// The name of the lookup argument.
+        name: &'static str,
+    },
+    /// A constraint of a custom argument.
+    CustomArgument {
+        /// The index of the custom argument.
+        argument_index: usize,
+        /// The name of the custom argument.
+        name: &'static str,
+        /// The index of the constraint within the argument.
+        constraint_index: usize,
+    },
+}
+
+impl fmt::Display for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Identity::Gate {
+                gate_index,
+                gate_name,
+                constraint_index,
+                constraint_name,
+            } => {
+                write!(f, "Constraint {}", constraint_index)?;
+                if !constraint_name.is_empty() {
+                    write!(f, " ('{}')", constraint_name)?;
+                }
+                write!(f, " in gate {} ('{}')", gate_index, gate_name)
+            }
+            Identity::PermutationFirstSet => write!(f, "Permutation first set"),
+            Identity::PermutationLastSet => write!(f, "Permutation last set"),
+            Identity::PermutationSetLink { set } => write!(f, "Permutation set {} link", set),
+            Identity::PermutationProduct { set } => write!(f, "Permutation set {} product", set),
+            Identity::LookupFirstProduct { lookup_index, name } => {
+                write!(f, "Lookup {} ('{}') first product", lookup_index, name)
+            }
+            Identity::LookupLastProduct { lookup_index, name } => {
+                write!(f, "Lookup {} ('{}') last product", lookup_index, name)
+            }
+            Identity::LookupProduct { lookup_index, name } => {
+                write!(f, "Lookup {} ('{}') product", lookup_index, name)
+            }
+            Identity::LookupFirstPermuted { lookup_index, name } => {
+                write!(f, "Lookup {} ('{}') first permuted row", lookup_index, name)
+            }
+            Identity::LookupPermutedOrder { lookup_index, name } => {
+                write!(f, "Lookup {} ('{}') permuted order", lookup_index, name)
+            }
+            Identity::CustomArgument {
+                argument_index,
+                name,
+                constraint_index,
+            } => write!(
+                f,
+                "Constraint {} in custom argument {} ('{}')",
+                constraint_index, argument_index, name
+            ),
+        }
+    }
+}
+
+impl Identity {
+    /// Lists the identities of a single proof in the order in which the verifier folds
+    /// them into the expected quotient.
+    pub(crate) fn list<F: Field>(cs: &ConstraintSystem<F>, cs_degree: usize) -> Vec<Self> {
+        let mut identities = vec![];
+
+        for (gate_index, gate) in cs.gates.iter().enumerate() {
+            for constraint_index in 0..gate.polynomials().len() {
+                identities.push(Identity::Gate {
+                    gate_index,
+                    gate_name: gate.name(),
+                    constraint_index,
+                    constraint_name: gate.constraint_name(constraint_index),
+                });
+            }
+        }
+
+        let sets = cs.permutation.columns.chunks(cs_degree - 2).len();
+        if sets > 0 {
+            identities.push(Identity::PermutationFirstSet);
+            identities.push(Identity::PermutationLastSet);
+            identities.extend((1..sets).map(|set| Identity::PermutationSetLink { set }));
+            identities.extend((0..sets).map(|set| Identity::PermutationProduct { set }));
+        }
+
+        for (lookup_index, lookup) in cs.lookups.iter().enumerate() {
+            let name = lookup.name;
+            identities.extend([
+                Identity::LookupFirstProduct { lookup_index, name },
+                Identity::LookupLastProduct { lookup_index, name },
+                Identity::LookupProduct { lookup_index, name },
+                Identity::LookupFirstPermuted { lookup_index, name },
+                Identity::LookupPermutedOrder { lookup_index, name },
+            ]);
+        }
+
+        for (argument_index, argument) in cs.custom_arguments.iter().enumerate() {
+            let name = argument.name;
+            identities.extend((0..argument.constraints.len()).map(|constraint_index| {
+                Identity::CustomArgument {
+                    argument_index,
+                    name,
+                    constraint_index,
+                }
+            }));
+        }
+
+        identities
+    }
+}
+
+/// The evaluation of a single identity at `x`, as computed by the verifier.
+#[derive(Clone, Debug)]
+pub struct QuotientTerm<F> {
+    /// The index of the proof (in the `instances` slice) that this term belongs to.
+    pub proof_index: usize,
+    /// The identity being evaluated.
+    pub identity: Identity,
+    /// The evaluation of the identity at `x`.
+    pub value: F,
+    /// The contribution of this identity to the expected `h(x)`, i.e. `value` scaled by
+    /// its power of `y` and divided by `x^n - 1`.
+    ///
+    /// This is zero if `x^n = 1`, in which case the report has no expected `h(x)`.
+    pub contribution: F,
+}
+
+/// The reason why [`verify_proof_diagnostic`] rejected a proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationFailure {
+    /// The instance columns do not match the verifying key.
+    InvalidInstances,
+    /// An instance column is longer than the usable rows of the circuit.
+    InstanceTooLarge,
+    /// The proof could not be read from the transcript during the given stage.
+    Transcript(VerificationStage),
+    /// The multi-opening argument could not be processed.
+    Opening,
+    /// The final multi-opening check (MSM or pairing) failed.
+    ///
+    /// All the identity evaluations were read successfully, but the committed
+    /// polynomials do not open to them, or the quotient commitment does not open to the
+    /// expected `h(x)`. The latter means that at least one identity is not satisfied by
+    /// the witness. The two cases cannot be told apart, as they are checked by a
+    /// single opening argument.
+    Rejected,
+    /// Verification failed with an error that [`verify_proof`] is not expected to
+    /// return.
+    ///
+    /// [`verify_proof`]: crate::plonk::verify_proof
+    Unexpected,
+}
+
+impl fmt::Display for VerificationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationFailure::InvalidInstances => {
+                write!(f, "Provided instances do not match the circuit")
+            }
+            VerificationFailure::InstanceTooLarge => {
+                write!(f, "Instance vectors are larger than the circuit")
+            }
+            VerificationFailure::Transcript(stage) => {
+                write!(f, "Proof could not be read at stage {:?}", stage)
+            }
+            VerificationFailure::Opening => write!(f, "Multi-opening proof was invalid"),
+            VerificationFailure::Rejected => write!(
+                f,
+                "Quotient commitment does not open to the expected h(x), or an opening is invalid"
+            ),
+            VerificationFailure::Unexpected => write!(f, "Verification failed unexpectedly"),
+        }
+    }
+}
+
+/// The outcome of [`verify_proof_diagnostic`].
+#[derive(Clone, Debug)]
+pub struct VerificationReport<F> {
+    /// The `x` challenge, if verification got far enough to sample it.
+    pub x: Option<F>,
+    /// The `y` challenge, if verification got far enough to sample it.
+    pub y: Option<F>,
+    /// The expected evaluation of the quotient polynomial at `x`, if verification got
+    /// far enough to compute it and `x^n != 1`.
+    pub expected_h_eval: Option<F>,
+    /// The evaluations of all identities at `x`, in the order the verifier folds them.
+    pub terms: Vec<QuotientTerm<F>>,
+    /// Why the proof was rejected, or `None` if it was accepted.
+    pub failure: Option<VerificationFailure>,
+}
+
+impl<F: FieldExt> VerificationReport<F> {
+    /// Returns `true` if the proof was accepted.
+    ///
+    /// For verification strategies that defer the final check (such as accumulator
+    /// strategies), this only means that the proof was accepted so far.
+    pub fn is_valid(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+impl<F: FieldExt> fmt::Display for VerificationReport<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.failure {
+            None => writeln!(f, "Proof accepted")?,
+            Some(failure) => writeln!(f, "Proof rejected: {}", failure)?,
+        }
+        if let Some(x) = self.x {
+            writeln!(f, "x = {:?}", x)?;
+        }
+        if let Some(y) = self.y {
+            writeln!(f, "y = {:?}", y)?;
+        }
+        if let Some(expected_h_eval) = self.expected_h_eval {
+            writeln!(f, "expected h(x) = {:?}", expected_h_eval)?;
+        }
+        for term in self.terms.iter() {
+            writeln!(
+                f,
+                "- proof {}: {} = {:?} (contributes {:?})",
+                term.proof_index, term.identity, term.value, term.contribution
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// State recorded by [`verify_proof_inner`] while it processes a proof.
+#[derive(Debug)]
+pub(super) struct Trace<F> {
+    pub(super) stage: VerificationStage,
+    pub(super) x: Option<F>,
+    pub(super) y: Option<F>,
+    pub(super) xn: Option<F>,
+    pub(super) expressions: Vec<F>,
+}
+
+impl<F> Default for Trace<F> {
+    fn default() -> Self {
+        Trace {
+            stage: VerificationStage::Instances,
+            x: None,
+            y: None,
+            xn: None,
+            expressions: vec![],
+        }
+    }
+}
+
+/// Records that verification has reached `stage`.
+pub(super) fn enter<F>(trace: &mut Option<&mut Trace<F>>, stage: VerificationStage) {
+    if let Some(trace) = trace {
+        trace.stage = stage;
+    }
+}
+
+/// Verifies a proof like [`verify_proof`], and additionally dumps the quotient terms:
+/// the evaluation of every identity at `x` and its contribution to the expected
+/// `h(x)`, along with the stage at which the proof was rejected.
+///
+/// This does not report which identity failed at `x`. The evaluations of the identities
+/// at `x` are non-zero even for a valid witness, and the quotient commitment only binds
+/// their random linear combination, which is checked together with every other opening.
+/// When the final check fails with [`VerificationFailure::Rejected`], run [`MockProver`]
+/// on the same witness to pinpoint the failing gate and row.
+///
+/// This is considerably slower than [`verify_proof`] and is meant for debugging only.
+///
+/// [`verify_proof`]: crate::plonk::verify_proof
+/// [`MockProver`]: crate::dev::MockProver
+pub fn verify_proof_diagnostic<
+    'params,
+    Scheme: CommitmentScheme,
+    V: Verifier<'params, Scheme>,
+    E: EncodedChallenge<Scheme::Curve>,
+    T: TranscriptRead<Scheme::Curve, E>,
+    Strategy: VerificationStrategy<'params, Scheme, V>,
+>(
+    params: &'params Scheme::ParamsVerifier,
+    vk: &VerifyingKey<Scheme::Curve>,
+    strategy: Strategy,
+    instances: &[&[&[Scheme::Scalar]]],
+    transcript: &mut T,
+) -> (
+    Result<Strategy::Output, Error>,
+    VerificationReport<Scheme::Scalar>,
+) {
+    let mut trace = Trace::default();
+    let result = verify_proof_inner(
+        params,
+        vk,
+        strategy,
+        instances,
+        transcript,
+        Some(&mut trace),
+    );
+
+    let failure = match &result {
+        Ok(_) => None,
+        Err(Error::InvalidInstances) => Some(VerificationFailure::InvalidInstances),
+        Err(Error::InstanceTooLarge) => Some(VerificationFailure::InstanceTooLarge),
+        Err(Error::Transcript(_)) => Some(VerificationFailure::Transcript(trace.stage)),
+        Err(Error::Opening) => Some(VerificationFailure::Opening),
+        Err(Error::ConstraintSystemFailure) => Some(VerificationFailure::Rejected),
+        Err(
+            Error::Synthesis
+            | Error::BoundsFailure
+            | Error::InvalidRange(..)
+            | Error::NotEnoughRowsAvailable { .. }
+            | Error::NotEnoughColumnsForConstants
+            | Error::ColumnNotInPermutation(_)
+            | Error::ConstraintNotSatisfied { .. }
+            | Error::InstanceRowConflict { .. }
+            | Error::InvalidTaggedTable(_),
+        ) => Some(VerificationFailure::Unexpected),
+    };
+
+    let identities = Identity::list(&vk.cs, vk.cs_degree);
+    let (terms, expected_h_eval) = match (trace.y, trace.xn) {
+        // A circuit without identities folds no expressions, and has no terms to report.
+        (Some(y), Some(xn)) if !trace.expressions.is_empty() => {
+            // Each proof folds the same identities in the same order, so any other number
+            // of expressions would attribute the terms to the wrong identities.
+            assert_eq!(
+                trace.expressions.len(),
+                instances.len() * identities.len(),
+                "the verifier folded an unexpected number of expressions"
+            );
+
+            // x^n = 1 only happens with negligible probability, but the report must not
+            // panic on a malformed proof.
+            let vanishing_inv: Option<Scheme::Scalar> =
+                (xn - Scheme::Scalar::one()).invert().into();
+            // The verifier folds the expressions with Horner's rule, so the i-th of N
+            // expressions is scaled by y^(N - 1 - i).
+            let mut scale = vanishing_inv.unwrap_or_else(Scheme::Scalar::zero);
+            let mut terms = trace
+                .expressions
+                .iter()
+                .rev()
+                .enumerate()
+                .map(|(rev_index, value)| {
+                    let index = trace.expressions.len() - 1 - rev_index;
+                    let term = QuotientTerm {
+                        proof_index: index / identities.len(),
+                        identity: identities[index % identities.len()].clone(),
+                        value: *value,
+                        contribution: *value * scale,
+                    };
+                    scale *= y;
+                    term
+                })
+                .collect::<Vec<_>>();
+            terms.reverse();
+
+            let expected_h_eval = vanishing_inv.map(|_| {
+                terms
+                    .iter()
+                    .fold(Scheme::Scalar::zero(), |acc, term| acc + term.contribution)
+            });
+            (terms, expected_h_eval)
+        }
+        _ => (vec![], None),
+    };
+
+    (
+        result,
+        VerificationReport {
+            x: trace.x,
+            y: trace.y,
+            expected_h_eval,
+            terms,
+            failure,
+        },
+    )
+}
@@ -316,7 +316,6 @@ mod test {
             Blake2bRead, Blake2bWrite, Challenge255, Transcript, TranscriptRead, TranscriptWrite,
         };
 
-        use crate::transcript::TranscriptReadBuffer;
         use crate::transcript::TranscriptWriterBuffer;
 
         let rng = OsRng;
@@ -13,9 +13,9 @@ mod test {
     };
     use crate::poly::{Coeff, Polynomial};
     use crate::transcript::{
-        self, Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge, Keccak256Read,
-        Keccak256Write, TranscriptRead, TranscriptReadBuffer, TranscriptWrite,
-        TranscriptWriterBuffer,
+        self, Blake2bRead, Blake2bWrite, Challenge128, Challenge255, EncodedChallenge,
+        Keccak256Read, Keccak256Write, PoseidonRead, PoseidonWrite, TranscriptRead,
+        TranscriptReadBuffer, TranscriptWrite, TranscriptWriterBuffer,
     };
     use ff::Field;
     use group::{Curve, Group};
@@ -97,6 +97,53 @@ mod test {
         >(verifier_params, &proof[..], true);
     }
 
+    #[test]
+    fn test_roundtrip_ipa_challenge128() {
+        use crate::poly::ipa::commitment::{IPACommitmentScheme, ParamsIPA};
+        use crate::poly::ipa::multiopen::{ProverIPA, VerifierIPA};
+        use crate::poly::ipa::strategy::AccumulatorStrategy;
+        use halo2curves::pasta::EqAffine;
+
+        const K: u32 = 4;
+
+        let params = ParamsIPA::<EqAffine>::new(K);
+        let verifier_params = params.verifier_params();
+
+        let proof = create_proof::<
+            IPACommitmentScheme<EqAffine>,
+            ProverIPA<_>,
+            _,
+            Blake2bWrite<_, _, Challenge128<_>>,
+        >(&params);
+
+        for should_fail in [false, true] {
+            verify::<
+                IPACommitmentScheme<EqAffine>,
+                VerifierIPA<_>,
+                _,
+                Blake2bRead<_, _, Challenge128<_>>,
+                AccumulatorStrategy<_>,
+            >(verifier_params, &proof[..], should_fail);
+        }
+
+        let proof = create_proof::<
+            IPACommitmentScheme<EqAffine>,
+            ProverIPA<_>,
+            _,
+            PoseidonWrite<_, _, Challenge128<_>>,
+        >(&params);
+
+        for should_fail in [false, true] {
+            verify::<
+                IPACommitmentScheme<EqAffine>,
+                VerifierIPA<_>,
+                _,
+                PoseidonRead<_, _, Challenge128<_>>,
+                AccumulatorStrategy<_>,
+            >(verifier_params, &proof[..], should_fail);
+        }
+    }
+
     #[test]
     fn test_roundtrip_gwc() {
         use crate::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
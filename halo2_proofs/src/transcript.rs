@@ -2,8 +2,13 @@
 //! transcripts.
 
 use blake2b_simd::{Params as Blake2bParams, State as Blake2bState};
+use digest::{
+    generic_array::{typenum::U64, GenericArray},
+    Digest,
+};
 use group::ff::PrimeField;
-use sha3::{Digest, Keccak256};
+use sha2::Sha256;
+use sha3::Keccak256;
 use std::convert::TryInto;
 
 use halo2curves::{Coordinates, CurveAffine, FieldExt};
@@ -11,34 +16,25 @@ use halo2curves::{Coordinates, CurveAffine, FieldExt};
 use std::io::{self, Read, Write};
 use std::marker::PhantomData;
 
-mod poseidon;
+pub mod poseidon;
 pub use self::poseidon::{PoseidonRead, PoseidonWrite};
 
 /// Prefix to a prover's message soliciting a challenge
-const BLAKE2B_PREFIX_CHALLENGE: u8 = 0;
-
-/// Prefix to a prover's message containing a curve point
-const BLAKE2B_PREFIX_POINT: u8 = 1;
-
-/// Prefix to a prover's message containing a scalar
-const BLAKE2B_PREFIX_SCALAR: u8 = 2;
-
-/// Prefix to a prover's message soliciting a challenge
-const KECCAK256_PREFIX_CHALLENGE: u8 = 0;
+const PREFIX_CHALLENGE: u8 = 0;
 
 /// First prefix to a prover's message soliciting a challenge
 /// Not included in the growing state!
-const KECCAK256_PREFIX_CHALLENGE_LO: u8 = 10;
+const PREFIX_CHALLENGE_LO: u8 = 10;
 
 /// Second prefix to a prover's message soliciting a challenge
 /// Not included in the growing state!
-const KECCAK256_PREFIX_CHALLENGE_HI: u8 = 11;
+const PREFIX_CHALLENGE_HI: u8 = 11;
 
 /// Prefix to a prover's message containing a curve point
-const KECCAK256_PREFIX_POINT: u8 = 1;
+const PREFIX_POINT: u8 = 1;
 
 /// Prefix to a prover's message containing a scalar
-const KECCAK256_PREFIX_SCALAR: u8 = 2;
+const PREFIX_SCALAR: u8 = 2;
 
 /// Generic transcript view (from either the prover or verifier's perspective)
 pub trait Transcript<C: CurveAffine, E: EncodedChallenge<C>> {
@@ -101,84 +97,216 @@ pub trait TranscriptWriterBuffer<W: Write, C: CurveAffine, E: EncodedChallenge<C
     fn finalize(self) -> W;
 }
 
-/// We will replace BLAKE2b with an algebraic hash function in a later version.
+/// The hash function behind a [`DigestRead`] / [`DigestWrite`] transcript, along with
+/// how the transcript is personalized and how challenges are derived from its state.
+pub trait TranscriptHash {
+    /// The hash function that absorbs the transcript.
+    type Digest: Digest + Clone;
+
+    /// Personalization string separating this transcript from other uses of the
+    /// hash function.
+    const PERSONALIZATION: &'static [u8] = b"Halo2-Transcript";
+
+    /// Returns the initial state of the transcript.
+    ///
+    /// By default the personalization string is absorbed as the first message.
+    fn init() -> Self::Digest {
+        let mut state = Self::Digest::new();
+        state.update(Self::PERSONALIZATION);
+        state
+    }
+
+    /// Derives the input of a challenge from `state`, which has already absorbed
+    /// the challenge prefix.
+    ///
+    /// By default two copies of the state absorb distinct prefixes, and the first
+    /// 32 bytes of their digests are concatenated. This requires a digest of at
+    /// least 32 bytes.
+    fn squeeze(state: &Self::Digest) -> [u8; 64] {
+        let mut state_lo = state.clone();
+        let mut state_hi = state.clone();
+        state_lo.update(&[PREFIX_CHALLENGE_LO]);
+        state_hi.update(&[PREFIX_CHALLENGE_HI]);
+
+        let mut result = [0u8; 64];
+        result[..32].copy_from_slice(&state_lo.finalize()[..32]);
+        result[32..].copy_from_slice(&state_hi.finalize()[..32]);
+        result
+    }
+}
+
+/// BLAKE2b with a 64-byte output, exposed as a [`Digest`].
 #[derive(Debug, Clone)]
-pub struct Blake2bRead<R: Read, C: CurveAffine, E: EncodedChallenge<C>> {
+pub struct Blake2b {
+    params: Blake2bParams,
     state: Blake2bState,
-    reader: R,
-    _marker: PhantomData<(C, E)>,
 }
 
-/// Keccak256 hash function reader for EVM compatibility
+impl Blake2b {
+    /// Constructs a BLAKE2b hasher with the given personalization, which must be at
+    /// most 16 bytes long.
+    pub fn with_personal(personal: &[u8]) -> Self {
+        let mut params = Blake2bParams::new();
+        params.hash_length(64).personal(personal);
+        let state = params.to_state();
+        Blake2b { params, state }
+    }
+}
+
+impl Default for Blake2b {
+    fn default() -> Self {
+        Self::with_personal(&[])
+    }
+}
+
+impl digest::Update for Blake2b {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        self.state.update(data.as_ref());
+    }
+}
+
+impl digest::FixedOutput for Blake2b {
+    type OutputSize = U64;
+
+    fn finalize_into(self, out: &mut GenericArray<u8, U64>) {
+        out.copy_from_slice(self.state.finalize().as_bytes());
+    }
+
+    fn finalize_into_reset(&mut self, out: &mut GenericArray<u8, U64>) {
+        out.copy_from_slice(self.state.finalize().as_bytes());
+        digest::Reset::reset(self);
+    }
+}
+
+impl digest::Reset for Blake2b {
+    fn reset(&mut self) {
+        self.state = self.params.to_state();
+    }
+}
+
+/// BLAKE2b transcript hash. The personalization is set in the BLAKE2b parameter
+/// block, and the 64-byte digest is used directly as the challenge input.
+#[derive(Clone, Copy, Debug)]
+pub struct Blake2bHash;
+
+impl TranscriptHash for Blake2bHash {
+    type Digest = Blake2b;
+
+    fn init() -> Blake2b {
+        Blake2b::with_personal(Self::PERSONALIZATION)
+    }
+
+    fn squeeze(state: &Blake2b) -> [u8; 64] {
+        state.clone().finalize().as_slice().try_into().unwrap()
+    }
+}
+
+/// Keccak256 transcript hash, for EVM compatibility.
+#[derive(Clone, Copy, Debug)]
+pub struct Keccak256Hash;
+
+impl TranscriptHash for Keccak256Hash {
+    type Digest = Keccak256;
+}
+
+/// SHA-256 transcript hash, for verifiers that only have a SHA-256 precompile.
+#[derive(Clone, Copy, Debug)]
+pub struct Sha256Hash;
+
+impl TranscriptHash for Sha256Hash {
+    type Digest = Sha256;
+}
+
+/// Transcript reader over any [`TranscriptHash`].
 #[derive(Debug, Clone)]
-pub struct Keccak256Read<R: Read, C: CurveAffine, E: EncodedChallenge<C>> {
-    state: Keccak256,
+pub struct DigestRead<R: Read, C: CurveAffine, E: EncodedChallenge<C>, H: TranscriptHash> {
+    state: H::Digest,
     reader: R,
-    _marker: PhantomData<(C, E)>,
+    _marker: PhantomData<(C, E, H)>,
 }
 
-impl<R: Read, C: CurveAffine> TranscriptReadBuffer<R, C, Challenge255<C>>
-    for Blake2bRead<R, C, Challenge255<C>>
-{
-    /// Initialize a transcript given an input buffer.
-    fn init(reader: R) -> Self {
-        Blake2bRead {
-            state: Blake2bParams::new()
-                .hash_length(64)
-                .personal(b"Halo2-Transcript")
-                .to_state(),
-            reader,
-            _marker: PhantomData,
-        }
+/// Transcript writer over any [`TranscriptHash`].
+#[derive(Debug, Clone)]
+pub struct DigestWrite<W: Write, C: CurveAffine, E: EncodedChallenge<C>, H: TranscriptHash> {
+    state: H::Digest,
+    writer: W,
+    _marker: PhantomData<(C, E, H)>,
+}
+
+/// We will replace BLAKE2b with an algebraic hash function in a later version.
+pub type Blake2bRead<R, C, E> = DigestRead<R, C, E, Blake2bHash>;
+
+/// We will replace BLAKE2b with an algebraic hash function in a later version.
+pub type Blake2bWrite<W, C, E> = DigestWrite<W, C, E, Blake2bHash>;
+
+/// Keccak256 hash function reader for EVM compatibility
+pub type Keccak256Read<R, C, E> = DigestRead<R, C, E, Keccak256Hash>;
+
+/// Keccak256 hash function writer for EVM compatibility
+pub type Keccak256Write<W, C, E> = DigestWrite<W, C, E, Keccak256Hash>;
+
+/// SHA-256 hash function reader
+pub type Sha256Read<R, C, E> = DigestRead<R, C, E, Sha256Hash>;
+
+/// SHA-256 hash function writer
+pub type Sha256Write<W, C, E> = DigestWrite<W, C, E, Sha256Hash>;
+
+impl<R: Read, C: CurveAffine, H: TranscriptHash> DigestRead<R, C, Challenge255<C>, H> {
+    /// Initialize a transcript with [`Challenge255`] challenges given an input buffer.
+    ///
+    /// This is [`TranscriptReadBuffer::init`] with the challenge type fixed, so that
+    /// `Blake2bRead::init(proof)` does not need a type annotation. Transcripts with
+    /// other challenges are initialized through the trait.
+    pub fn init(reader: R) -> Self {
+        <Self as TranscriptReadBuffer<R, C, Challenge255<C>>>::init(reader)
     }
 }
 
-impl<R: Read, C: CurveAffine> TranscriptReadBuffer<R, C, Challenge255<C>>
-    for Keccak256Read<R, C, Challenge255<C>>
-{
-    /// Initialize a transcript given an input buffer.
-    fn init(reader: R) -> Self {
-        let mut state = Keccak256::new();
-        state.update(b"Halo2-Transcript");
-        Keccak256Read {
-            state,
-            reader,
-            _marker: PhantomData,
-        }
+impl<W: Write, C: CurveAffine, H: TranscriptHash> DigestWrite<W, C, Challenge255<C>, H> {
+    /// Initialize a transcript with [`Challenge255`] challenges given an output buffer.
+    ///
+    /// This is [`TranscriptWriterBuffer::init`] with the challenge type fixed, so that
+    /// `Blake2bWrite::init(vec![])` does not need a type annotation. Transcripts with
+    /// other challenges are initialized through the trait.
+    pub fn init(writer: W) -> Self {
+        <Self as TranscriptWriterBuffer<W, C, Challenge255<C>>>::init(writer)
     }
 }
 
-impl<R: Read, C: CurveAffine> TranscriptRead<C, Challenge255<C>>
-    for Blake2bRead<R, C, Challenge255<C>>
-{
-    fn read_point(&mut self) -> io::Result<C> {
-        let mut compressed = C::Repr::default();
-        self.reader.read_exact(compressed.as_mut())?;
-        let point: C = Option::from(C::from_bytes(&compressed)).ok_or_else(|| {
-            io::Error::new(io::ErrorKind::Other, "invalid point encoding in proof")
-        })?;
-        self.common_point(point)?;
+fn absorb_point<C: CurveAffine, D: Digest>(state: &mut D, point: C) -> io::Result<()> {
+    state.update(&[PREFIX_POINT]);
+    let coords: Coordinates<C> = Option::from(point.coordinates()).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            "cannot write points at infinity to the transcript",
+        )
+    })?;
+    state.update(coords.x().to_repr().as_ref());
+    state.update(coords.y().to_repr().as_ref());
 
-        Ok(point)
-    }
+    Ok(())
+}
 
-    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
-        let mut data = <C::Scalar as PrimeField>::Repr::default();
-        self.reader.read_exact(data.as_mut())?;
-        let scalar: C::Scalar = Option::from(C::Scalar::from_repr(data)).ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                "invalid field element encoding in proof",
-            )
-        })?;
-        self.common_scalar(scalar)?;
+fn absorb_scalar<C: CurveAffine, D: Digest>(state: &mut D, scalar: C::Scalar) {
+    state.update(&[PREFIX_SCALAR]);
+    state.update(scalar.to_repr().as_ref());
+}
 
-        Ok(scalar)
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>, H: TranscriptHash>
+    TranscriptReadBuffer<R, C, E> for DigestRead<R, C, E, H>
+{
+    /// Initialize a transcript given an input buffer.
+    fn init(reader: R) -> Self {
+        DigestRead {
+            state: H::init(),
+            reader,
+            _marker: PhantomData,
+        }
     }
 }
 
-impl<R: Read, C: CurveAffine> TranscriptRead<C, Challenge255<C>>
-    for Keccak256Read<R, C, Challenge255<C>>
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>, H: TranscriptHash>
+    TranscriptRead<C, E> for DigestRead<R, C, E, H>
 {
     fn read_point(&mut self) -> io::Result<C> {
         let mut compressed = C::Repr::default();
@@ -206,126 +334,32 @@ impl<R: Read, C: CurveAffine> TranscriptRead<C, Challenge255<C>>
     }
 }
 
-impl<R: Read, C: CurveAffine> Transcript<C, Challenge255<C>>
-    for Blake2bRead<R, C, Challenge255<C>>
-{
-    fn squeeze_challenge(&mut self) -> Challenge255<C> {
-        self.state.update(&[BLAKE2B_PREFIX_CHALLENGE]);
-        let hasher = self.state.clone();
-        let result: [u8; 64] = hasher.finalize().as_bytes().try_into().unwrap();
-        Challenge255::<C>::new(&result)
-    }
-
-    fn common_point(&mut self, point: C) -> io::Result<()> {
-        self.state.update(&[BLAKE2B_PREFIX_POINT]);
-        let coords: Coordinates<C> = Option::from(point.coordinates()).ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                "cannot write points at infinity to the transcript",
-            )
-        })?;
-        self.state.update(coords.x().to_repr().as_ref());
-        self.state.update(coords.y().to_repr().as_ref());
-
-        Ok(())
-    }
-
-    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
-        self.state.update(&[BLAKE2B_PREFIX_SCALAR]);
-        self.state.update(scalar.to_repr().as_ref());
-
-        Ok(())
-    }
-}
-
-impl<R: Read, C: CurveAffine> Transcript<C, Challenge255<C>>
-    for Keccak256Read<R, C, Challenge255<C>>
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>, H: TranscriptHash>
+    Transcript<C, E> for DigestRead<R, C, E, H>
 {
-    fn squeeze_challenge(&mut self) -> Challenge255<C> {
-        self.state.update(&[KECCAK256_PREFIX_CHALLENGE]);
-
-        let mut state_lo = self.state.clone();
-        let mut state_hi = self.state.clone();
-        state_lo.update(&[KECCAK256_PREFIX_CHALLENGE_LO]);
-        state_hi.update(&[KECCAK256_PREFIX_CHALLENGE_HI]);
-        let result_lo: [u8; 32] = state_lo.finalize().as_slice().try_into().unwrap();
-        let result_hi: [u8; 32] = state_hi.finalize().as_slice().try_into().unwrap();
-
-        let mut t = result_lo.to_vec();
-        t.extend_from_slice(&result_hi[..]);
-        let result: [u8; 64] = t.as_slice().try_into().unwrap();
-
-        Challenge255::<C>::new(&result)
+    fn squeeze_challenge(&mut self) -> E {
+        self.state.update(&[PREFIX_CHALLENGE]);
+        E::new(&H::squeeze(&self.state))
     }
 
     fn common_point(&mut self, point: C) -> io::Result<()> {
-        self.state.update(&[KECCAK256_PREFIX_POINT]);
-        let coords: Coordinates<C> = Option::from(point.coordinates()).ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                "cannot write points at infinity to the transcript",
-            )
-        })?;
-        self.state.update(coords.x().to_repr().as_ref());
-        self.state.update(coords.y().to_repr().as_ref());
-
-        Ok(())
+        absorb_point(&mut self.state, point)
     }
 
     fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
-        self.state.update(&[KECCAK256_PREFIX_SCALAR]);
-        self.state.update(scalar.to_repr().as_ref());
+        absorb_scalar::<C, _>(&mut self.state, scalar);
 
         Ok(())
     }
 }
 
-/// We will replace BLAKE2b with an algebraic hash function in a later version.
-#[derive(Debug, Clone)]
-pub struct Blake2bWrite<W: Write, C: CurveAffine, E: EncodedChallenge<C>> {
-    state: Blake2bState,
-    writer: W,
-    _marker: PhantomData<(C, E)>,
-}
-
-/// Keccak256 hash function writer for EVM compatibility
-#[derive(Debug, Clone)]
-pub struct Keccak256Write<W: Write, C: CurveAffine, E: EncodedChallenge<C>> {
-    state: Keccak256,
-    writer: W,
-    _marker: PhantomData<(C, E)>,
-}
-
-impl<W: Write, C: CurveAffine> TranscriptWriterBuffer<W, C, Challenge255<C>>
-    for Blake2bWrite<W, C, Challenge255<C>>
-{
-    /// Initialize a transcript given an output buffer.
-    fn init(writer: W) -> Self {
-        Blake2bWrite {
-            state: Blake2bParams::new()
-                .hash_length(64)
-                .personal(b"Halo2-Transcript")
-                .to_state(),
-            writer,
-            _marker: PhantomData,
-        }
-    }
-
-    fn finalize(self) -> W {
-        // TODO: handle outstanding scalars? see issue #138
-        self.writer
-    }
-}
-
-impl<W: Write, C: CurveAffine> TranscriptWriterBuffer<W, C, Challenge255<C>>
-    for Keccak256Write<W, C, Challenge255<C>>
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>, H: TranscriptHash>
+    TranscriptWriterBuffer<W, C, E> for DigestWrite<W, C, E, H>
 {
     /// Initialize a transcript given an output buffer.
     fn init(writer: W) -> Self {
-        let mut state = Keccak256::new();
-        state.update(b"Halo2-Transcript");
-        Keccak256Write {
-            state,
+        DigestWrite {
+            state: H::init(),
             writer,
             _marker: PhantomData,
         }
@@ -338,23 +372,8 @@ impl<W: Write, C: CurveAffine> TranscriptWriterBuffer<W, C, Challenge255<C>>
     }
 }
 
-impl<W: Write, C: CurveAffine> TranscriptWrite<C, Challenge255<C>>
-    for Blake2bWrite<W, C, Challenge255<C>>
-{
-    fn write_point(&mut self, point: C) -> io::Result<()> {
-        self.common_point(point)?;
-        let compressed = point.to_bytes();
-        self.writer.write_all(compressed.as_ref())
-    }
-    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
-        self.common_scalar(scalar)?;
-        let data = scalar.to_repr();
-        self.writer.write_all(data.as_ref())
-    }
-}
-
-impl<W: Write, C: CurveAffine> TranscriptWrite<C, Challenge255<C>>
-    for Keccak256Write<W, C, Challenge255<C>>
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>, H: TranscriptHash>
+    TranscriptWrite<C, E> for DigestWrite<W, C, E, H>
 {
     fn write_point(&mut self, point: C) -> io::Result<()> {
         self.common_point(point)?;
@@ -368,80 +387,24 @@ impl<W: Write, C: CurveAffine> TranscriptWrite<C, Challenge255<C>>
     }
 }
 
-impl<W: Write, C: CurveAffine> Transcript<C, Challenge255<C>>
-    for Blake2bWrite<W, C, Challenge255<C>>
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>, H: TranscriptHash>
+    Transcript<C, E> for DigestWrite<W, C, E, H>
 {
-    fn squeeze_challenge(&mut self) -> Challenge255<C> {
-        self.state.update(&[BLAKE2B_PREFIX_CHALLENGE]);
-        let hasher = self.state.clone();
-        let result: [u8; 64] = hasher.finalize().as_bytes().try_into().unwrap();
-        Challenge255::<C>::new(&result)
+    fn squeeze_challenge(&mut self) -> E {
+        self.state.update(&[PREFIX_CHALLENGE]);
+        E::new(&H::squeeze(&self.state))
     }
 
     fn common_point(&mut self, point: C) -> io::Result<()> {
-        self.state.update(&[BLAKE2B_PREFIX_POINT]);
-        let coords: Coordinates<C> = Option::from(point.coordinates()).ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                "cannot write points at infinity to the transcript",
-            )
-        })?;
-        self.state.update(coords.x().to_repr().as_ref());
-        self.state.update(coords.y().to_repr().as_ref());
-
-        Ok(())
-    }
-
-    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
-        self.state.update(&[BLAKE2B_PREFIX_SCALAR]);
-        self.state.update(scalar.to_repr().as_ref());
-
-        Ok(())
-    }
-}
-
-impl<W: Write, C: CurveAffine> Transcript<C, Challenge255<C>>
-    for Keccak256Write<W, C, Challenge255<C>>
-{
-    fn squeeze_challenge(&mut self) -> Challenge255<C> {
-        self.state.update(&[KECCAK256_PREFIX_CHALLENGE]);
-
-        let mut state_lo = self.state.clone();
-        let mut state_hi = self.state.clone();
-        state_lo.update(&[KECCAK256_PREFIX_CHALLENGE_LO]);
-        state_hi.update(&[KECCAK256_PREFIX_CHALLENGE_HI]);
-        let result_lo: [u8; 32] = state_lo.finalize().as_slice().try_into().unwrap();
-        let result_hi: [u8; 32] = state_hi.finalize().as_slice().try_into().unwrap();
-
-        let mut t = result_lo.to_vec();
-        t.extend_from_slice(&result_hi[..]);
-        let result: [u8; 64] = t.as_slice().try_into().unwrap();
-
-        Challenge255::<C>::new(&result)
-    }
-
-    fn common_point(&mut self, point: C) -> io::Result<()> {
-        self.state.update(&[KECCAK256_PREFIX_POINT]);
-        let coords: Coordinates<C> = Option::from(point.coordinates()).ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                "cannot write points at infinity to the transcript",
-            )
-        })?;
-        self.state.update(coords.x().to_repr().as_ref());
-        self.state.update(coords.y().to_repr().as_ref());
-
-        Ok(())
+        absorb_point(&mut self.state, point)
     }
 
     fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
-        self.state.update(&[KECCAK256_PREFIX_SCALAR]);
-        self.state.update(scalar.to_repr().as_ref());
+        absorb_scalar::<C, _>(&mut self.state, scalar);
 
         Ok(())
     }
 }
-
 /// The scalar representation of a verifier challenge.
 ///
 /// The `Type` type can be used to scope the challenge to a specific context, or
@@ -516,6 +479,71 @@ impl<C: CurveAffine> EncodedChallenge<C> for Challenge255<C> {
     }
 }
 
+/// A 128-bit challenge, mapped to a scalar through the curve endomorphism.
+///
+/// A point can be multiplied by the scalar inside a circuit using the endomorphism, with
+/// half as many doublings as for a full-width scalar. This is only available for the
+/// Pasta curves, where `Scalar::ZETA` is the eigenvalue of the endomorphism
+/// `(x, y) -> (Base::ZETA * x, y)`.
+///
+/// The challenge is the little-endian integer in the first 16 bytes of the
+/// challenge input. Endoscaling `P` by it starts from `Acc = [2](φ(P) + P)` and, for
+/// each pair of bits `(c_{2i}, c_{2i+1})` from `i = 63` down to `0`, computes
+/// `Acc = (Acc + S_i) + Acc`, where `S_i` is `φ(P)` if `c_{2i}` is set and `P`
+/// otherwise, negated if `c_{2i+1}` is set. This is the convention of the original
+/// `halo2` challenges; Algorithm 1 of the [Halo paper] instead selects the
+/// endomorphism with `c_{2i+1}` and negates when `c_{2i}` is unset, so the two map the
+/// same challenge to different scalars.
+///
+/// [Halo paper]: https://eprint.iacr.org/2019/1021
+#[derive(Copy, Clone, Debug)]
+pub struct Challenge128<C: CurveAffine>(u128, PhantomData<C>);
+
+impl<C: CurveAffine> std::ops::Deref for Challenge128<C> {
+    type Target = u128;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Maps a 128-bit challenge to the scalar that endoscaling by the challenge
+/// multiplies a point by, following the bit convention described on [`Challenge128`].
+fn endoscalar<F: FieldExt>(challenge: u128) -> F {
+    let mut acc = (F::ZETA + F::one()).double();
+    for i in (0..64).rev() {
+        let should_negate = (challenge >> ((i << 1) + 1)) & 1 == 1;
+        let should_endo = (challenge >> (i << 1)) & 1 == 1;
+
+        let q = if should_negate { -F::one() } else { F::one() };
+        let q = if should_endo { q * F::ZETA } else { q };
+        acc = acc + q + acc;
+    }
+    acc
+}
+
+macro_rules! impl_challenge128 {
+    ($curve:ty) => {
+        impl EncodedChallenge<$curve> for Challenge128<$curve> {
+            type Input = [u8; 64];
+
+            fn new(challenge_input: &[u8; 64]) -> Self {
+                Challenge128(
+                    u128::from_le_bytes(challenge_input[..16].try_into().unwrap()),
+                    PhantomData,
+                )
+            }
+
+            fn get_scalar(&self) -> <$curve as CurveAffine>::ScalarExt {
+                endoscalar(self.0)
+            }
+        }
+    };
+}
+
+impl_challenge128!(halo2curves::pasta::EpAffine);
+impl_challenge128!(halo2curves::pasta::EqAffine);
+
 pub(crate) fn read_n_points<C: CurveAffine, E: EncodedChallenge<C>, T: TranscriptRead<C, E>>(
     transcript: &mut T,
     n: usize,
@@ -529,3 +557,119 @@ pub(crate) fn read_n_scalars<C: CurveAffine, E: EncodedChallenge<C>, T: Transcri
 ) -> io::Result<Vec<C::Scalar>> {
     (0..n).map(|_| transcript.read_scalar()).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use group::{prime::PrimeCurveAffine, Curve};
+    use halo2curves::bn256::{Fr, G1Affine};
+
+    fn point() -> G1Affine {
+        (G1Affine::generator() * Fr::from(3)).to_affine()
+    }
+
+    /// Writes a point and a scalar, squeezing a challenge after each of them.
+    fn transcript_challenges<
+        T: TranscriptWriterBuffer<Vec<u8>, G1Affine, Challenge255<G1Affine>>,
+    >() -> (Vec<u8>, Vec<Fr>) {
+        let mut transcript = T::init(vec![]);
+        let mut challenges = vec![];
+        transcript.write_point(point()).unwrap();
+        challenges.push(transcript.squeeze_challenge().get_scalar());
+        transcript.write_scalar(Fr::from(5)).unwrap();
+        challenges.push(transcript.squeeze_challenge().get_scalar());
+        (transcript.finalize(), challenges)
+    }
+
+    /// Encodes challenges as the hex of their little-endian representation.
+    fn to_hex(challenges: &[Fr]) -> Vec<String> {
+        challenges
+            .iter()
+            .map(|challenge| {
+                challenge
+                    .to_repr()
+                    .as_ref()
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // The expected challenges below were produced by the transcripts as they were before
+    // they were built on `TranscriptHash`, and pin their byte-level behaviour.
+
+    #[test]
+    fn blake2b_test_vectors() {
+        let (_, challenges) = transcript_challenges::<Blake2bWrite<_, _, _>>();
+        assert_eq!(
+            to_hex(&challenges),
+            [
+                "9db44bc0b13cbf3d397e88ab2f0937aad800b66993ebf701481539d1a0090804",
+                "f081e5f58011ff02a9aba0e664a034de0165ee6ff9c15483f6af5baa68b6341c",
+            ]
+        );
+    }
+
+    #[test]
+    fn keccak256_test_vectors() {
+        let (_, challenges) = transcript_challenges::<Keccak256Write<_, _, _>>();
+        assert_eq!(
+            to_hex(&challenges),
+            [
+                "40b2283ef63035c4b8f20332a53d5b138b94e097eda4c78cd9006a3247422f0c",
+                "061a16176531a19ad178d94881cbf4511ab551ae1e1eba3f5da035cba5189a2d",
+            ]
+        );
+    }
+
+    #[test]
+    fn sha256_roundtrip() {
+        let (proof, challenges) = transcript_challenges::<Sha256Write<_, _, _>>();
+        assert_eq!(
+            to_hex(&challenges),
+            [
+                "41d4fe263a6a54781f9500d5fe34eefaaa6b2a8c3f7abe7b76a6209e42c44710",
+                "75c996d88572c28fc0c196be4ec8740b016c3165ab7b6ab31bfe3cf6bbbd4b1c",
+            ]
+        );
+
+        // The challenge type is inferred for `Challenge255` transcripts.
+        let mut transcript = Sha256Read::<_, G1Affine, _>::init(&proof[..]);
+        assert_eq!(transcript.read_point().unwrap(), point());
+        let first = transcript.squeeze_challenge();
+        assert_eq!(transcript.read_scalar().unwrap(), Fr::from(5));
+        let second = transcript.squeeze_challenge();
+        assert_eq!(vec![first.get_scalar(), second.get_scalar()], challenges);
+    }
+
+    #[test]
+    fn challenge128_endoscaling() {
+        use group::Group;
+        use halo2curves::pasta::{EpAffine, Fp, Fq};
+
+        let challenge = Challenge128::<EpAffine>::new(&[0x5a; 64]);
+        let p = EpAffine::generator();
+        let coords = p.coordinates().unwrap();
+        let endo_p = EpAffine::from_xy(*coords.x() * Fp::ZETA, *coords.y()).unwrap();
+
+        // Endoscaling as described on `Challenge128`, on points.
+        let mut acc = (p.to_curve() + endo_p).double();
+        for i in (0..64).rev() {
+            let base = if (*challenge >> (i << 1)) & 1 == 1 {
+                endo_p
+            } else {
+                p
+            };
+            let s = if (*challenge >> ((i << 1) + 1)) & 1 == 1 {
+                -base.to_curve()
+            } else {
+                base.to_curve()
+            };
+            acc = acc + s + acc;
+        }
+
+        let scalar: Fq = challenge.get_scalar();
+        assert_eq!(acc, p * scalar);
+    }
+}
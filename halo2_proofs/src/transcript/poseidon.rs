@@ -1,39 +1,121 @@
-use super::{Challenge255, EncodedChallenge, Transcript, TranscriptRead, TranscriptWrite};
+//! A Poseidon transcript, for proofs that are verified inside a circuit.
+//!
+//! The transcript is a Poseidon sponge over the scalar field of the curve, with
+//! width `T = RATE + 1`. Every message is absorbed as a sequence of scalars,
+//! starting with a prefix that separates the kinds of messages:
+//!
+//! - a scalar `s` is absorbed as `[2, s]`;
+//! - a point `(x, y)` is absorbed as `[1, x mod r, y mod r]`, where `r` is the
+//!   scalar field modulus;
+//! - a challenge is obtained by absorbing `[0]` and squeezing a scalar `c`. The
+//!   input of the [`EncodedChallenge`] is the little-endian representation of
+//!   `c`, zero-extended to 64 bytes, so that [`Challenge255`] yields exactly `c`.
+//!
+//! Points at infinity cannot be absorbed.
+//!
+//! ## Point coordinates
+//!
+//! Coordinates live in the base field and are reduced modulo `r` before being
+//! absorbed, which is also how an in-circuit verifier over the scalar field sees
+//! them. When the base field is smaller than the scalar field (as for Pallas) this
+//! is injective. When it is larger (as for Vesta, or for BN254, where in both cases
+//! `q - r < 2^127`) the coordinates `x` and `x + r` collide, but only coordinates in
+//! `[r, q)` are affected, which is a fraction of about `2^-126` of them.
+//!
+//! [`Challenge255`]: super::Challenge255
+
+use super::{
+    EncodedChallenge, Transcript, TranscriptRead, TranscriptReadBuffer, TranscriptWrite,
+    TranscriptWriterBuffer,
+};
 use crate::helpers::base_to_scalar;
-use ff::Field;
 use group::ff::PrimeField;
-use halo2curves::{Coordinates, CurveAffine, FieldExt};
-use num_bigint::BigUint;
+use halo2curves::{Coordinates, CurveAffine};
 use poseidon::Poseidon;
-use std::convert::TryInto;
 use std::io::{self, Read, Write};
 use std::marker::PhantomData;
 
-const POSEIDON_RATE: usize = 8usize;
-const POSEIDON_T: usize = POSEIDON_RATE + 1usize;
+/// Default rate of the sponge.
+const POSEIDON_RATE: usize = 8;
+
+/// Default width of the sponge.
+const POSEIDON_T: usize = POSEIDON_RATE + 1;
+
+/// Number of full rounds used by `init`.
+const POSEIDON_R_F: usize = 8;
+
+/// Number of partial rounds used by `init`, as recommended for a width of 9 over
+/// a 254-bit field.
+const POSEIDON_R_P: usize = 63;
+
+/// Prefix to a prover's message soliciting a challenge
+const PREFIX_CHALLENGE: u64 = 0;
 
-/// TODO
+/// Prefix to a prover's message containing a curve point
+const PREFIX_POINT: u64 = 1;
+
+/// Prefix to a prover's message containing a scalar
+const PREFIX_SCALAR: u64 = 2;
+
+/// Poseidon transcript reader, see the [module documentation](self).
+///
+/// `T` and `RATE` select the width and rate of the sponge. `init` is only available for
+/// the default width of 9 and rate of 8, with 8 full and 63 partial rounds; use
+/// [`PoseidonRead::with_rounds`] with any other width.
 #[derive(Debug, Clone)]
-pub struct PoseidonRead<R: Read, C: CurveAffine, E: EncodedChallenge<C>> {
-    state: Poseidon<C::ScalarExt, POSEIDON_T, POSEIDON_RATE>,
+pub struct PoseidonRead<
+    R: Read,
+    C: CurveAffine,
+    E: EncodedChallenge<C>,
+    const T: usize = POSEIDON_T,
+    const RATE: usize = POSEIDON_RATE,
+> {
+    state: Poseidon<C::ScalarExt, T, RATE>,
     reader: R,
     _marker: PhantomData<(C, E)>,
 }
 
-/// TODO
-impl<R: Read, C: CurveAffine, E: EncodedChallenge<C>> PoseidonRead<R, C, E> {
-    /// Initialize a transcript given an input buffer.
-    pub fn init(reader: R) -> Self {
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C>, const T: usize, const RATE: usize>
+    PoseidonRead<R, C, E, T, RATE>
+{
+    /// Initialize a transcript given an input buffer, with `r_f` full rounds and
+    /// `r_p` partial rounds.
+    pub fn with_rounds(reader: R, r_f: usize, r_p: usize) -> Self {
         PoseidonRead {
-            state: Poseidon::new(8usize, 63usize),
+            state: Poseidon::new(r_f, r_p),
             reader,
             _marker: PhantomData,
         }
     }
 }
 
-impl<R: Read, C: CurveAffine> TranscriptRead<C, Challenge255<C>>
-    for PoseidonRead<R, C, Challenge255<C>>
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>>
+    PoseidonRead<R, C, E, POSEIDON_T, POSEIDON_RATE>
+{
+    /// Initialize a transcript given an input buffer.
+    ///
+    /// This is [`TranscriptReadBuffer::init`], available without importing the trait.
+    pub fn init(reader: R) -> Self {
+        <Self as TranscriptReadBuffer<R, C, E>>::init(reader)
+    }
+}
+
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>>
+    TranscriptReadBuffer<R, C, E> for PoseidonRead<R, C, E, POSEIDON_T, POSEIDON_RATE>
+{
+    /// Initialize a transcript given an input buffer.
+    fn init(reader: R) -> Self {
+        Self::with_rounds(reader, POSEIDON_R_F, POSEIDON_R_P)
+    }
+}
+
+impl<
+        R: Read,
+        C: CurveAffine,
+        E: EncodedChallenge<C, Input = [u8; 64]>,
+        const T: usize,
+        const RATE: usize,
+    > TranscriptRead<C, E> for PoseidonRead<R, C, E, T, RATE>
 {
     fn read_point(&mut self) -> io::Result<C> {
         let mut compressed = C::Repr::default();
@@ -64,67 +146,102 @@ impl<R: Read, C: CurveAffine> TranscriptRead<C, Challenge255<C>>
     }
 }
 
-impl<R: Read, C: CurveAffine> Transcript<C, Challenge255<C>>
-    for PoseidonRead<R, C, Challenge255<C>>
+impl<
+        R: Read,
+        C: CurveAffine,
+        E: EncodedChallenge<C, Input = [u8; 64]>,
+        const T: usize,
+        const RATE: usize,
+    > Transcript<C, E> for PoseidonRead<R, C, E, T, RATE>
 {
-    fn squeeze_challenge(&mut self) -> Challenge255<C> {
-        //self.state.update(&[PREFIX_SQUEEZE]);
-        let scalar = self.state.squeeze();
-        let mut scalar_bytes = scalar.to_repr().as_ref().to_vec();
-        scalar_bytes.resize(64, 0u8);
-        Challenge255::<C>::new(&scalar_bytes.try_into().unwrap())
+    fn squeeze_challenge(&mut self) -> E {
+        squeeze::<C, E, T, RATE>(&mut self.state)
     }
 
     fn common_point(&mut self, point: C) -> io::Result<()> {
-        //self.state.update(&[PREFIX_POINT]);
-        let coords: Coordinates<C> = Option::from(point.coordinates()).ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                "cannot write points at infinity to the transcript",
-            )
-        })?;
-        let x = coords.x();
-        let y = coords.y();
-        self.state
-            .update(&[base_to_scalar::<C>(x), base_to_scalar::<C>(y)]);
-
-        Ok(())
+        absorb_point(&mut self.state, point)
     }
 
     fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
-        //self.state.update(&[BLAKE2B_PREFIX_SCALAR]);
-        self.state.update(&[scalar]);
+        absorb_scalar::<C, T, RATE>(&mut self.state, scalar);
 
         Ok(())
     }
 }
-/// TODO
+
+/// Poseidon transcript writer, see the [module documentation](self).
+///
+/// `T` and `RATE` select the width and rate of the sponge. `init` is only available for
+/// the default width of 9 and rate of 8, with 8 full and 63 partial rounds; use
+/// [`PoseidonWrite::with_rounds`] with any other width.
 #[derive(Debug, Clone)]
-pub struct PoseidonWrite<W: Write, C: CurveAffine, E: EncodedChallenge<C>> {
-    state: Poseidon<C::ScalarExt, POSEIDON_T, POSEIDON_RATE>,
+pub struct PoseidonWrite<
+    W: Write,
+    C: CurveAffine,
+    E: EncodedChallenge<C>,
+    const T: usize = POSEIDON_T,
+    const RATE: usize = POSEIDON_RATE,
+> {
+    state: Poseidon<C::ScalarExt, T, RATE>,
     writer: W,
     _marker: PhantomData<(C, E)>,
 }
 
-impl<W: Write, C: CurveAffine, E: EncodedChallenge<C>> PoseidonWrite<W, C, E> {
-    /// Initialize a transcript given an output buffer.
-    pub fn init(writer: W) -> Self {
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C>, const T: usize, const RATE: usize>
+    PoseidonWrite<W, C, E, T, RATE>
+{
+    /// Initialize a transcript given an output buffer, with `r_f` full rounds and
+    /// `r_p` partial rounds.
+    pub fn with_rounds(writer: W, r_f: usize, r_p: usize) -> Self {
         PoseidonWrite {
-            state: Poseidon::new(8usize, 63usize),
+            state: Poseidon::new(r_f, r_p),
             writer,
             _marker: PhantomData,
         }
     }
+}
+
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>>
+    PoseidonWrite<W, C, E, POSEIDON_T, POSEIDON_RATE>
+{
+    /// Initialize a transcript given an output buffer.
+    ///
+    /// This is [`TranscriptWriterBuffer::init`], available without importing the trait.
+    pub fn init(writer: W) -> Self {
+        <Self as TranscriptWriterBuffer<W, C, E>>::init(writer)
+    }
 
     /// Conclude the interaction and return the output buffer (writer).
+    ///
+    /// This is [`TranscriptWriterBuffer::finalize`], available without importing the
+    /// trait.
     pub fn finalize(self) -> W {
+        <Self as TranscriptWriterBuffer<W, C, E>>::finalize(self)
+    }
+}
+
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>>
+    TranscriptWriterBuffer<W, C, E> for PoseidonWrite<W, C, E, POSEIDON_T, POSEIDON_RATE>
+{
+    /// Initialize a transcript given an output buffer.
+    fn init(writer: W) -> Self {
+        Self::with_rounds(writer, POSEIDON_R_F, POSEIDON_R_P)
+    }
+
+    /// Conclude the interaction and return the output buffer (writer).
+    fn finalize(self) -> W {
         // TODO: handle outstanding scalars? see issue #138
         self.writer
     }
 }
 
-impl<W: Write, C: CurveAffine> TranscriptWrite<C, Challenge255<C>>
-    for PoseidonWrite<W, C, Challenge255<C>>
+impl<
+        W: Write,
+        C: CurveAffine,
+        E: EncodedChallenge<C, Input = [u8; 64]>,
+        const T: usize,
+        const RATE: usize,
+    > TranscriptWrite<C, E> for PoseidonWrite<W, C, E, T, RATE>
 {
     fn write_point(&mut self, point: C) -> io::Result<()> {
         self.common_point(point)?;
@@ -138,37 +255,170 @@ impl<W: Write, C: CurveAffine> TranscriptWrite<C, Challenge255<C>>
     }
 }
 
-impl<W: Write, C: CurveAffine> Transcript<C, Challenge255<C>>
-    for PoseidonWrite<W, C, Challenge255<C>>
+impl<
+        W: Write,
+        C: CurveAffine,
+        E: EncodedChallenge<C, Input = [u8; 64]>,
+        const T: usize,
+        const RATE: usize,
+    > Transcript<C, E> for PoseidonWrite<W, C, E, T, RATE>
 {
-    fn squeeze_challenge(&mut self) -> Challenge255<C> {
-        //self.state.update(&[PREFIX_SQUEEZE]);
-        let scalar = self.state.squeeze();
-        let mut scalar_bytes = scalar.to_repr().as_ref().to_vec();
-        scalar_bytes.resize(64, 0u8);
-        Challenge255::<C>::new(&scalar_bytes.try_into().unwrap())
+    fn squeeze_challenge(&mut self) -> E {
+        squeeze::<C, E, T, RATE>(&mut self.state)
     }
 
     fn common_point(&mut self, point: C) -> io::Result<()> {
-        //self.state.update(&[PREFIX_POINT]);
-        let coords: Coordinates<C> = Option::from(point.coordinates()).ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                "cannot write points at infinity to the transcript",
-            )
-        })?;
-        let x = coords.x();
-        let y = coords.y();
-        self.state
-            .update(&[base_to_scalar::<C>(x), base_to_scalar::<C>(y)]);
-
-        Ok(())
+        absorb_point(&mut self.state, point)
     }
 
     fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
-        //self.state.update(&[BLAKE2B_PREFIX_SCALAR]);
-        self.state.update(&[scalar]);
+        absorb_scalar::<C, T, RATE>(&mut self.state, scalar);
 
         Ok(())
     }
 }
+
+fn squeeze<
+    C: CurveAffine,
+    E: EncodedChallenge<C, Input = [u8; 64]>,
+    const T: usize,
+    const RATE: usize,
+>(
+    state: &mut Poseidon<C::ScalarExt, T, RATE>,
+) -> E {
+    state.update(&[C::Scalar::from(PREFIX_CHALLENGE)]);
+    let scalar = state.squeeze();
+
+    let mut input = [0u8; 64];
+    let repr = scalar.to_repr();
+    input[..repr.as_ref().len()].copy_from_slice(repr.as_ref());
+    E::new(&input)
+}
+
+fn absorb_point<C: CurveAffine, const T: usize, const RATE: usize>(
+    state: &mut Poseidon<C::ScalarExt, T, RATE>,
+    point: C,
+) -> io::Result<()> {
+    let coords: Coordinates<C> = Option::from(point.coordinates()).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            "cannot write points at infinity to the transcript",
+        )
+    })?;
+    state.update(&[
+        C::Scalar::from(PREFIX_POINT),
+        base_to_scalar::<C>(coords.x()),
+        base_to_scalar::<C>(coords.y()),
+    ]);
+
+    Ok(())
+}
+
+fn absorb_scalar<C: CurveAffine, const T: usize, const RATE: usize>(
+    state: &mut Poseidon<C::ScalarExt, T, RATE>,
+    scalar: C::Scalar,
+) {
+    state.update(&[C::Scalar::from(PREFIX_SCALAR), scalar]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::Challenge255;
+    use group::{prime::PrimeCurveAffine, Curve};
+    use halo2curves::bn256::{Fr, G1Affine};
+
+    /// Reproduces a short transcript directly on the Poseidon sponge, the way an
+    /// in-circuit verifier would.
+    #[test]
+    fn matches_sponge() {
+        let point = (G1Affine::generator() * Fr::from(3)).to_affine();
+        let scalar = Fr::from(5);
+
+        let mut transcript = PoseidonWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        transcript.write_point(point).unwrap();
+        let first = transcript.squeeze_challenge().get_scalar();
+        transcript.write_scalar(scalar).unwrap();
+        let second = transcript.squeeze_challenge().get_scalar();
+        let proof = transcript.finalize();
+
+        let mut sponge = Poseidon::<Fr, 9, 8>::new(8, 63);
+        let coords = point.coordinates().unwrap();
+        sponge.update(&[
+            Fr::from(1),
+            base_to_scalar::<G1Affine>(coords.x()),
+            base_to_scalar::<G1Affine>(coords.y()),
+            Fr::from(0),
+        ]);
+        assert_eq!(first, sponge.squeeze());
+        sponge.update(&[Fr::from(2), scalar, Fr::from(0)]);
+        assert_eq!(second, sponge.squeeze());
+
+        let mut transcript = PoseidonRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
+        assert_eq!(transcript.read_point().unwrap(), point);
+        assert_eq!(transcript.squeeze_challenge().get_scalar(), first);
+        assert_eq!(transcript.read_scalar().unwrap(), scalar);
+        assert_eq!(transcript.squeeze_challenge().get_scalar(), second);
+    }
+
+    /// Pins the challenges of the default width-9 transcript after absorbing the point
+    /// `3 * G` and squeezing, then absorbing the scalar `5` and squeezing. An in-circuit
+    /// verifier must reproduce these values.
+    #[test]
+    fn test_vectors() {
+        let mut transcript = PoseidonWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        transcript
+            .write_point((G1Affine::generator() * Fr::from(3)).to_affine())
+            .unwrap();
+        let first = transcript.squeeze_challenge().get_scalar();
+        transcript.write_scalar(Fr::from(5)).unwrap();
+        let second = transcript.squeeze_challenge().get_scalar();
+
+        let to_hex = |challenge: Fr| -> String {
+            challenge
+                .to_repr()
+                .as_ref()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect()
+        };
+        assert_eq!(
+            to_hex(first),
+            "b2f215194916bb20eb331b8c8f3e21ed67631aaebeb11c26e0e91d1c5dfde703"
+        );
+        assert_eq!(
+            to_hex(second),
+            "56e12ebd0fc9191697d7e623f5a753b5aa1095a934e1e32c67b667edb623ec18"
+        );
+    }
+
+    #[test]
+    fn separates_messages() {
+        // A scalar equal to a point's first coordinate must not produce the same
+        // challenge as the point.
+        let point = G1Affine::generator();
+        let x = base_to_scalar::<G1Affine>(point.coordinates().unwrap().x());
+
+        let mut a = PoseidonWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        a.write_point(point).unwrap();
+        let mut b = PoseidonWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        b.write_scalar(x).unwrap();
+
+        assert_ne!(
+            a.squeeze_challenge().get_scalar(),
+            b.squeeze_challenge().get_scalar()
+        );
+    }
+
+    #[test]
+    fn selectable_parameters() {
+        let mut transcript =
+            PoseidonWrite::<_, G1Affine, Challenge255<_>, 5, 4>::with_rounds(vec![], 8, 60);
+        transcript.write_scalar(Fr::from(7)).unwrap();
+        let challenge = transcript.squeeze_challenge().get_scalar();
+
+        let mut sponge = Poseidon::<Fr, 5, 4>::new(8, 60);
+        sponge.update(&[Fr::from(2), Fr::from(7), Fr::from(0)]);
+        assert_eq!(challenge, sponge.squeeze());
+    }
+}
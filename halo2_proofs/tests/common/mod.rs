@@ -0,0 +1,53 @@
+//! Helpers shared by the integration tests.
+
+use halo2_proofs::plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::ipa::commitment::{IPACommitmentScheme, ParamsIPA};
+use halo2_proofs::poly::ipa::multiopen::{ProverIPA, VerifierIPA};
+use halo2_proofs::poly::ipa::strategy::AccumulatorStrategy;
+use halo2_proofs::poly::VerificationStrategy;
+use halo2_proofs::transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptWriterBuffer};
+use halo2curves::pasta::{EqAffine, Fp};
+use rand_core::OsRng;
+
+/// Generates the keys of `circuit`, proves it with the given values of its instance
+/// columns, and returns whether the proof verifies.
+///
+/// A witness that does not satisfy the circuit is either refused by the prover (when it
+/// checks the witness under the `sanity-checks` feature) or rejected by the verifier, and
+/// in both cases this returns `false`.
+pub fn prove_and_verify<C: Circuit<Fp>>(
+    params: &ParamsIPA<EqAffine>,
+    circuit: C,
+    instances: &[&[Fp]],
+) -> bool {
+    let vk = keygen_vk(params, &circuit.without_witnesses()).unwrap();
+    let pk = keygen_pk(params, vk, &circuit.without_witnesses()).unwrap();
+
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    if create_proof::<IPACommitmentScheme<_>, ProverIPA<_>, _, _, _, _>(
+        params,
+        &pk,
+        &[circuit],
+        &[instances],
+        OsRng,
+        &mut transcript,
+    )
+    .is_err()
+    {
+        return false;
+    }
+    let proof = transcript.finalize();
+
+    let verifier_params = params.verifier_params();
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+    verify_proof::<IPACommitmentScheme<_>, VerifierIPA<_>, _, _, _>(
+        verifier_params,
+        pk.get_vk(),
+        AccumulatorStrategy::new(verifier_params),
+        &[instances],
+        &mut transcript,
+    )
+    .map(|strategy| strategy.finalize())
+    .unwrap_or(false)
+}
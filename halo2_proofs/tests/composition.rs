@@ -0,0 +1,309 @@
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::{
+    compute_instances, Layouter, PublicInputs, SimpleFloorPlanner, SubCircuit, Value,
+};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{
+    Advice, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed, FloorPlanner, Instance,
+    Selector,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::ipa::commitment::ParamsIPA;
+use halo2_proofs::poly::Rotation;
+use halo2curves::pasta::{EqAffine, Fp};
+
+mod common;
+use common::prove_and_verify;
+
+/// A floor planner for circuits that are only synthesized as sub-circuits, whose
+/// regions are laid out by the parent circuit's floor planner.
+struct SubCircuitOnly;
+
+impl FloorPlanner for SubCircuitOnly {
+    fn synthesize<F: Field, CS: Assignment<F>, C: Circuit<F>>(
+        _: &mut CS,
+        _: &C,
+        _: C::Config,
+        _: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        unreachable!("sub-circuits are laid out by the parent circuit's floor planner")
+    }
+}
+
+#[derive(Clone, Debug)]
+struct PairConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    s: Selector,
+    instance: Column<Instance>,
+}
+
+fn configure_pair(
+    meta: &mut ConstraintSystem<Fp>,
+    gate: impl Fn(Expression, Expression) -> Expression,
+) -> PairConfig {
+    let a = meta.advice_column();
+    let b = meta.advice_column();
+    let s = meta.selector();
+    let instance = meta.instance_column();
+    meta.enable_equality(a);
+    meta.enable_equality(b);
+    meta.enable_equality(instance);
+
+    meta.create_gate("pair", |meta| {
+        let a = meta.query_advice(a, Rotation::cur());
+        let b = meta.query_advice(b, Rotation::cur());
+        let s = meta.query_selector(s);
+        vec![s * gate(a, b)]
+    });
+
+    PairConfig { a, b, s, instance }
+}
+
+type Expression = halo2_proofs::plonk::Expression<Fp>;
+
+/// Exposes `x` and `x^2` in rows 0 and 1 of its instance column.
+///
+/// It is only synthesized as a sub-circuit, which does not use its floor planner.
+#[derive(Clone, Default)]
+struct SquareCircuit {
+    x: Value<Fp>,
+}
+
+impl Circuit<Fp> for SquareCircuit {
+    type Config = PairConfig;
+    type FloorPlanner = SubCircuitOnly;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        configure_pair(meta, |a, b| a.clone() * a - b)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let (x, square) = layouter.assign_region(
+            || "square",
+            |mut region| {
+                config.s.enable(&mut region, 0)?;
+                let x = region.assign_advice(|| "x", config.a, 0, || self.x)?;
+                let square =
+                    region.assign_advice(|| "x^2", config.b, 0, || self.x.map(|x| x.square()))?;
+                Ok((x.cell(), square.cell()))
+            },
+        )?;
+        layouter.constrain_instance(x, config.instance, 0)?;
+        layouter.constrain_instance(square, config.instance, 1)
+    }
+}
+
+/// Exposes `y` and `y + 1` in rows 0 and 1 of its instance column.
+#[derive(Clone, Default)]
+struct IncrementCircuit {
+    y: Value<Fp>,
+}
+
+impl Circuit<Fp> for IncrementCircuit {
+    type Config = PairConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        configure_pair(meta, |a, b| a + Expression::Constant(Fp::one()) - b)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let (y, increment) = layouter.namespace(|| "increment").assign_region(
+            || "increment",
+            |mut region| {
+                config.s.enable(&mut region, 0)?;
+                let y = region.assign_advice(|| "y", config.a, 0, || self.y)?;
+                let increment = region.assign_advice(
+                    || "y + 1",
+                    config.b,
+                    0,
+                    || self.y + Value::known(Fp::one()),
+                )?;
+                Ok((y.cell(), increment.cell()))
+            },
+        )?;
+        // Cells can also be exposed from within a namespace.
+        let mut layouter = layouter.namespace(|| "expose");
+        layouter.constrain_instance(y, config.instance, 0)?;
+        layouter.constrain_instance(increment, config.instance, 1)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ComposedConfig {
+    square: SubCircuit<Fp, SquareCircuit>,
+    increment: SubCircuit<Fp, IncrementCircuit>,
+    instance: Column<Instance>,
+}
+
+/// Proves knowledge of `x` such that `x^2 + 1` is its public input.
+#[derive(Clone, Default)]
+struct ComposedCircuit {
+    x: Value<Fp>,
+}
+
+impl Circuit<Fp> for ComposedCircuit {
+    type Config = ComposedConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        let square = SubCircuit::configure_with_shared_columns(meta);
+        let increment = SubCircuit::configure_with_shared_columns(meta);
+        ComposedConfig {
+            square,
+            increment,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let square =
+            config
+                .square
+                .synthesize(&SquareCircuit { x: self.x }, &mut layouter, || "square")?;
+        let increment = config.increment.synthesize(
+            &IncrementCircuit {
+                y: self.x.map(|x| x.square()),
+            },
+            &mut layouter,
+            || "increment",
+        )?;
+
+        // The square is the input of the increment, whose output is public.
+        increment.constrain_equal(&mut layouter, 0, 0, square.cell(0, 1).unwrap())?;
+        let mut public_inputs = PublicInputs::new(config.instance);
+        public_inputs.expose(&mut layouter, increment.cell(0, 1).unwrap(), || "x^2 + 1")?;
+        Ok(())
+    }
+}
+
+#[test]
+fn composed_circuit() {
+    const K: u32 = 4;
+
+    let circuit = ComposedCircuit {
+        x: Value::known(Fp::from(3)),
+    };
+
+    // The sub-circuits share the advice and instance columns.
+    let mut meta = ConstraintSystem::default();
+    ComposedCircuit::configure(&mut meta);
+    assert_eq!(meta.num_advice_columns(), 2);
+    assert_eq!(meta.num_instance_columns(), 1);
+
+    let instances = compute_instances(&circuit).unwrap();
+    assert_eq!(instances, vec![vec![Fp::from(10)]]);
+
+    let prover = MockProver::run(K, &circuit, instances.clone()).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = MockProver::run(K, &circuit, vec![vec![Fp::from(9)]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // The composed circuit is proven with a single proof.
+    let params = ParamsIPA::<EqAffine>::new(K);
+    let instance: Vec<&[Fp]> = instances.iter().map(|column| &column[..]).collect();
+    assert!(prove_and_verify(&params, circuit, &instance));
+}
+
+#[test]
+fn ungated_lookup_columns_are_not_shared() {
+    let mut meta = ConstraintSystem::<Fp>::default();
+    let range_checked = meta.advice_column();
+    let free = meta.advice_column();
+    let table = meta.lookup_table_column();
+    meta.lookup("range", |meta| {
+        vec![(meta.query_advice(range_checked, Rotation::cur()), table)]
+    });
+
+    // The square sub-circuit takes `free`, but allocates a new column rather than put
+    // its cells in the range-checked one.
+    let square = SubCircuit::<Fp, SquareCircuit>::configure_with_shared_columns(&mut meta);
+    assert_eq!(square.config().a, free);
+    assert_ne!(square.config().b, range_checked);
+    assert_eq!(meta.num_advice_columns(), 3);
+}
+
+#[test]
+fn ungated_gate_columns_are_not_shared() {
+    let mut meta = ConstraintSystem::<Fp>::default();
+    let boolean = meta.advice_column();
+    let free = meta.advice_column();
+    meta.create_gate("boolean", |meta| {
+        let boolean = meta.query_advice(boolean, Rotation::cur());
+        vec![boolean.clone() * (Expression::Constant(Fp::one()) - boolean)]
+    });
+
+    let square = SubCircuit::<Fp, SquareCircuit>::configure_with_shared_columns(&mut meta);
+    assert_eq!(square.config().a, free);
+    assert_ne!(square.config().b, boolean);
+    assert_eq!(meta.num_advice_columns(), 3);
+}
+
+/// Looks up every cell of its advice column in a table.
+#[derive(Clone, Default)]
+struct UngatedLookupCircuit;
+
+impl Circuit<Fp> for UngatedLookupCircuit {
+    type Config = ();
+    type FloorPlanner = SubCircuitOnly;
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let a = meta.advice_column();
+        let table = meta.lookup_table_column();
+        meta.lookup("ungated", |meta| {
+            vec![(meta.query_advice(a, Rotation::cur()), table)]
+        });
+    }
+
+    fn synthesize(&self, _: Self::Config, _: impl Layouter<Fp>) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[test]
+#[should_panic(expected = "without a selector or fixed column")]
+fn ungated_lookup_on_shared_column() {
+    let mut meta = ConstraintSystem::<Fp>::default();
+    meta.advice_column();
+    SubCircuit::<Fp, UngatedLookupCircuit>::configure_with_shared_columns(&mut meta);
+}
+
+#[test]
+fn ungated_lookup_on_own_column() {
+    // Without sharing, the sub-circuit's lookup only constrains its own column.
+    let mut meta = ConstraintSystem::<Fp>::default();
+    meta.advice_column();
+    SubCircuit::<Fp, UngatedLookupCircuit>::configure(&mut meta);
+    assert_eq!(meta.num_advice_columns(), 2);
+}
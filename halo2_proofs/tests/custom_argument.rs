@@ -0,0 +1,236 @@
+mod common;
+
+use common::prove_and_verify;
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::{CircuitCost, FailureLocation, MockProver, VerifyFailure};
+use halo2_proofs::plonk::{
+    Advice, ArgumentExpression, ArgumentInputs, Challenge, Circuit, Column, ConstraintSystem,
+    CustomArgument, Error, Expression, FirstPhase, SecondPhase,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::ipa::commitment::ParamsIPA;
+use halo2_proofs::poly::Rotation;
+use halo2curves::pasta::{EqAffine, Fp};
+
+const K: u32 = 4;
+
+/// Checks that the values of `a` are a permutation of the values of `b`, with a single
+/// running product column `z` where `z(\omega X) (b(X) + r) = z(X) (a(X) + r)`.
+///
+/// `r` is `gamma`, unless the argument is given a challenge to use instead.
+#[derive(Debug)]
+struct MultisetCheck {
+    a: Expression<Fp>,
+    b: Expression<Fp>,
+    challenge: Option<Challenge>,
+}
+
+impl CustomArgument<Fp> for MultisetCheck {
+    fn num_columns(&self) -> usize {
+        1
+    }
+
+    fn inputs(&self) -> Vec<Expression<Fp>> {
+        vec![self.a.clone(), self.b.clone()]
+    }
+
+    fn constraints(&self) -> Vec<ArgumentExpression<Fp>> {
+        let one = || ArgumentExpression::Constant(Fp::one());
+        let r = || match self.challenge {
+            Some(challenge) => ArgumentExpression::from(Expression::Challenge(challenge)),
+            None => ArgumentExpression::Gamma,
+        };
+        let z = ArgumentExpression::Column(0, Rotation::cur());
+        let z_next = ArgumentExpression::Column(0, Rotation::next());
+        let a = ArgumentExpression::from(self.a.clone()) + r();
+        let b = ArgumentExpression::from(self.b.clone()) + r();
+
+        vec![
+            ArgumentExpression::First * (one() - z.clone()),
+            ArgumentExpression::Last * (z.clone() - one()),
+            ArgumentExpression::Active * (z_next * b - z * a),
+        ]
+    }
+
+    fn compute_columns(&self, inputs: &ArgumentInputs<'_, Fp>) -> Result<Vec<Vec<Fp>>, Error> {
+        let r = match self.challenge {
+            Some(challenge) => inputs.challenge(challenge),
+            None => inputs.gamma(),
+        };
+        let (a, b) = (inputs.input(0), inputs.input(1));
+
+        let mut z = Vec::with_capacity(inputs.rows());
+        z.push(Fp::one());
+        for i in 0..inputs.rows() - 1 {
+            let denominator = Option::from((b[i] + r).invert()).ok_or(Error::Synthesis)?;
+            z.push(z[i] * (a[i] + r) * denominator);
+        }
+
+        Ok(vec![z])
+    }
+}
+
+#[derive(Clone, Debug)]
+struct MultisetConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+}
+
+/// Checks that `a` is a permutation of `b`. If `PHASED`, the columns of the argument
+/// are committed in the second phase, and use a challenge squeezed after the first.
+#[derive(Clone, Default)]
+struct MultisetCircuit<const PHASED: bool> {
+    a: Vec<Value<Fp>>,
+    b: Vec<Value<Fp>>,
+}
+
+impl<const PHASED: bool> MultisetCircuit<PHASED> {
+    fn new(a: [u64; 4], b: [u64; 4]) -> Self {
+        let values = |values: [u64; 4]| {
+            values
+                .iter()
+                .map(|value| Value::known(Fp::from(*value)))
+                .collect()
+        };
+        MultisetCircuit {
+            a: values(a),
+            b: values(b),
+        }
+    }
+}
+
+impl<const PHASED: bool> Circuit<Fp> for MultisetCircuit<PHASED> {
+    type Config = MultisetConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            a: vec![Value::unknown(); self.a.len()],
+            b: vec![Value::unknown(); self.b.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+
+        if PHASED {
+            // The argument is committed along with the advice columns of the second
+            // phase, so the circuit needs one.
+            meta.advice_column_in(SecondPhase);
+            let challenge = meta.challenge_usable_after(FirstPhase);
+            meta.custom_argument_in(SecondPhase, "multiset", |meta| MultisetCheck {
+                a: meta.query_advice(a, Rotation::cur()),
+                b: meta.query_advice(b, Rotation::cur()),
+                challenge: Some(challenge),
+            });
+        } else {
+            meta.custom_argument("multiset", |meta| MultisetCheck {
+                a: meta.query_advice(a, Rotation::cur()),
+                b: meta.query_advice(b, Rotation::cur()),
+                challenge: None,
+            });
+        }
+
+        MultisetConfig { a, b }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "values",
+            |mut region| {
+                for (offset, (a, b)) in self.a.iter().zip(self.b.iter()).enumerate() {
+                    region.assign_advice(|| "a", config.a, offset, || *a)?;
+                    region.assign_advice(|| "b", config.b, offset, || *b)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn multiset_check() {
+    // The constraints of the argument count towards the degree of the circuit.
+    let mut meta = ConstraintSystem::<Fp>::default();
+    MultisetCircuit::<false>::configure(&mut meta);
+    assert_eq!(meta.degree(), 3);
+
+    let params = ParamsIPA::<EqAffine>::new(K);
+
+    let permuted = MultisetCircuit::<false>::new([1, 2, 3, 4], [4, 1, 3, 2]);
+    assert_eq!(
+        MockProver::run(K, &permuted, vec![]).unwrap().verify(),
+        Ok(())
+    );
+    assert!(prove_and_verify(&params, permuted, &[]));
+
+    let not_permuted = MultisetCircuit::<false>::new([1, 2, 3, 4], [4, 1, 3, 3]);
+    // 16 rows, less 5 blinding factors, leave the last row of the argument at row 10.
+    assert_eq!(
+        MockProver::run(K, &not_permuted, vec![]).unwrap().verify(),
+        Err(vec![VerifyFailure::CustomArgument {
+            name: "multiset",
+            argument_index: 0,
+            constraint_index: 1,
+            location: FailureLocation::OutsideRegion { row: 10 },
+        }])
+    );
+    assert!(!prove_and_verify(&params, not_permuted, &[]));
+}
+
+#[test]
+fn phased_multiset_check() {
+    let params = ParamsIPA::<EqAffine>::new(K);
+
+    let permuted = MultisetCircuit::<true>::new([1, 2, 3, 4], [4, 1, 3, 2]);
+    assert_eq!(
+        MockProver::run(K, &permuted, vec![]).unwrap().verify(),
+        Ok(())
+    );
+    assert!(prove_and_verify(&params, permuted, &[]));
+
+    let not_permuted = MultisetCircuit::<true>::new([1, 2, 3, 4], [4, 1, 3, 3]);
+    assert!(MockProver::run(K, &not_permuted, vec![])
+        .unwrap()
+        .verify()
+        .is_err());
+    assert!(!prove_and_verify(&params, not_permuted, &[]));
+}
+
+#[test]
+fn proof_size_counts_argument_columns() {
+    let cost = CircuitCost::<halo2curves::pasta::Eq, _>::measure(
+        K as usize,
+        &MultisetCircuit::<false>::default(),
+    );
+
+    let argument = &cost.custom_arguments()[0];
+    assert_eq!((argument.columns, argument.evaluations), (1, 2));
+
+    // Two advice commitments and evaluations, and the argument's column commitment and
+    // its evaluations at the current and next rows.
+    assert_eq!(
+        usize::from(cost.marginal_proof_size()),
+        (2 + 2) * 32 + (1 + 2) * 32
+    );
+}
+
+#[test]
+#[should_panic(expected = "expression containing simple selector supplied to custom argument")]
+fn simple_selector_in_argument() {
+    let mut meta = ConstraintSystem::<Fp>::default();
+    let a = meta.advice_column();
+    let q = meta.selector();
+
+    meta.custom_argument("multiset", |meta| MultisetCheck {
+        a: meta.query_selector(q) * meta.query_advice(a, Rotation::cur()),
+        b: meta.query_advice(a, Rotation::cur()),
+        challenge: None,
+    });
+}
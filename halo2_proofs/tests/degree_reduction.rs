@@ -0,0 +1,102 @@
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    Advice, Challenge, Circuit, Column, ConstraintSystem, Error, FirstPhase, SecondPhase, Selector,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::ipa::commitment::ParamsIPA;
+use halo2_proofs::poly::Rotation;
+use halo2curves::pasta::{EqAffine, Fp};
+
+mod common;
+use common::prove_and_verify;
+
+#[derive(Clone, Debug)]
+struct PowerConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    s: Selector,
+    theta: Challenge,
+}
+
+/// Constrains `b = a^4 (a + theta)`, a gate of degree 6 that depends on a challenge,
+/// in a circuit whose gates are limited to degree 3. `tamper` is added to `b`.
+#[derive(Clone, Default)]
+struct PowerCircuit {
+    a: Value<Fp>,
+    tamper: u64,
+}
+
+impl Circuit<Fp> for PowerCircuit {
+    type Config = PowerConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let a = meta.advice_column_in(FirstPhase);
+        let theta = meta.challenge_usable_after(FirstPhase);
+        let b = meta.advice_column_in(SecondPhase);
+        let s = meta.selector();
+        meta.set_max_gate_degree(3);
+
+        meta.create_gate("power", |meta| {
+            let s = meta.query_selector(s);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let theta = meta.query_challenge(theta);
+            vec![s * (a.clone() * a.clone() * a.clone() * a.clone() * (a + theta) - b)]
+        });
+
+        PowerConfig { a, b, s, theta }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let theta = layouter.get_challenge(config.theta);
+        layouter.assign_region(
+            || "power",
+            |mut region| {
+                config.s.enable(&mut region, 0)?;
+                region.assign_advice(|| "a", config.a, 0, || self.a)?;
+                let b = self
+                    .a
+                    .zip(theta)
+                    .map(|(a, theta)| a.square().square() * (a + theta) + Fp::from(self.tamper));
+                region.assign_advice(|| "b", config.b, 0, || b)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn reduced_gate_round_trip() {
+    const K: u32 = 4;
+
+    // The gate is split into constraints of degree 3, using intermediate columns that
+    // the prover fills in, the last of them after `theta` is squeezed.
+    let mut meta = ConstraintSystem::<Fp>::default();
+    PowerCircuit::configure(&mut meta);
+    assert_eq!(meta.degree(), 3);
+    assert!(!meta.intermediate_columns().is_empty());
+
+    // The circuit has no instance columns.
+    let params = ParamsIPA::<EqAffine>::new(K);
+    let a = Value::known(Fp::from(3));
+    assert!(prove_and_verify(
+        &params,
+        PowerCircuit { a, tamper: 0 },
+        &[]
+    ));
+    assert!(!prove_and_verify(
+        &params,
+        PowerCircuit { a, tamper: 1 },
+        &[]
+    ));
+}
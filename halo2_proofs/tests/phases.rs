@@ -0,0 +1,105 @@
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    Advice, Challenge, Circuit, Column, ConstraintSystem, Error, NthPhase, Selector,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::ipa::commitment::ParamsIPA;
+use halo2_proofs::poly::Rotation;
+use halo2curves::pasta::{EqAffine, Fp};
+
+mod common;
+use common::prove_and_verify;
+
+#[derive(Clone, Debug)]
+struct RoundsConfig {
+    columns: [Column<Advice>; 4],
+    challenges: [Challenge; 3],
+    s: Selector,
+}
+
+/// Starts from `3` in the first phase and multiplies the value of each phase by the
+/// challenge squeezed after it, over four phases. `tamper` is added to the last round.
+#[derive(Clone, Default)]
+struct RoundsCircuit {
+    tamper: u64,
+}
+
+impl Circuit<Fp> for RoundsCircuit {
+    type Config = RoundsConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let a = meta.advice_column_in(NthPhase::<0>);
+        let alpha = meta.challenge_usable_after(NthPhase::<0>);
+        let b = meta.advice_column_in(NthPhase::<1>);
+        let beta = meta.challenge_usable_after(NthPhase::<1>);
+        let c = meta.advice_column_in(NthPhase::<2>);
+        let gamma = meta.challenge_usable_after(NthPhase::<2>);
+        let d = meta.advice_column_in(NthPhase::<3>);
+        let s = meta.selector();
+
+        meta.create_gate("rounds", |meta| {
+            let s = meta.query_selector(s);
+            let [a, b, c, d] =
+                [a, b, c, d].map(|column| meta.query_advice(column, Rotation::cur()));
+            let [alpha, beta, gamma] =
+                [alpha, beta, gamma].map(|challenge| meta.query_challenge(challenge));
+            vec![
+                s.clone() * (b.clone() - a * alpha),
+                s.clone() * (c.clone() - b * beta),
+                s * (d - c * gamma),
+            ]
+        });
+
+        RoundsConfig {
+            columns: [a, b, c, d],
+            challenges: [alpha, beta, gamma],
+            s,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let challenges = config
+            .challenges
+            .map(|challenge| layouter.get_challenge(challenge));
+
+        layouter.assign_region(
+            || "rounds",
+            |mut region| {
+                config.s.enable(&mut region, 0)?;
+                let mut value = Value::known(Fp::from(3));
+                region.assign_advice(|| "a", config.columns[0], 0, || value)?;
+                for (column, challenge) in config.columns[1..].iter().zip(challenges) {
+                    value = value * challenge;
+                    if *column == config.columns[3] {
+                        value = value + Value::known(Fp::from(self.tamper));
+                    }
+                    region.assign_advice(|| "round", *column, 0, || value)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn four_phases_round_trip() {
+    const K: u32 = 4;
+
+    let mut meta = ConstraintSystem::<Fp>::default();
+    RoundsCircuit::configure(&mut meta);
+    assert_eq!(meta.max_phase(), 3);
+
+    // The circuit has no instance columns.
+    let params = ParamsIPA::<EqAffine>::new(K);
+    assert!(prove_and_verify(&params, RoundsCircuit::default(), &[]));
+    assert!(!prove_and_verify(&params, RoundsCircuit { tamper: 1 }, &[]));
+}
@@ -0,0 +1,143 @@
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::{cost::KzgMultiopen, CircuitCost};
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, Advice, Circuit, Column, ConstraintSystem, Error, Instance,
+    ProvingKey, Selector, TableColumn,
+};
+use halo2_proofs::poly::commitment::{ParamsProver, Prover};
+use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+use halo2_proofs::poly::kzg::multiopen::{ProverGWC, ProverSHPLONK};
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer};
+use halo2curves::bn256::{Bn256, Fr, G1Affine, G1};
+use rand_core::OsRng;
+
+const K: u32 = 5;
+
+#[derive(Clone, Debug)]
+struct SquareConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    q: Selector,
+    table: TableColumn,
+    instance: Column<Instance>,
+}
+
+/// Range checks `x` to `[0, 8)` and exposes `x^2` in row 0 of its instance column.
+#[derive(Clone, Default)]
+struct SquareCircuit {
+    x: Value<Fr>,
+}
+
+impl Circuit<Fr> for SquareCircuit {
+    type Config = SquareConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let q = meta.complex_selector();
+        let table = meta.lookup_table_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(b);
+        meta.enable_equality(instance);
+
+        meta.create_gate("square", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let q = meta.query_selector(q);
+            vec![q * (a.clone() * a - b)]
+        });
+
+        meta.lookup("range", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let q = meta.query_selector(q);
+            vec![(q * a, table)]
+        });
+
+        SquareConfig {
+            a,
+            b,
+            q,
+            table,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        layouter.assign_table(
+            || "range",
+            |mut table| {
+                for value in 0..8u64 {
+                    table.assign_cell(
+                        || "range",
+                        config.table,
+                        value as usize,
+                        || Value::known(Fr::from(value)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+
+        let square = layouter.assign_region(
+            || "square",
+            |mut region| {
+                config.q.enable(&mut region, 0)?;
+                region.assign_advice(|| "x", config.a, 0, || self.x)?;
+                region.assign_advice(|| "x^2", config.b, 0, || self.x.map(|x| x.square()))
+            },
+        )?;
+        layouter.constrain_instance(square.cell(), config.instance, 0)
+    }
+}
+
+fn proof_len<'params, P: Prover<'params, KZGCommitmentScheme<Bn256>>>(
+    params: &'params ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+) -> usize {
+    let circuit = SquareCircuit {
+        x: Value::known(Fr::from(3)),
+    };
+    let instance = [Fr::from(9)];
+
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<KZGCommitmentScheme<_>, P, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[&[&instance[..]]],
+        OsRng,
+        &mut transcript,
+    )
+    .unwrap();
+    transcript.finalize().len()
+}
+
+#[test]
+fn kzg_proof_size_matches_proof() {
+    let params = ParamsKZG::<Bn256>::new(K);
+    let vk = keygen_vk(&params, &SquareCircuit::default()).unwrap();
+    let pk = keygen_pk(&params, vk, &SquareCircuit::default()).unwrap();
+
+    let cost = CircuitCost::<G1, _>::measure(K as usize, &SquareCircuit::default());
+
+    // SHPLONK does not open the instance column, so its evaluation is not in the proof.
+    assert_eq!(
+        usize::from(cost.kzg_proof_size(1, KzgMultiopen::Shplonk)),
+        proof_len::<ProverSHPLONK<_>>(&params, &pk)
+    );
+    assert_eq!(
+        usize::from(cost.kzg_proof_size(1, KzgMultiopen::Gwc)),
+        proof_len::<ProverGWC<_>>(&params, &pk)
+    );
+}
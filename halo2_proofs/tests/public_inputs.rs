@@ -0,0 +1,101 @@
+use halo2_proofs::circuit::{compute_instances, Layouter, PublicInputs, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::ipa::commitment::ParamsIPA;
+use halo2curves::pasta::{EqAffine, Fp};
+
+mod common;
+use common::prove_and_verify;
+
+const K: u32 = 4;
+
+/// Exposes `a` in row 0 of the instance column with [`Layouter::constrain_instance`],
+/// then `2a` and `3a` with a [`PublicInputs`] starting at `offset`.
+#[derive(Clone)]
+struct ExposeCircuit {
+    a: Value<Fp>,
+    offset: usize,
+}
+
+impl Circuit<Fp> for ExposeCircuit {
+    type Config = (Column<Advice>, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        ExposeCircuit {
+            a: Value::unknown(),
+            offset: self.offset,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+        (advice, instance)
+    }
+
+    fn synthesize(
+        &self,
+        (advice, instance): Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let cells = layouter.assign_region(
+            || "multiples",
+            |mut region| {
+                let mut cells = vec![];
+                for (row, multiple) in [1u64, 2, 3].into_iter().enumerate() {
+                    let value = self.a.map(|a| a * Fp::from(multiple));
+                    let cell = region.assign_advice(|| "multiple", advice, row, || value)?;
+                    cells.push(cell.cell());
+                }
+                Ok(cells)
+            },
+        )?;
+
+        layouter.constrain_instance(cells[0], instance, 0)?;
+        let mut public_inputs = PublicInputs::with_offset(instance, self.offset);
+        public_inputs.expose(&mut layouter, cells[1], || "2a")?;
+        public_inputs.expose(&mut layouter, cells[2], || "3a")?;
+        Ok(())
+    }
+}
+
+#[test]
+fn computed_instances_are_accepted() {
+    let circuit = ExposeCircuit {
+        a: Value::known(Fp::from(5)),
+        offset: 1,
+    };
+    let instances = compute_instances(&circuit).unwrap();
+    assert_eq!(
+        instances,
+        vec![vec![Fp::from(5), Fp::from(10), Fp::from(15)]]
+    );
+
+    let prover = MockProver::run(K, &circuit, instances.clone()).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let params = ParamsIPA::<EqAffine>::new(K);
+    let instances: Vec<_> = instances.iter().map(|column| &column[..]).collect();
+    assert!(prove_and_verify(&params, circuit.clone(), &instances));
+
+    // The proof does not verify against instances other than the computed ones.
+    let wrong = [Fp::from(5), Fp::from(10), Fp::from(16)];
+    assert!(!prove_and_verify(&params, circuit, &[&wrong[..]]));
+}
+
+#[test]
+fn conflicting_rows() {
+    // The public inputs start at the row that `a` is already constrained to.
+    let circuit = ExposeCircuit {
+        a: Value::known(Fp::from(5)),
+        offset: 0,
+    };
+    assert!(matches!(
+        compute_instances(&circuit),
+        Err(Error::InstanceRowConflict { row: 0, .. })
+    ));
+}
@@ -0,0 +1,220 @@
+#![cfg(feature = "sanity-checks")]
+
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, Advice, Any, Circuit, Column, ConstraintSystem, Error,
+    Selector, TableColumn, UnsatisfiedConstraint,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::ipa::commitment::{IPACommitmentScheme, ParamsIPA};
+use halo2_proofs::poly::ipa::multiopen::ProverIPA;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer};
+use halo2curves::pasta::{EqAffine, Fp};
+use rand_core::OsRng;
+
+#[derive(Clone, Debug)]
+struct TestConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    c: Column<Advice>,
+    d: Column<Advice>,
+    s: Selector,
+    range: Selector,
+    table: TableColumn,
+}
+
+/// Assigns `a`, `b = a^2` and `c = 2a` at row 1, along with a copy `d` of `a`. The
+/// fields override the witness to break one of the constraints.
+#[derive(Clone, Default)]
+struct TestCircuit {
+    a: u64,
+    b: Option<u64>,
+    c: Option<u64>,
+    d: Option<u64>,
+}
+
+impl Circuit<Fp> for TestCircuit {
+    type Config = TestConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let c = meta.advice_column();
+        let d = meta.advice_column();
+        let s = meta.selector();
+        let range = meta.complex_selector();
+        let table = meta.lookup_table_column();
+        meta.enable_equality(a);
+        meta.enable_equality(d);
+
+        meta.create_gate("square and double", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            let s = meta.query_selector(s);
+            vec![
+                ("square", s.clone() * (a.clone() * a.clone() - b)),
+                ("double", s * (a.clone() + a - c)),
+            ]
+        });
+
+        meta.lookup("range", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let range = meta.query_selector(range);
+            vec![(range * a, table)]
+        });
+
+        TestConfig {
+            a,
+            b,
+            c,
+            d,
+            s,
+            range,
+            table,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        layouter.assign_table(
+            || "range",
+            |mut table| {
+                for value in 0..8u64 {
+                    table.assign_cell(
+                        || "range",
+                        config.table,
+                        value as usize,
+                        || Value::known(Fp::from(value)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+
+        layouter.assign_region(
+            || "witness",
+            |mut region| {
+                // Row 0 is left empty, so that the failing row is not the first one.
+                config.s.enable(&mut region, 1)?;
+                config.range.enable(&mut region, 1)?;
+
+                let a = self.a;
+                let b = self.b.unwrap_or(a * a);
+                let c = self.c.unwrap_or(2 * a);
+                let d = self.d.unwrap_or(a);
+                let assign = |value| Value::known(Fp::from(value));
+
+                let a = region.assign_advice(|| "a", config.a, 1, || assign(a))?;
+                region.assign_advice(|| "b", config.b, 1, || assign(b))?;
+                region.assign_advice(|| "c", config.c, 1, || assign(c))?;
+                let d = region.assign_advice(|| "d", config.d, 1, || assign(d))?;
+                region.constrain_equal(a.cell(), d.cell())
+            },
+        )
+    }
+}
+
+fn prove(circuit: TestCircuit) -> Result<(), Error> {
+    const K: u32 = 4;
+
+    let params = ParamsIPA::<EqAffine>::new(K);
+    let vk = keygen_vk(&params, &TestCircuit::default()).unwrap();
+    let pk = keygen_pk(&params, vk, &TestCircuit::default()).unwrap();
+
+    // The circuit has no instance columns.
+    let instances: &[&[&[Fp]]] = &[&[]];
+
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<IPACommitmentScheme<_>, ProverIPA<_>, _, _, _, _>(
+        &params,
+        &pk,
+        &[circuit],
+        instances,
+        OsRng,
+        &mut transcript,
+    )
+}
+
+fn unsatisfied(circuit: TestCircuit) -> UnsatisfiedConstraint {
+    match prove(circuit) {
+        Err(Error::ConstraintNotSatisfied {
+            circuit: 0,
+            constraint,
+        }) => constraint,
+        result => panic!("expected an unsatisfied constraint, got {:?}", result),
+    }
+}
+
+#[test]
+fn valid_witness() {
+    assert!(prove(TestCircuit {
+        a: 3,
+        ..Default::default()
+    })
+    .is_ok());
+}
+
+#[test]
+fn gate_not_satisfied() {
+    assert_eq!(
+        unsatisfied(TestCircuit {
+            a: 3,
+            c: Some(7),
+            ..Default::default()
+        }),
+        UnsatisfiedConstraint::Gate {
+            gate_index: 0,
+            gate_name: "square and double",
+            constraint_index: 1,
+            constraint_name: "double",
+            row: 1,
+        }
+    );
+}
+
+#[test]
+fn lookup_not_satisfied() {
+    // 9 is not in the range table, but satisfies the gate.
+    assert_eq!(
+        unsatisfied(TestCircuit {
+            a: 9,
+            ..Default::default()
+        }),
+        UnsatisfiedConstraint::Lookup {
+            lookup_index: 0,
+            name: "range",
+            row: 1,
+        }
+    );
+}
+
+#[test]
+fn copy_not_satisfied() {
+    let mut meta = ConstraintSystem::<Fp>::default();
+    let config = TestCircuit::configure(&mut meta);
+    let column = |column: Column<Advice>| -> Column<Any> { column.into() };
+
+    assert_eq!(
+        unsatisfied(TestCircuit {
+            a: 3,
+            d: Some(4),
+            ..Default::default()
+        }),
+        UnsatisfiedConstraint::Permutation {
+            column: column(config.a),
+            row: 1,
+            target_column: column(config.d),
+            target_row: 1,
+        }
+    );
+}
@@ -0,0 +1,201 @@
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{
+    Advice, Circuit, Column, ConstraintSystem, Error, Selector, TableColumn, TaggedTable,
+    TaggedTableError,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::ipa::commitment::ParamsIPA;
+use halo2_proofs::poly::Rotation;
+use halo2curves::pasta::{EqAffine, Fp};
+
+mod common;
+use common::prove_and_verify;
+
+#[derive(Clone, Debug)]
+struct TaggedConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    q_bit: Selector,
+    q_square: Selector,
+    bits: TaggedTable,
+    squares: TaggedTable,
+    value: TableColumn,
+    square: TableColumn,
+}
+
+/// Looks up `bit` in a table of bits, and `(root, square)` in a table of squares, where
+/// both tables share the `value` column.
+#[derive(Clone, Default)]
+struct TaggedCircuit {
+    bit: u64,
+    root: u64,
+    square: u64,
+    /// Assigns the first bit of the bits table twice.
+    duplicate: bool,
+}
+
+impl Circuit<Fp> for TaggedCircuit {
+    type Config = TaggedConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            duplicate: self.duplicate,
+            ..Self::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let q_bit = meta.complex_selector();
+        let q_square = meta.complex_selector();
+        let tag = meta.lookup_table_column();
+        let value = meta.lookup_table_column();
+        let square = meta.lookup_table_column();
+        let bits = meta.tagged_lookup_table(tag);
+        let squares = meta.tagged_lookup_table(tag);
+
+        meta.lookup_tagged("bit", bits, |cells| {
+            let a = cells.query_advice(a, Rotation::cur());
+            let q = cells.query_selector(q_bit);
+            (q.clone(), vec![(q * a, value)])
+        });
+        meta.lookup_tagged("square", squares, |cells| {
+            let a = cells.query_advice(a, Rotation::cur());
+            let b = cells.query_advice(b, Rotation::cur());
+            let q = cells.query_selector(q_square);
+            (q.clone(), vec![(q.clone() * a, value), (q * b, square)])
+        });
+
+        TaggedConfig {
+            a,
+            b,
+            q_bit,
+            q_square,
+            bits,
+            squares,
+            value,
+            square,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        layouter.assign_tagged_table(
+            || "bits",
+            config.bits,
+            |mut table| {
+                for i in 0..2 {
+                    table.assign_cell(
+                        || "bit",
+                        config.value,
+                        i,
+                        || Value::known(Fp::from(i as u64)),
+                    )?;
+                }
+                if self.duplicate {
+                    table.assign_cell(|| "bit", config.value, 0, || Value::known(Fp::zero()))?;
+                }
+                Ok(())
+            },
+        )?;
+        layouter.assign_tagged_table(
+            || "squares",
+            config.squares,
+            |mut table| {
+                for i in 0..4 {
+                    table.assign_cell(
+                        || "value",
+                        config.value,
+                        i,
+                        || Value::known(Fp::from(i as u64)),
+                    )?;
+                    table.assign_cell(
+                        || "square",
+                        config.square,
+                        i,
+                        || Value::known(Fp::from((i * i) as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+
+        layouter.assign_region(
+            || "inputs",
+            |mut region| {
+                config.q_bit.enable(&mut region, 0)?;
+                region.assign_advice(|| "bit", config.a, 0, || Value::known(Fp::from(self.bit)))?;
+                config.q_square.enable(&mut region, 1)?;
+                region.assign_advice(
+                    || "root",
+                    config.a,
+                    1,
+                    || Value::known(Fp::from(self.root)),
+                )?;
+                region.assign_advice(
+                    || "square",
+                    config.b,
+                    1,
+                    || Value::known(Fp::from(self.square)),
+                )?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn tagged_tables_round_trip() {
+    const K: u32 = 5;
+
+    // The circuit has no instance columns.
+    let params = ParamsIPA::<EqAffine>::new(K);
+    let circuit = TaggedCircuit {
+        bit: 1,
+        root: 3,
+        square: 9,
+        duplicate: false,
+    };
+    assert!(prove_and_verify(&params, circuit.clone(), &[]));
+
+    // 2 is in the shared value column, but only in the rows tagged as squares.
+    assert!(!prove_and_verify(
+        &params,
+        TaggedCircuit { bit: 2, ..circuit },
+        &[]
+    ));
+    // (3, 4) is not a row of the squares table, although both values are in the table
+    // columns.
+    assert!(!prove_and_verify(
+        &params,
+        TaggedCircuit {
+            square: 4,
+            ..circuit
+        },
+        &[]
+    ));
+}
+
+#[test]
+fn tagged_table_cell_assigned_twice() {
+    const K: u32 = 5;
+
+    let circuit = TaggedCircuit {
+        bit: 1,
+        root: 3,
+        square: 9,
+        duplicate: true,
+    };
+    assert!(matches!(
+        MockProver::run(K, &circuit, vec![]),
+        Err(Error::InvalidTaggedTable(
+            TaggedTableError::CellAssignedTwice { offset: 0, .. }
+        ))
+    ));
+}
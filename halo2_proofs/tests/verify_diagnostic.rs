@@ -0,0 +1,234 @@
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::{
+    create_proof, keygen_pk, keygen_vk, verify_proof, verify_proof_diagnostic, Advice, Circuit,
+    Column, ConstraintSystem, Error, Identity, Instance, ProvingKey, Selector, VerificationFailure,
+    VerificationReport, VerificationStage,
+};
+use halo2_proofs::poly::commitment::ParamsProver;
+use halo2_proofs::poly::ipa::commitment::{IPACommitmentScheme, ParamsIPA};
+use halo2_proofs::poly::ipa::multiopen::{ProverIPA, VerifierIPA};
+use halo2_proofs::poly::ipa::strategy::SingleStrategy;
+use halo2_proofs::poly::{Rotation, VerificationStrategy};
+use halo2_proofs::transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptWriterBuffer};
+use halo2curves::pasta::{EqAffine, Fp};
+use rand_core::OsRng;
+
+const K: u32 = 4;
+
+#[derive(Clone, Debug)]
+struct SquareConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    s: Selector,
+    instance: Column<Instance>,
+}
+
+/// Exposes `x^2` in row 0 of its instance column.
+#[derive(Clone, Default)]
+struct SquareCircuit {
+    x: Value<Fp>,
+}
+
+impl Circuit<Fp> for SquareCircuit {
+    type Config = SquareConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let a = meta.advice_column();
+        let b = meta.advice_column();
+        let s = meta.selector();
+        let instance = meta.instance_column();
+        meta.enable_equality(b);
+        meta.enable_equality(instance);
+
+        meta.create_gate("square", |meta| {
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let s = meta.query_selector(s);
+            vec![s * (a.clone() * a - b)]
+        });
+
+        SquareConfig { a, b, s, instance }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let square = layouter.assign_region(
+            || "square",
+            |mut region| {
+                config.s.enable(&mut region, 0)?;
+                region.assign_advice(|| "x", config.a, 0, || self.x)?;
+                region.assign_advice(|| "x^2", config.b, 0, || self.x.map(|x| x.square()))
+            },
+        )?;
+        layouter.constrain_instance(square.cell(), config.instance, 0)
+    }
+}
+
+fn setup() -> (ParamsIPA<EqAffine>, ProvingKey<EqAffine>) {
+    let params = ParamsIPA::<EqAffine>::new(K);
+    let vk = keygen_vk(&params, &SquareCircuit::default()).unwrap();
+    let pk = keygen_pk(&params, vk, &SquareCircuit::default()).unwrap();
+    (params, pk)
+}
+
+fn prove(params: &ParamsIPA<EqAffine>, pk: &ProvingKey<EqAffine>, x: u64, square: u64) -> Vec<u8> {
+    let circuit = SquareCircuit {
+        x: Value::known(Fp::from(x)),
+    };
+    let instance = [Fp::from(square)];
+
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<IPACommitmentScheme<_>, ProverIPA<_>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[&[&instance[..]]],
+        OsRng,
+        &mut transcript,
+    )
+    .unwrap();
+    transcript.finalize()
+}
+
+/// Verifies `proof` with both [`verify_proof`] and [`verify_proof_diagnostic`], checks
+/// that they agree, and returns whether the proof was accepted along with the report.
+fn verify(
+    params: &ParamsIPA<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    proof: &[u8],
+    square: u64,
+) -> (bool, VerificationReport<Fp>) {
+    let verifier_params = params.verifier_params();
+    let instance = [Fp::from(square)];
+
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+    let accepted = verify_proof::<IPACommitmentScheme<_>, VerifierIPA<_>, _, _, _>(
+        verifier_params,
+        pk.get_vk(),
+        SingleStrategy::new(verifier_params),
+        &[&[&instance[..]]],
+        &mut transcript,
+    )
+    .is_ok();
+
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+    let (res, report) = verify_proof_diagnostic::<IPACommitmentScheme<_>, VerifierIPA<_>, _, _, _>(
+        verifier_params,
+        pk.get_vk(),
+        SingleStrategy::new(verifier_params),
+        &[&[&instance[..]]],
+        &mut transcript,
+    );
+    assert_eq!(res.is_ok(), accepted);
+    assert_eq!(report.is_valid(), accepted);
+
+    (accepted, report)
+}
+
+#[test]
+fn diagnostic_agrees_with_verify_proof() {
+    let (params, pk) = setup();
+    let proof = prove(&params, &pk, 3, 9);
+
+    let (accepted, report) = verify(&params, &pk, &proof, 9);
+    assert!(accepted);
+    assert_eq!(report.failure, None);
+
+    // The proof is for a different public input.
+    let (accepted, report) = verify(&params, &pk, &proof, 10);
+    assert!(!accepted);
+    assert_eq!(report.failure, Some(VerificationFailure::Rejected));
+
+    // The witness does not satisfy the gate. The prover refuses to create such a proof
+    // when it checks the witness.
+    #[cfg(not(feature = "sanity-checks"))]
+    {
+        let proof = prove(&params, &pk, 3, 10);
+        let (accepted, report) = verify(&params, &pk, &proof, 10);
+        assert!(!accepted);
+        assert_eq!(report.failure, Some(VerificationFailure::Rejected));
+    }
+
+    // The proof has been tampered with.
+    let mut proof = prove(&params, &pk, 3, 9);
+    let last = proof.len() - 1;
+    proof[last] ^= 1;
+    let (accepted, report) = verify(&params, &pk, &proof, 9);
+    assert!(!accepted);
+    assert!(report.failure.is_some());
+}
+
+#[test]
+fn diagnostic_reports_truncation_stage() {
+    let (params, pk) = setup();
+    let proof = prove(&params, &pk, 3, 9);
+
+    let (accepted, report) = verify(&params, &pk, &[], 9);
+    assert!(!accepted);
+    assert_eq!(
+        report.failure,
+        Some(VerificationFailure::Transcript(
+            VerificationStage::AdviceCommitments
+        ))
+    );
+    assert_eq!(report.x, None);
+
+    // The proof starts with the two advice commitments, followed by the permutation
+    // product commitment as the circuit has no lookups.
+    let (accepted, report) = verify(&params, &pk, &proof[..64], 9);
+    assert!(!accepted);
+    assert_eq!(
+        report.failure,
+        Some(VerificationFailure::Transcript(
+            VerificationStage::PermutationCommitments
+        ))
+    );
+    assert!(report.terms.is_empty());
+    assert_eq!(report.expected_h_eval, None);
+}
+
+#[test]
+fn diagnostic_terms_sum_to_expected_h_eval() {
+    let (params, pk) = setup();
+    let proof = prove(&params, &pk, 3, 9);
+
+    let (accepted, report) = verify(&params, &pk, &proof, 9);
+    assert!(accepted);
+
+    let x = report.x.unwrap();
+    let y = report.y.unwrap();
+    let expected_h_eval = report.expected_h_eval.unwrap();
+    assert!(matches!(
+        report.terms[0].identity,
+        Identity::Gate {
+            gate_index: 0,
+            gate_name: "square",
+            constraint_index: 0,
+            ..
+        }
+    ));
+    assert!(report.terms.iter().all(|term| term.proof_index == 0));
+
+    let sum = report
+        .terms
+        .iter()
+        .fold(Fp::zero(), |acc, term| acc + term.contribution);
+    assert_eq!(sum, expected_h_eval);
+
+    // Fold the values like the verifier does, independently of the contributions.
+    let xn = x.pow_vartime(&[1u64 << K]);
+    let folded = report
+        .terms
+        .iter()
+        .fold(Fp::zero(), |acc, term| acc * y + term.value);
+    assert_eq!(folded * (xn - Fp::one()).invert().unwrap(), expected_h_eval);
+}